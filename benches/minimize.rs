@@ -0,0 +1,210 @@
+//Compares DFA::minimize against the partition refinement it replaced.
+//Run with `cargo bench --bench minimize`.
+use std::time::{Duration, Instant};
+
+use srs_to_dfa::builder::*;
+use srs_to_dfa::solver::*;
+use srs_to_dfa::util::{SymbolSet, DFA};
+
+//DFA::minimize as it was before Hopcroft's algorithm, copied verbatim so there's something to
+//compare against
+trait OldMinimize {
+    fn old_minimize(&mut self);
+}
+
+#[allow(
+    clippy::needless_range_loop,
+    clippy::needless_borrow,
+    clippy::clone_on_copy,
+    clippy::unused_enumerate_index
+)]
+impl OldMinimize for DFA {
+    fn old_minimize(&mut self) {
+        let mut new_partition_membership = vec![0; self.state_transitions.len()];
+        let mut old_partition_membership = vec![0; self.state_transitions.len()];
+        let mut new_partitions: Vec<Vec<usize>> = vec![vec![], vec![]];
+        let mut old_partitions = vec![];
+
+        let mut old_outputs = Vec::new();
+        for i in 0..self.state_transitions.len() {
+            let idx = match (&mut old_outputs.clone().into_iter())
+                .position(|x| x == self.accepting_states[i])
+            {
+                Some(idx) => idx,
+                None => {
+                    old_outputs.push(self.accepting_states[i].clone());
+                    old_outputs.len() - 1
+                }
+            };
+            new_partition_membership[i] = idx;
+            new_partitions[idx].push(i)
+        }
+
+        while new_partitions.len() > old_partitions.len() {
+            std::mem::swap(&mut old_partitions, &mut new_partitions);
+            std::mem::swap(&mut old_partition_membership, &mut new_partition_membership);
+            new_partitions.clear();
+            for partition in &old_partitions {
+                if partition.len() == 1 {
+                    new_partition_membership[partition[0]] = new_partitions.len();
+                    new_partitions.push(partition.clone());
+                    continue;
+                }
+                let mut truth_vals: Vec<Vec<usize>> = vec![];
+                let mut split_partitions: Vec<Vec<usize>> = vec![];
+                for state in partition {
+                    let mut truth_val = vec![0; self.symbol_set.length];
+                    for symbol in 0..self.symbol_set.length {
+                        truth_val[symbol] =
+                            old_partition_membership[self.state_transitions[*state][symbol]];
+                    }
+                    let mut match_found = false;
+                    for tv_idx in 0..truth_vals.len() {
+                        if truth_val == truth_vals[tv_idx] {
+                            split_partitions[tv_idx].push(*state);
+                            new_partition_membership[*state] = new_partitions.len() + tv_idx;
+                            match_found = true;
+                            break;
+                        }
+                    }
+                    if !match_found {
+                        new_partition_membership[*state] = new_partitions.len() + truth_vals.len();
+                        truth_vals.push(truth_val);
+                        split_partitions.push(vec![*state]);
+                    }
+                }
+                new_partitions.append(&mut split_partitions);
+            }
+        }
+        let mut new_accepting = Vec::new();
+        for (_p_index, partition) in new_partitions.iter().enumerate() {
+            new_accepting.push(self.accepting_states[partition[0]].clone());
+        }
+
+        self.starting_state = new_partition_membership[self.starting_state];
+        let mut new_transition_table = vec![];
+
+        for partition in new_partitions {
+            let mut new_transitions = vec![0; self.symbol_set.length];
+            for symbol in 0..self.symbol_set.length {
+                new_transitions[symbol] =
+                    new_partition_membership[self.state_transitions[partition[0]][symbol]];
+            }
+
+            new_transition_table.push(new_transitions);
+        }
+        self.state_transitions = new_transition_table;
+        self.accepting_states = new_accepting;
+    }
+}
+
+//Deterministic so runs can be compared with each other
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self, bound: usize) -> usize {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((self.0 >> 33) as usize) % bound
+    }
+}
+
+fn random_dfa(states: usize, symbols: usize, seed: u64) -> DFA {
+    let mut rng = Lcg(seed);
+    DFA {
+        starting_state: 0,
+        state_transitions: (0..states)
+            .map(|_| (0..symbols).map(|_| rng.next(states)).collect())
+            .collect(),
+        accepting_states: (0..states).map(|_| rng.next(2) == 0).collect(),
+        symbol_set: SymbolSet {
+            length: symbols,
+            representations: (0..symbols).map(|symbol| symbol.to_string()).collect(),
+        },
+    }
+}
+
+//Counts symbols mod `states`, accepting only at the end. Every round of the old version
+//only splits off one more state, which makes it quadratic.
+fn counter_dfa(states: usize) -> DFA {
+    let mut dfa = random_dfa(states, 2, 0);
+    for state in 0..states {
+        dfa.state_transitions[state] = vec![(state + 1) % states, (state + 1) % states];
+        dfa.accepting_states[state] = state == states - 1;
+    }
+    dfa
+}
+
+//Glues together `copies` disconnected copies of a DFA, all equivalent to the original
+fn duplicate(dfa: &DFA, copies: usize) -> DFA {
+    let mut result = dfa.clone();
+    result.state_transitions.clear();
+    result.accepting_states.clear();
+    let state_count = dfa.state_transitions.len();
+    for copy in 0..copies {
+        for transitions in &dfa.state_transitions {
+            result.state_transitions.push(
+                transitions
+                    .iter()
+                    .map(|state| state + copy * state_count)
+                    .collect(),
+            );
+        }
+        result
+            .accepting_states
+            .extend(dfa.accepting_states.iter().cloned());
+    }
+    result
+}
+
+fn time(dfa: &DFA, minimize: fn(&mut DFA)) -> (Duration, DFA) {
+    let mut best = Duration::MAX;
+    let mut result = dfa.clone();
+    for _ in 0..3 {
+        let mut candidate = dfa.clone();
+        let start = Instant::now();
+        minimize(&mut candidate);
+        best = best.min(start.elapsed());
+        result = candidate;
+    }
+    (best, result)
+}
+
+fn compare(name: &str, dfa: DFA) {
+    let (hopcroft_time, hopcroft) = time(&dfa, DFA::minimize);
+    let (old_time, old) = time(&dfa, DFA::old_minimize);
+    assert!(hopcroft.state_transitions.len() == old.state_transitions.len());
+    assert!(hopcroft == old);
+    println!(
+        "{:<28} {:>7} -> {:>6} states   hopcroft {:>10.3?}   old {:>10.3?}",
+        name,
+        dfa.state_transitions.len(),
+        hopcroft.state_transitions.len(),
+        hopcroft_time,
+        old_time
+    );
+}
+
+fn main() {
+    for (states, symbols) in [(1000, 2), (10000, 2), (10000, 5), (50000, 3)] {
+        compare(
+            &format!("random {}x{}", states, symbols),
+            random_dfa(states, symbols, states as u64),
+        );
+    }
+    for states in [1000, 5000] {
+        compare(&format!("counter {}", states), counter_dfa(states));
+    }
+
+    let flip = build_flip::<MinkidSolver>().unwrap().run(4, vec![]);
+    for copies in [10, 100, 500] {
+        compare(
+            &format!("flip result x{}", copies),
+            duplicate(&flip, copies),
+        );
+    }
+    let onedpeg = build_1dpeg_result();
+    compare("1dpeg result x5000", duplicate(&onedpeg, 5000));
+}
@@ -2,7 +2,7 @@ use std::{sync::mpsc::{Sender,Receiver}, thread::JoinHandle, time::Duration};
 
 use egui::{Ui, Color32, RichText};
 
-use srs_to_dfa::{solver::{DFAStructure,MinkidSolver, SSStructure, Solver, SRSSolver, SubsetSolver, BFSSolver, HashSolver}, util::{DFA, Ruleset, SymbolIdx, SymbolSet}};
+use srs_to_dfa::{solver::{CancelToken,Cancelled,DFAStructure,MinkidSolver, SSStructure, Solver, SolverEvent, SRSSolver, SubsetSolver, BFSSolver, HashSolver}, util::{DFA, Ruleset, SymbolIdx, SymbolSet}};
 
 use super::{Instant,execute};
 
@@ -12,13 +12,13 @@ use super::{AvailableSolver, PrepPanel, Error};
 
 
 pub struct DFAConstructor {
-    dfa_receiver : Option<Receiver<(DFAStructure,SSStructure)>>,
-    phase_receiver : Option<Receiver<Duration>>,
+    event_receiver : Option<Receiver<SolverEvent>>,
     pub dfa_content : Option<(DFAStructure,SSStructure)>,
     pub last_solver : Option<SolverContents>,
     pub final_dfa : Option<DFA>,
     pub iteration_state_lens : Vec<usize>,
-    handle : Option<JoinHandle<DFA>>,
+    handle : Option<JoinHandle<Result<DFA,Cancelled>>>,
+    cancel_token : CancelToken,
     solve_string : String,
     last_solve_string : Option<Vec<SymbolIdx>>,
     solve_path : Option<Result<Vec<(usize,usize,usize,Vec<SymbolIdx>)>,()>>,
@@ -44,11 +44,11 @@ impl DFAConstructor{
 
     pub fn new(e_reporter : Sender<Error>) -> Self {
         Self { 
-            dfa_receiver : None,
-            phase_receiver : None,
+            event_receiver : None,
             dfa_content : None,
             final_dfa : None,
             handle : None,
+            cancel_token : CancelToken::new(),
             phase_content : vec![],
             phase_idx : 0,
             last_phase_msg : Instant::now(),
@@ -72,13 +72,28 @@ impl DFAConstructor{
             std::mem::swap(&mut handle, &mut self.handle);
             if let Some(h) = handle {
                 if h.is_finished() {
-                    let new_dfa = h.join().unwrap();
-                    if self.verify_run && (self.final_dfa.is_none() || &new_dfa != self.final_dfa.as_ref().unwrap()) {
+                    let new_dfa = match h.join().unwrap() {
+                        Ok(new_dfa) => new_dfa,
+                        Err(cancelled) => {
+                            //Show what got built before the cancel, and fall back to the last finished k
+                            if let Some(sig_sets) = cancelled.sig_sets {
+                                self.dfa_content = Some((cancelled.dfa, sig_sets));
+                            }
+                            if self.verify_run && self.final_dfa.is_some() {
+                                prep_panel.sig_k -= 1;
+                            }
+                            self.has_finished = true;
+                            return;
+                        }
+                    };
+                    let solve_ref: &SolverContents = self.last_solver.as_ref().unwrap();
+                    //A DFA that can be proven correct ends the search right away at this k
+                    let proven = self.verify_run && solve_ref.solve_type.is_correct(&solve_ref.rules, &solve_ref.goal, &new_dfa);
+                    if self.verify_run && !proven && (self.final_dfa.is_none() || &new_dfa != self.final_dfa.as_ref().unwrap()) {
                         prep_panel.sig_k += 1;
-                        let solve_ref: &SolverContents = self.last_solver.as_ref().unwrap();
                         self.run_dfa(solve_ref.solve_type.clone(), solve_ref.rules.clone(), solve_ref.goal.clone(), prep_panel.sig_k, true);
                     } else {
-                        if self.verify_run {
+                        if self.verify_run && !proven {
                             prep_panel.sig_k -= 1;
                         }
                         self.has_finished = true;
@@ -90,37 +105,26 @@ impl DFAConstructor{
                 }                
             }
         }
-        //Phase messages loop
+        //Solver events loop
         loop {
-            match &self.phase_receiver {
-                Some(k_phase_recv) => match k_phase_recv.try_recv() {
-                    Ok(message) => {
-                        if let None = self.initialization_dur {
-                            self.initialization_dur = Some(message);
-                        }else {
-                            self.max_duration = message.as_secs_f64().max(self.max_duration);
-                            self.phase_content[self.phase_idx].push(message);
-                            self.phase_idx = (self.phase_idx + 1) % self.last_solver.as_ref().unwrap().solve_type.get_phases().len();
-                            self.last_phase_msg = Instant::now();
-                        }
+            match &self.event_receiver {
+                Some(k_event_recv) => match k_event_recv.try_recv() {
+                    Ok(SolverEvent::Initialized { duration }) => {
+                        self.initialization_dur = Some(duration);
                     }
-                    Err(_) => {
-                        break
+                    Ok(SolverEvent::PhaseFinished { name, duration }) => {
+                        let phases = self.last_solver.as_ref().unwrap().solve_type.get_phases();
+                        let finished_idx = phases.iter().position(|phase| *phase == name).unwrap();
+                        self.max_duration = duration.as_secs_f64().max(self.max_duration);
+                        self.phase_content[finished_idx].push(duration);
+                        self.phase_idx = (finished_idx + 1) % phases.len();
+                        self.last_phase_msg = Instant::now();
                     }
-                },
-                None => {
-                    break
-                }
-            }
-        }
-        //DFA messages loop
-        loop {
-            match &self.dfa_receiver {
-                Some(k_dfa_recv) => match k_dfa_recv.try_recv() {
-                    Ok(message) => {
-                        self.iteration_state_lens.push(message.0.len());
-                        self.dfa_content = Some(message);
+                    Ok(SolverEvent::PartialDFA(dfa, sig_sets)) => {
+                        self.iteration_state_lens.push(dfa.len());
+                        self.dfa_content = Some((dfa, sig_sets));
                     }
+                    Ok(SolverEvent::IterationFinished { .. }) | Ok(SolverEvent::Finished { .. }) => {}
                     Err(reason) => {
                         match reason {
                             std::sync::mpsc::TryRecvError::Disconnected => {
@@ -128,7 +132,7 @@ impl DFAConstructor{
                                     let event = self.dfa_content.as_ref().unwrap();
                                     self.final_dfa = Some(srs_to_dfa::solver::event_to_dfa(&event.0,&event.1, &self.last_solver.as_ref().unwrap().rules));
                                 }
-                                self.dfa_receiver = None;
+                                self.event_receiver = None;
                                 self.has_finished = true;
                             },
                             std::sync::mpsc::TryRecvError::Empty => {
@@ -143,12 +147,20 @@ impl DFAConstructor{
             }
         }
     }
+    pub fn cancel(&mut self) {
+        self.cancel_token.cancel();
+    }
     pub fn update_solve_window(&mut self, ui : &mut Ui) {
     ui.add_enabled_ui(self.final_dfa.is_some(), |ui|{
     ui.separator();
+    ui.horizontal(|ui| {
     if ui.button("Save DFA").clicked() {
         save_dfa(self.final_dfa.as_ref().unwrap().clone(),self.e_reporter.clone());
     }
+    if ui.button("Copy as regex").clicked() {
+        ui.ctx().copy_text(self.final_dfa.as_ref().unwrap().to_regex());
+    }
+    });
 
     ui.separator();
     ui.horizontal_wrapped(|ui| {
@@ -207,16 +219,16 @@ impl DFAConstructor{
 
     #[cfg(not(target_arch = "wasm32"))]
     fn run_dfa_arch<S>(&mut self, solver : S, k : usize) where S : Solver{
-        let (dfa_rx, phase_rx, temp_h) = solver.run_debug(k,vec![]); 
-        self.dfa_receiver = Some(dfa_rx);
-        self.phase_receiver = Some(phase_rx);
+        self.cancel_token = CancelToken::new();
+        let (event_rx, temp_h) = solver.run_debug(k,vec![],self.cancel_token.clone()); 
+        self.event_receiver = Some(event_rx);
         self.handle = Some(temp_h);
     }
     #[cfg(target_arch = "wasm32")]
     fn run_dfa_arch<S>(&mut self, solver : S, k : usize) where S : Solver{
-        let (dfa_rx, phase_rx) = solver.run_debug(k,vec![]); 
-        self.dfa_receiver = Some(dfa_rx);
-        self.phase_receiver = Some(phase_rx);
+        self.cancel_token = CancelToken::new();
+        let event_rx = solver.run_debug(k,vec![],self.cancel_token.clone()); 
+        self.event_receiver = Some(event_rx);
     }
     pub fn run_dfa(&mut self, solver : AvailableSolver, rules : Ruleset, goal : DFA, k : usize, verify_run : bool){
         
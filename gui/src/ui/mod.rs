@@ -28,7 +28,7 @@ pub use web_time::Instant;
 #[cfg(not(target_arch = "wasm32"))]
 pub use std::time::Instant;
 
-use srs_to_dfa::solver::*;
+use srs_to_dfa::{solver::*, util::{DFA, Ruleset}};
 
 pub enum OpenItem {
     Goal,
@@ -53,6 +53,14 @@ impl AvailableSolver {
             AvailableSolver::Hash => HashSolver::PHASES
         }
     }
+    fn is_correct(&self, rules : &Ruleset, goal : &DFA, dfa : &DFA) -> bool {
+        match self {
+            AvailableSolver::Minkid => MinkidSolver::new(rules.clone(), goal.clone()).is_ok_and(|s| s.is_correct(dfa)),
+            AvailableSolver::Subset => SubsetSolver::new(rules.clone(), goal.clone()).is_ok_and(|s| s.is_correct(dfa)),
+            AvailableSolver::BFS => <BFSSolver as SRSSolver>::new(rules.clone(), goal.clone()).is_ok_and(|s| s.is_correct(dfa)),
+            AvailableSolver::Hash => HashSolver::new(rules.clone(), goal.clone()).is_ok_and(|s| s.is_correct(dfa))
+        }
+    }
 }
 
 impl Display for AvailableSolver {
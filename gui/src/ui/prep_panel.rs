@@ -2,7 +2,7 @@ use std::{path::PathBuf, sync::mpsc::Sender};
 
 use egui::{Ui, RichText};
 
-use srs_to_dfa::{util::{DFA, Ruleset}, builder::*};
+use srs_to_dfa::{util::{DFA, Ruleset, ParseError}, builder::*};
 
 use super::{open_file, OpenItem, PathSender, PathReceiver, AvailableSolver, Error};
 
@@ -16,6 +16,8 @@ pub struct PrepPanel {
     goal_pick : ExampleGoals,
     pub verify_run : bool,
     pub solver_type : AvailableSolver,
+    parse_error : Option<ParseError>,
+    goal_regex : String,
     e_reporter : Sender<Error>
 }
 
@@ -125,10 +127,27 @@ impl PrepPanel {
             goal_pick : ExampleGoals::OnlyOne1,
             verify_run : true,
             solver_type : AvailableSolver::Minkid,
+            parse_error : None,
+            goal_regex : "0*10*".to_owned(),
             e_reporter : e_reporter
             }
     }
 
+    //Parses the SRS in the editor. If that fails, the error is reported and the bad line highlighted until the SRS is edited
+    pub fn parse_srs(&mut self) -> Option<Ruleset> {
+        match Ruleset::parse(&self.srs_text) {
+            Ok(rules) => {
+                self.parse_error = None;
+                Some(rules)
+            }
+            Err(e) => {
+                let _ = self.e_reporter.send(Error { title : "Unable to parse SRS".to_owned(), body : RichText::new(e.to_string())});
+                self.parse_error = Some(e);
+                None
+            }
+        }
+    }
+
     pub fn topbar_update(&mut self, ui : &mut Ui) {
         ui.menu_button("File", |ui| {
 
@@ -159,7 +178,13 @@ impl PrepPanel {
             }
             });
         });
-        
+
+        if ui.button("Check termination").clicked() {
+            if let Some(rules) = self.parse_srs() {
+                let body = RichText::new(rules.termination().to_string(&rules.symbol_set));
+                let _ = self.e_reporter.send(Error { title : "Termination".to_owned(), body : body});
+            }
+        }
     }
 
     pub fn solve_window_update(&mut self, ui : &mut Ui) -> bool{
@@ -200,6 +225,24 @@ impl PrepPanel {
                 ui.close_menu();
                 open_file(OpenItem::Goal, self.path_s.clone());
             }
+            ui.menu_button("Goal from regex", |ui| {
+                ui.label("Written over the symbols of the current SRS, e.g. 0*10*");
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(&mut self.goal_regex);
+                    if ui.button("Use").clicked() {
+                        ui.close_menu();
+                        if let Some(rules) = self.parse_srs() {
+                            match DFA::from_regex(&self.goal_regex, &rules.symbol_set) {
+                                Ok(dfa) => {
+                                    self.goal = dfa;
+                                    self.goal_pick = ExampleGoals::Custom(self.goal_regex.clone());
+                                }
+                                Err(e) => {let _ = self.e_reporter.send(Error { title : "Unable to parse regex".to_owned(), body : RichText::new(e.to_string())});}
+                            }
+                        }
+                    }
+                });
+            });
             ui.menu_button("Load example goal", |ui| {
                 for i in vec![ExampleGoals::All0, ExampleGoals::OnlyOne1, ExampleGoals::OnlyOne2, ExampleGoals::All000,ExampleGoals::OneDPegResult,ExampleGoals::OneDPegResultxThree] {
                     if ui.button(i.to_string()).clicked() {
@@ -245,8 +288,24 @@ impl PrepPanel {
         }
 
         egui::ScrollArea::vertical().auto_shrink([false,false]).max_height(f32::INFINITY).show(ui, |ui| {
-            let srs_editor = egui::TextEdit::multiline(&mut self.srs_text).frame(false).desired_width(f32::INFINITY).code_editor();
-            ui.add_sized(ui.available_size(),srs_editor);
+            let error_line = self.parse_error.as_ref().map(|e| e.line);
+            let mut layouter = |ui : &Ui, text : &str, wrap_width : f32| {
+                let font_id = egui::TextStyle::Monospace.resolve(ui.style());
+                let mut job = egui::text::LayoutJob::default();
+                for (idx, line) in text.split_inclusive('\n').enumerate() {
+                    let mut format = egui::TextFormat::simple(font_id.clone(), ui.visuals().text_color());
+                    if Some(idx + 1) == error_line {
+                        format.background = ui.visuals().error_fg_color.gamma_multiply(0.3);
+                    }
+                    job.append(line, 0.0, format);
+                }
+                job.wrap.max_width = wrap_width;
+                ui.fonts(|f| f.layout_job(job))
+            };
+            let srs_editor = egui::TextEdit::multiline(&mut self.srs_text).frame(false).desired_width(f32::INFINITY).code_editor().layouter(&mut layouter);
+            if ui.add_sized(ui.available_size(),srs_editor).changed() {
+                self.parse_error = None;
+            }
             });
     }
 }
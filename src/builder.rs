@@ -1,4 +1,6 @@
 
+use std::collections::{HashMap, HashSet};
+
 use crate::{
     solver::{DomainError, SRSSolver},
     util::{Ruleset, SymbolIdx, SymbolSet, DFA},
@@ -262,93 +264,10 @@ pub fn build_flipx3_rs() -> Ruleset {
             vec![1 - i / 4 % 2, 1 - i / 2 % 2, 1 - i % 2],
         ))
     }
-
-    let k = 3;
-    let symbol_num = 2_u32.pow(k as u32) as usize;
-    let mut new_rules = vec![];
-    let mut vert_starts;
-    let mut vert_ends;
-    for rule in rules_vec {
-        //Horizontal (single-symbol) rules
-        for i in 0..(rule.0.len() - k + 1) {
-            let mut start_sym_idx = 0;
-            for (rule_sym_idx, rule_sym) in rule.0.iter().enumerate() {
-                start_sym_idx += rule_sym
-                    * (b_symbol_set.length as SymbolIdx)
-                        .pow((rule.0.len() - rule_sym_idx - 1) as u32);
-            }
-            start_sym_idx *= (b_symbol_set.length as SymbolIdx).pow((i) as u32);
-
-            let mut end_sym_idx = 0;
-            for (rule_sym_idx, rule_sym) in rule.1.iter().enumerate() {
-                end_sym_idx += rule_sym
-                    * (b_symbol_set.length as SymbolIdx)
-                        .pow((rule.1.len() - rule_sym_idx - 1) as u32);
-            }
-            end_sym_idx *= (b_symbol_set.length as SymbolIdx).pow(i as u32);
-            new_rules.push((vec![start_sym_idx], vec![end_sym_idx]));
-        }
-        //Vertical (normal symbol length) rules
-        //i is horizontal index selected
-        //Represents the fixed column we're doing business with
-
-        for i in 0..k {
-            //LHS and RHS respectively
-            vert_starts = vec![vec![0; rule.0.len()]];
-            vert_ends = vec![vec![0; rule.1.len()]];
-            //j is vertical index selected
-            for j in 0..k {
-                let cur_vert_rules = vert_starts.len();
-                let pow_num = (b_symbol_set.length as SymbolIdx).pow(j as u32);
-
-                //If we're looking at the fixed row
-                if i == j {
-                    for start_idx in 0..cur_vert_rules {
-                        for vert_idx in 0..vert_starts[start_idx].len() {
-                            vert_starts[start_idx][vert_idx] += rule.0[vert_idx] * pow_num;
-                        }
-                        for vert_idx in 0..vert_ends[start_idx].len() {
-                            vert_ends[start_idx][vert_idx] += rule.1[vert_idx] * pow_num;
-                        }
-                    }
-                } else {
-                    for start_idx in 0..cur_vert_rules {
-                        for k in 1..symbol_num {
-                            let mut new_vert_start = vert_starts[start_idx].clone();
-                            let mut new_vert_end = vert_ends[start_idx].clone();
-                            for l in 0..k {
-                                if (k >> l) % 2 == 1 {
-                                    new_vert_start[l] += pow_num;
-                                    new_vert_end[l] += pow_num;
-                                }
-                            }
-
-                            vert_starts.push(new_vert_start);
-                            vert_ends.push(new_vert_end);
-                        }
-                    }
-                }
-            }
-            for i in 0..vert_starts.len() {
-                new_rules.push((vert_starts[i].clone(), vert_ends[i].clone()));
-            }
-        }
-    }
-    let by_k_symbol_set = SymbolSet {
-        length: 2_u32.pow(k as u32) as usize,
-        representations: vec![
-            "000".to_owned(),
-            "001".to_owned(),
-            "010".to_owned(),
-            "011".to_owned(),
-            "100".to_owned(),
-            "101".to_owned(),
-            "110".to_owned(),
-            "111".to_owned(),
-        ], //whoops! lol
-    };
-
-    Ruleset::from_vec(new_rules, by_k_symbol_set.clone())
+    MultiTrack::new(3, b_symbol_set)
+        .unwrap()
+        .ruleset(&rules_vec, MoveDirection::Both)
+        .unwrap()
 }
 
 pub fn build_default2dpegx3_rs() -> Ruleset {
@@ -356,98 +275,16 @@ pub fn build_default2dpegx3_rs() -> Ruleset {
         length: 2,
         representations: vec!["0".to_owned(), "1".to_owned()],
     };
-
-    let rule_vec = vec![
-        (vec![1, 1, 0], vec![0, 0, 1]),
-        (vec![0, 1, 1], vec![1, 0, 0]),
-    ];
-
-    let k = 3;
-    let symbol_num = 2_u32.pow(k as u32) as usize;
-    let mut new_rules = vec![];
-    let mut vert_starts;
-    let mut vert_ends;
-    for rule in rule_vec {
-        //Horizontal (single-symbol) rules
-        for i in 0..(rule.0.len() - k + 1) {
-            let mut start_sym_idx = 0;
-            for (rule_sym_idx, rule_sym) in rule.0.iter().enumerate() {
-                start_sym_idx += rule_sym
-                    * (b_symbol_set.length as SymbolIdx)
-                        .pow((rule.0.len() - rule_sym_idx - 1) as u32);
-            }
-            start_sym_idx *= (b_symbol_set.length as SymbolIdx).pow((i) as u32);
-
-            let mut end_sym_idx = 0;
-            for (rule_sym_idx, rule_sym) in rule.1.iter().enumerate() {
-                end_sym_idx += rule_sym
-                    * (b_symbol_set.length as SymbolIdx)
-                        .pow((rule.1.len() - rule_sym_idx - 1) as u32);
-            }
-            end_sym_idx *= (b_symbol_set.length as SymbolIdx).pow(i as u32);
-            new_rules.push((vec![start_sym_idx], vec![end_sym_idx]));
-        }
-        //Vertical (normal symbol length) rules
-        //i is horizontal index selected
-        //Represents the fixed column we're doing business with
-
-        for i in 0..k {
-            //LHS and RHS respectively
-            vert_starts = vec![vec![0; rule.0.len()]];
-            vert_ends = vec![vec![0; rule.1.len()]];
-            //j is vertical index selected
-            for j in 0..k {
-                let cur_vert_rules = vert_starts.len();
-                let pow_num = (b_symbol_set.length as SymbolIdx).pow(j as u32);
-
-                //If we're looking at the fixed row
-                if i == j {
-                    for start_idx in 0..cur_vert_rules {
-                        for vert_idx in 0..vert_starts[start_idx].len() {
-                            vert_starts[start_idx][vert_idx] += rule.0[vert_idx] * pow_num;
-                        }
-                        for vert_idx in 0..vert_ends[start_idx].len() {
-                            vert_ends[start_idx][vert_idx] += rule.1[vert_idx] * pow_num;
-                        }
-                    }
-                } else {
-                    for start_idx in 0..cur_vert_rules {
-                        for k in 1..symbol_num {
-                            let mut new_vert_start = vert_starts[start_idx].clone();
-                            let mut new_vert_end = vert_ends[start_idx].clone();
-                            for l in 0..k {
-                                if (k >> l) % 2 == 1 {
-                                    new_vert_start[l] += pow_num;
-                                    new_vert_end[l] += pow_num;
-                                }
-                            }
-
-                            vert_starts.push(new_vert_start);
-                            vert_ends.push(new_vert_end);
-                        }
-                    }
-                }
-            }
-            for i in 0..vert_starts.len() {
-                new_rules.push((vert_starts[i].clone(), vert_ends[i].clone()));
-            }
-        }
-    }
-    let by_k_symbol_set = SymbolSet {
-        length: 2_u32.pow(k as u32) as usize,
-        representations: vec![
-            "000".to_owned(),
-            "001".to_owned(),
-            "010".to_owned(),
-            "011".to_owned(),
-            "100".to_owned(),
-            "101".to_owned(),
-            "110".to_owned(),
-            "111".to_owned(),
-        ], //whoops! lol
-    };
-
-    Ruleset::from_vec(new_rules, by_k_symbol_set.clone())
+    MultiTrack::new(3, b_symbol_set)
+        .unwrap()
+        .ruleset(
+            &[
+                (vec![1, 1, 0], vec![0, 0, 1]),
+                (vec![0, 1, 1], vec![1, 0, 0]),
+            ],
+            MoveDirection::Both,
+        )
+        .unwrap()
 }
 
 pub fn build_threerulesolver<S>() -> Result<S, DomainError>
@@ -525,3 +362,246 @@ where
 {
     S::new(build_default2dpegx3_rs(), build_2dpeg_goal())
 }
+
+//A single lhs and rhs, as taken by Ruleset::from_vec
+pub type Rule = (Vec<SymbolIdx>, Vec<SymbolIdx>);
+
+//Which way the moves given to MultiTrack::ruleset are played
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveDirection {
+    //Along any one row, across neighbouring columns
+    Rows,
+    //Down a single column
+    Columns,
+    Both,
+}
+
+//A board with a fixed number of rows that's read one column at a time, so each symbol of a string
+//is a whole column. Moves and goals are given in terms of single cells and expanded into column symbols,
+//e.g. MultiTrack::new(3, binary) has the symbols "000" to "111" used by build_default2dpegx3_rs.
+#[derive(Clone)]
+pub struct MultiTrack {
+    pub rows: usize,
+    //What a single cell can hold
+    pub cells: SymbolSet,
+    //Column symbols, named after their cells from the top row down
+    pub symbol_set: SymbolSet,
+    //The cells of each column symbol, from the top row down
+    columns: Vec<Vec<SymbolIdx>>,
+    indices: HashMap<Vec<SymbolIdx>, SymbolIdx>,
+}
+
+impl MultiTrack {
+    //Fails if there are more possible columns than SymbolIdx can index
+    pub fn new(rows: usize, cells: SymbolSet) -> Result<Self, DomainError> {
+        match cells.length.checked_pow(rows as u32) {
+            Some(count) if count <= SymbolIdx::MAX as usize + 1 => {}
+            count => return Err(DomainError::TooManySymbols(count.unwrap_or(usize::MAX))),
+        }
+        //Single character cells are just written next to each other, like "011"
+        let separator = if cells.representations.iter().all(|name| name.chars().count() == 1) {
+            ""
+        } else {
+            "_"
+        };
+        let name = |column: &Vec<SymbolIdx>| {
+            column
+                .iter()
+                .map(|cell| cells.representations[*cell as usize].clone())
+                .collect::<Vec<String>>()
+                .join(separator)
+        };
+        let mut columns: Vec<Vec<SymbolIdx>> = vec![vec![]];
+        for _ in 0..rows {
+            columns = columns
+                .into_iter()
+                .flat_map(|column| {
+                    (0..cells.length).map(move |cell| {
+                        let mut column = column.clone();
+                        column.push(cell as SymbolIdx);
+                        column
+                    })
+                })
+                .collect();
+        }
+        //Sorted the same way as the SymbolSet, so that their indices line up
+        columns.sort_by_cached_key(name);
+        let symbol_set = SymbolSet::<String>::new(columns.iter().map(name).collect());
+        let indices = columns
+            .iter()
+            .enumerate()
+            .map(|(idx, column)| (column.clone(), idx as SymbolIdx))
+            .collect();
+        Ok(MultiTrack {
+            rows,
+            cells,
+            symbol_set,
+            columns,
+            indices,
+        })
+    }
+
+    //The column symbol holding these cells, from the top row down
+    pub fn column(&self, cells: &[SymbolIdx]) -> Result<SymbolIdx, DomainError> {
+        self.indices
+            .get(cells)
+            .copied()
+            .ok_or_else(|| DomainError::InvalidCells(cells.to_vec()))
+    }
+
+    //The cells in a column symbol, from the top row down
+    pub fn cells_of(&self, column: SymbolIdx) -> &[SymbolIdx] {
+        &self.columns[column as usize]
+    }
+
+    //Rules for lhs turning into rhs along any one row, over as many neighbouring columns as lhs is long.
+    //Every other row can hold anything, and is left as it is.
+    pub fn row_rules(
+        &self,
+        lhs: &[SymbolIdx],
+        rhs: &[SymbolIdx],
+    ) -> Result<Vec<Rule>, DomainError> {
+        self.check_move(lhs, rhs)?;
+        let mut rules = vec![];
+        for row in 0..self.rows {
+            for others in 0..self.cells.length.pow(((self.rows - 1) * lhs.len()) as u32) {
+                let (mut rule_lhs, mut rule_rhs) = (vec![], vec![]);
+                let mut others = others;
+                for (lhs_cell, rhs_cell) in lhs.iter().zip(rhs) {
+                    let (mut lhs_column, mut rhs_column) = (vec![], vec![]);
+                    for cur_row in 0..self.rows {
+                        if cur_row == row {
+                            lhs_column.push(*lhs_cell);
+                            rhs_column.push(*rhs_cell);
+                        } else {
+                            let cell = (others % self.cells.length) as SymbolIdx;
+                            others /= self.cells.length;
+                            lhs_column.push(cell);
+                            rhs_column.push(cell);
+                        }
+                    }
+                    rule_lhs.push(self.column(&lhs_column)?);
+                    rule_rhs.push(self.column(&rhs_column)?);
+                }
+                rules.push((rule_lhs, rule_rhs));
+            }
+        }
+        Ok(rules)
+    }
+
+    //Rules for lhs turning into rhs down a single column, starting at any row it fits from.
+    //The rest of the column can hold anything, and is left as it is.
+    pub fn column_rules(
+        &self,
+        lhs: &[SymbolIdx],
+        rhs: &[SymbolIdx],
+    ) -> Result<Vec<Rule>, DomainError> {
+        self.check_move(lhs, rhs)?;
+        let mut rules = vec![];
+        if lhs.len() > self.rows {
+            return Ok(rules);
+        }
+        for offset in 0..=(self.rows - lhs.len()) {
+            for others in 0..self.cells.length.pow((self.rows - lhs.len()) as u32) {
+                let mut others = others;
+                let (mut lhs_column, mut rhs_column) = (vec![], vec![]);
+                for cur_row in 0..self.rows {
+                    if (offset..offset + lhs.len()).contains(&cur_row) {
+                        lhs_column.push(lhs[cur_row - offset]);
+                        rhs_column.push(rhs[cur_row - offset]);
+                    } else {
+                        let cell = (others % self.cells.length) as SymbolIdx;
+                        others /= self.cells.length;
+                        lhs_column.push(cell);
+                        rhs_column.push(cell);
+                    }
+                }
+                rules.push((
+                    vec![self.column(&lhs_column)?],
+                    vec![self.column(&rhs_column)?],
+                ));
+            }
+        }
+        Ok(rules)
+    }
+
+    //Moves can't change the size of the board, and can only use cells the board has
+    fn check_move(&self, lhs: &[SymbolIdx], rhs: &[SymbolIdx]) -> Result<(), DomainError> {
+        let rule = || (lhs.to_vec(), rhs.to_vec());
+        if lhs.len() < rhs.len() {
+            return Err(DomainError::Generating(rule()));
+        }
+        if lhs.len() > rhs.len() {
+            return Err(DomainError::Deleting(rule()));
+        }
+        let out_of_range =
+            |cells: &&[SymbolIdx]| cells.iter().any(|cell| *cell as usize >= self.cells.length);
+        match [lhs, rhs].into_iter().find(out_of_range) {
+            Some(cells) => Err(DomainError::InvalidCells(cells.to_vec())),
+            None => Ok(()),
+        }
+    }
+
+    //Every move works in the given direction, e.g. down columns and along rows for a 1D SRS
+    //played on a 2D board
+    pub fn ruleset(
+        &self,
+        moves: &[Rule],
+        direction: MoveDirection,
+    ) -> Result<Ruleset, DomainError> {
+        let mut seen = HashSet::new();
+        let mut rules = vec![];
+        for (lhs, rhs) in moves {
+            let mut move_rules = vec![];
+            if direction != MoveDirection::Rows {
+                move_rules.extend(self.column_rules(lhs, rhs)?);
+            }
+            if direction != MoveDirection::Columns {
+                move_rules.extend(self.row_rules(lhs, rhs)?);
+            }
+            for rule in move_rules {
+                if seen.insert(rule.clone()) {
+                    rules.push(rule);
+                }
+            }
+        }
+        Ok(Ruleset::from_vec(rules, self.symbol_set.clone()))
+    }
+
+    //Boards with exactly count cells holding cell, e.g. exactly one peg left
+    pub fn count_goal(&self, cell: SymbolIdx, count: usize) -> DFA {
+        //Each state is how many have been seen so far, with count + 1 standing for too many
+        DFA {
+            starting_state: 0,
+            state_transitions: (0..=count + 1)
+                .map(|seen| {
+                    self.columns
+                        .iter()
+                        .map(|column| {
+                            (seen + column.iter().filter(|x| **x == cell).count()).min(count + 1)
+                        })
+                        .collect()
+                })
+                .collect(),
+            accepting_states: (0..=count + 1).map(|seen| seen == count).collect(),
+            symbol_set: self.symbol_set.clone(),
+        }
+    }
+
+    //Draws a string as the board it stands for, one row per line
+    pub fn draw(&self, string: &[SymbolIdx]) -> String {
+        (0..self.rows)
+            .map(|row| {
+                string
+                    .iter()
+                    .map(|column| {
+                        self.cells.representations[self.columns[*column as usize][row] as usize]
+                            .clone()
+                    })
+                    .collect::<Vec<String>>()
+                    .join(" ")
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
@@ -0,0 +1,176 @@
+use std::{
+    fs::File,
+    io::{Read, Write},
+    path::Path,
+    process::exit,
+};
+
+use srs_to_dfa::{
+    solver::{BFSSolver, HashSolver, MinkidSolver, SRSSolver, SubsetSolver},
+    util::{Ruleset, DFA},
+};
+
+const USAGE: &str = "Usage: srs_to_dfa <srs file> <goal (.dfa or .jff)> <k> <minkid|subset|hash|bfs> <output (.dfa, .jff or .dot)> [--quiet] [--regex] [--canonical] [--report <.md or .json>]";
+
+fn main() {
+    let mut quiet = false;
+    let mut goal_is_regex = false;
+    let mut canonical = false;
+    let mut report = None;
+    let mut positional = vec![];
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match &arg[..] {
+            "-q" | "--quiet" => quiet = true,
+            "--regex" => goal_is_regex = true,
+            "--canonical" => canonical = true,
+            "--report" => report = Some(args.next().unwrap_or_else(|| fail(USAGE))),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ => positional.push(arg),
+        }
+    }
+    if positional.len() != 5 {
+        fail(USAGE);
+    }
+
+    let rules = match std::fs::read_to_string(&positional[0]) {
+        Ok(contents) => match Ruleset::parse_with_stats(&contents) {
+            Ok((rules, stats)) => {
+                if !quiet && (!stats.patterns.is_empty() || stats.identities > 0) {
+                    println!("{}", stats);
+                }
+                rules
+            }
+            Err(e) => fail(&format!("Unable to parse SRS \"{}\": {}", positional[0], e)),
+        },
+        Err(e) => fail(&format!("Unable to read SRS \"{}\": {}", positional[0], e)),
+    };
+    let goal = if goal_is_regex {
+        match DFA::from_regex(&positional[1], &rules.symbol_set) {
+            Ok(goal) => goal,
+            Err(e) => fail(&format!("Unable to parse goal regex \"{}\": {}", positional[1], e)),
+        }
+    } else {
+        load_goal(&positional[1])
+    };
+    let sig_k: usize = match positional[2].parse() {
+        Ok(k) => k,
+        Err(_) => fail(&format!("k must be a non-negative integer, got \"{}\"", positional[2])),
+    };
+    //Both outputs are created before solving, so a bad path doesn't throw away a long run
+    let output = create_output(
+        &positional[4],
+        &["dfa", "jff", "dot"],
+        "Output needs to be .dfa, .jff or .dot.",
+    );
+    let report = report.map(|path| {
+        let file = create_output(&path, &["md", "json"], "Report needs to be .md or .json.");
+        (file, path)
+    });
+
+    let mut result = match &positional[3].to_lowercase()[..] {
+        "minkid" => solve::<MinkidSolver>(rules, goal, sig_k, quiet),
+        "subset" => solve::<SubsetSolver>(rules, goal, sig_k, quiet),
+        "hash" => solve::<HashSolver>(rules, goal, sig_k, quiet),
+        "bfs" => solve::<BFSSolver>(rules, goal, sig_k, quiet),
+        other => fail(&format!(
+            "Unknown solver \"{}\". Expected one of minkid, subset, hash or bfs",
+            other
+        )),
+    };
+
+    if canonical {
+        result.canonicalize();
+    }
+    save_result(&result, output, &positional[4]);
+    if let Some((file, path)) = report {
+        save_report(&result, sig_k, file, &path);
+    }
+}
+
+fn solve<S: SRSSolver>(rules: Ruleset, goal: DFA, sig_k: usize, quiet: bool) -> DFA {
+    let symbol_set = rules.symbol_set.clone();
+    let solver = match S::new(rules, goal) {
+        Ok(solver) => solver,
+        Err(d_error) => fail(&d_error.to_string(&symbol_set)),
+    };
+    if quiet {
+        solver.run(sig_k, vec![])
+    } else {
+        solver.run_with_print(sig_k, vec![])
+    }
+}
+
+fn load_goal(path: &str) -> DFA {
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) => fail(&format!("Unable to open goal \"{}\": {}", path, e)),
+    };
+    match extension(path) {
+        Some("dfa") => match DFA::load(&mut file) {
+            Ok(dfa) => dfa,
+            Err(e) => fail(&format!("Unable to parse goal \"{}\": {}", path, e)),
+        },
+        Some("jff") => {
+            let mut contents = String::new();
+            if let Err(e) = file.read_to_string(&mut contents) {
+                fail(&format!("Unable to read goal \"{}\": {}", path, e));
+            }
+            match DFA::parse_jflap(&contents) {
+                Ok(dfa) => dfa,
+                Err(e) => fail(&format!("Unable to parse goal \"{}\": {}", path, e)),
+            }
+        }
+        _ => fail("Goal needs to be .dfa or .jff."),
+    }
+}
+
+fn create_output(path: &str, extensions: &[&str], message: &str) -> File {
+    if !extension(path).is_some_and(|ext| extensions.contains(&ext)) {
+        fail(message);
+    }
+    match File::create(path) {
+        Ok(file) => file,
+        Err(e) => fail(&format!("Unable to create \"{}\": {}", path, e)),
+    }
+}
+
+fn save_result(dfa: &DFA, mut file: File, path: &str) {
+    let ext = extension(path);
+    let written = if ext == Some("jff") {
+        file.write_all(&dfa.save_jflap_to_bytes())
+            .map_err(|e| e.to_string())
+    } else if ext == Some("dot") {
+        file.write_all(dfa.to_dot().as_bytes())
+            .map_err(|e| e.to_string())
+    } else {
+        dfa.save(&mut file).map_err(|e| e.to_string())
+    };
+    if let Err(e) = written {
+        fail(&format!("Unable to save \"{}\": {}", path, e));
+    }
+}
+
+fn save_report(dfa: &DFA, sig_k: usize, mut file: File, path: &str) {
+    let report = dfa.nerode_report(sig_k);
+    let contents = if extension(path) == Some("md") {
+        report.to_markdown()
+    } else {
+        report.to_json()
+    };
+    if let Err(e) = file.write_all(contents.as_bytes()) {
+        fail(&format!("Unable to save \"{}\": {}", path, e));
+    }
+}
+
+fn extension(path: &str) -> Option<&str> {
+    Path::new(path).extension().and_then(|ext| ext.to_str())
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    exit(1)
+}
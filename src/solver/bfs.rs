@@ -1,7 +1,7 @@
 use std::collections::{HashMap, HashSet};
 use std::hash::Hash;
 use std::ops::Range;
-use std::sync::{mpsc::{Sender,Receiver}, Arc};
+use std::sync::{mpsc::{Sender,Receiver,RecvTimeoutError}, Arc};
 use std::thread;
 use std::time::Duration;
 
@@ -13,11 +13,11 @@ use crate::util::{Ruleset, SymbolIdx, DFA};
 use crate::SymbolSet;
 
 use super::Instant;
-use super::{DFAStructure, GenericSolver, SRSSolver, SSStructure};
-use crate::solver::srssolver::DomainError;
+use super::{CancelToken, Cancelled, DFAStructure, GenericSolver, SRSSolver, SolverEvent, SolverObserver};
+use crate::solver::srssolver::{check_symbol_count, DomainError};
 
 #[derive(Clone)]
-pub struct BFSSolver<State = Vec<u8>, Input = String, Output = bool>
+pub struct BFSSolver<State = Vec<SymbolIdx>, Input = String, Output = bool>
 where
     State: Clone,
 {
@@ -39,6 +39,7 @@ impl SRSSolver for BFSSolver<Vec<SymbolIdx>, String, bool> {
 
     fn new(ruleset: Ruleset, goal: DFA) -> Result<Self, DomainError> {
         //Self::ensure_expansion(&mut ruleset,&mut goal);
+        check_symbol_count(&goal.symbol_set)?;
         Ok(BFSSolver {
             rules: Some(ruleset),
             goal: Some(goal.clone()),
@@ -80,12 +81,12 @@ where
     fn run_internal(
         self,
         sig_k: usize,
-        is_debug: bool,
-        dfa_events: Sender<(DFAStructure, SSStructure)>,
-        phase_events: Sender<Duration>,
         origin: State,
-    ) -> DFA<Input, Output> {
+        observer: &mut dyn SolverObserver,
+        cancel: CancelToken,
+    ) -> Result<DFA<Input, Output>, Cancelled> {
         let init_begin_time = Instant::now();
+        let is_debug = observer.is_listening();
         let mut trans_table: Vec<Vec<usize>> = Vec::new(); //omg it's me !!!
         let mut table_reference = HashMap::<Vec<Output>, usize>::new();
 
@@ -99,25 +100,49 @@ where
 
         let mut new_state_to_ctx = HashMap::<State,EvaluatedState<Output>>::new();
 
-        let (mut input, output) = self.create_workers(thread_translator.clone());
+        let (mut input, output, workers) = self.create_workers(thread_translator.clone(), &cancel);
 
         let mut empty_copy: Vec<usize> = Vec::new();
         for _ in 0..self.symbol_set.length {
             empty_copy.push(0);
         }
 
-        input.send(Dispatch{origin, k: sig_k, range : 0..self.symbol_set.sig_set_size(sig_k)}).unwrap();
-
-        let start_accepting = output.recv().unwrap().results;
+        //The starting state's sig set is split up too, so a cancel doesn't wait on all of it
+        let start_chunks = self.worker_threads.min(self.symbol_set.sig_set_size(sig_k)).max(1);
+        self.dispatch(&mut input, &origin, sig_k, start_chunks);
+        let mut start_accepting = vec![Output::default(); self.symbol_set.sig_set_size(sig_k)];
+        for _ in 0..start_chunks {
+            match self.collect(&output, &cancel) {
+                Some(response) => {
+                    start_accepting[response.range].clone_from_slice(&response.results)
+                }
+                None => {
+                    self.terminate_workers(input, workers);
+                    return Err(Cancelled {
+                        dfa: DFAStructure::Dense(trans_table),
+                        sig_sets: None,
+                    });
+                }
+            }
+        }
         table_reference.insert(start_accepting.clone(), 0);
         trans_table.push(empty_copy.clone());
 
         //redundant bc of start_accepting already checking this but idc
 
         if is_debug {
-            let _ = phase_events.send(Instant::now() - init_begin_time);
+            observer.notify(&SolverEvent::Initialized {
+                duration: init_begin_time.elapsed(),
+            });
         }
+        //Only set when the loop stops early, so a cancel that comes in after the last iteration
+        //doesn't throw away a finished DFA
+        let mut cancelled = false;
         while new_boards.len() > 0 {
+            if cancel.is_cancelled() {
+                cancelled = true;
+                break;
+            }
             new_state_to_ctx.clear();
             if is_debug {
                 //TODO: Genericize this
@@ -133,7 +158,6 @@ where
                 chunks_per_sig_set = 1;
             }
             let mut chunks_dispatched = 0;
-            let chunk_length = self.symbol_set.sig_set_size(sig_k) / chunks_per_sig_set;
             for (start_idx, board) in &old_boards {
                 for sym_idx in 0..(self.symbol_set.length as SymbolIdx) {
                     let new_board = self.mutate(board.clone(), sym_idx);
@@ -143,32 +167,21 @@ where
                         results : vec![Output::default(); self.symbol_set.sig_set_size(sig_k)],
                         chunks_received : 0
                     });
-                    
-                    //First chunk takes the brunt of any divisibility issues so sig set elements aren't missing
-                    let mut chunk_cursor = chunk_length + self.symbol_set.sig_set_size(sig_k) % chunks_per_sig_set;
-                    input.send(Dispatch {
-                        origin : new_board.clone(),
-                        k : sig_k,
-                        range : 0..chunk_cursor
-                    }).unwrap();
-                    chunks_dispatched+=1;
-                    //dole out any additional chunks
-                    for _chunk_idx in 1..chunks_per_sig_set {
-                        input.send(Dispatch {
-                            origin : new_board.clone(),
-                            k : sig_k,
-                            range : chunk_cursor..chunk_cursor + chunk_length
-                        }).unwrap();
-                        chunks_dispatched+=1;
-                        chunk_cursor += chunk_length;
-                    }
-                    assert!(chunk_cursor == self.symbol_set.sig_set_size(sig_k), "Internal chunking issue");
+
+                    self.dispatch(&mut input, &new_board, sig_k, chunks_per_sig_set);
+                    chunks_dispatched += chunks_per_sig_set;
                 }
             }    
             // Collect results from worker threads + evaluate when appropriate
             let mut chunks_collected = 0;
             while chunks_collected < chunks_dispatched {
-                let collected_chunk = output.recv().unwrap();
+                let collected_chunk = match self.collect(&output, &cancel) {
+                    Some(response) => response,
+                    None => {
+                        cancelled = true;
+                        break;
+                    }
+                };
                 chunks_collected += 1;
                 let eval_state = new_state_to_ctx.get_mut(&collected_chunk.origin).unwrap();
                 
@@ -197,22 +210,41 @@ where
                 trans_table[eval_state.origin_idx][eval_state.sym_idx as usize] = dest_idx;
             }
         }
+            if cancelled {
+                break;
+            }
             if is_debug {
                 let dur = iter_begin_time.elapsed();
-                phase_events.send(dur).unwrap();
+                observer.notify(&SolverEvent::PhaseFinished {
+                    name: Self::PHASES[0],
+                    duration: dur,
+                });
+                observer.notify(&SolverEvent::IterationFinished {
+                    states: trans_table.len(),
+                    new_states: new_boards.len(),
+                });
             }
         }
-        if is_debug {
+        if is_debug && !cancelled {
             //TODO: Genericize this
             //dfa_events.send((DFAStructure::Dense(trans_table.clone()),SSStructure::BooleanMap(table_reference.clone()))).unwrap();
+            observer.notify(&SolverEvent::Finished {
+                states: trans_table.len(),
+            });
         }
-        self.terminate_workers(input);
-        DFA {
+        self.terminate_workers(input, workers);
+        if cancelled {
+            return Err(Cancelled {
+                dfa: DFAStructure::Dense(trans_table),
+                sig_sets: None,
+            });
+        }
+        Ok(DFA {
             state_transitions: trans_table,
             accepting_states: state_outputs,
             starting_state: 0,
             symbol_set: self.symbol_set.clone(),
-        }
+        })
     }
 }
 
@@ -254,22 +286,74 @@ where
     fn create_workers(
         &self,
         thread_translator: Arc<Self>,
+        cancel: &CancelToken,
     ) -> (
         spmc::Sender<Dispatch<State>>,
         Receiver<DispatchResponse<State, Output>>,
+        Vec<thread::JoinHandle<()>>,
     ) {
         let (input_tx, input_rx) = spmc::channel::<Dispatch<State>>();
         let (output_tx, output_rx)= std::sync::mpsc::channel();
 
+        let mut workers = vec![];
         for _ in 0..self.worker_threads {
-            worker_thread(thread_translator.clone(), input_rx.clone(), output_tx.clone());
+            workers.push(worker_thread(thread_translator.clone(), input_rx.clone(), output_tx.clone(), cancel.clone()));
         }
-        (input_tx, output_rx)
+        (input_tx, output_rx, workers)
     }
-    fn terminate_workers(&self, mut input: spmc::Sender<Dispatch<State>>) {
+    fn terminate_workers(&self, mut input: spmc::Sender<Dispatch<State>>, workers: Vec<thread::JoinHandle<()>>) {
         for _ in 0..self.worker_threads {
             input.send(Dispatch{origin : State::default(), range : 0..usize::MAX, k : 0}).unwrap();
         }
+        for worker in workers {
+            worker.join().unwrap();
+        }
+    }
+    //Splits the sig set of origin into chunks for the workers
+    fn dispatch(
+        &self,
+        input: &mut spmc::Sender<Dispatch<State>>,
+        origin: &State,
+        sig_k: usize,
+        chunks: usize,
+    ) {
+        let sig_set_size = self.symbol_set.sig_set_size(sig_k);
+        let chunk_length = sig_set_size / chunks;
+        //First chunk takes the brunt of any divisibility issues so sig set elements aren't missing
+        let mut chunk_cursor = chunk_length + sig_set_size % chunks;
+        input.send(Dispatch {
+            origin : origin.clone(),
+            k : sig_k,
+            range : 0..chunk_cursor
+        }).unwrap();
+        //dole out any additional chunks
+        for _chunk_idx in 1..chunks {
+            input.send(Dispatch {
+                origin : origin.clone(),
+                k : sig_k,
+                range : chunk_cursor..chunk_cursor + chunk_length
+            }).unwrap();
+            chunk_cursor += chunk_length;
+        }
+        assert!(chunk_cursor == sig_set_size, "Internal chunking issue");
+    }
+    //Waits on the next finished chunk, giving up if the run gets cancelled in the meantime
+    fn collect(
+        &self,
+        output: &Receiver<DispatchResponse<State, Output>>,
+        cancel: &CancelToken,
+    ) -> Option<DispatchResponse<State, Output>> {
+        loop {
+            match output.recv_timeout(Duration::from_millis(100)) {
+                Ok(response) => return Some(response),
+                Err(RecvTimeoutError::Timeout) => {
+                    if cancel.is_cancelled() {
+                        return None;
+                    }
+                }
+                Err(RecvTimeoutError::Disconnected) => panic!("BFS worker threads hung up"),
+            }
+        }
     }
 }
 
@@ -319,6 +403,7 @@ fn worker_thread<State, Input, Output>(
     translator: Arc<BFSSolver<State, Input, Output>>,
     input: spmc::Receiver<Dispatch<State>>,
     output: Sender<DispatchResponse<State,Output>>,
+    cancel: CancelToken,
 ) -> thread::JoinHandle<()>
 where
 State: Clone + 'static + std::marker::Send + std::marker::Sync + Default + Hash + Eq,
@@ -331,17 +416,29 @@ Output: std::marker::Send + std::marker::Sync + Clone + 'static + Default + std:
             if dispatch.range.end == usize::MAX {
                 return;
             }
+            //Leftover work from a cancelled run is dropped so the pool can wind down quickly.
+            //It's checked for every element, since a single one can take a long time to evaluate.
+            if cancel.is_cancelled() {
+                continue;
+            }
             let sig_set_iter = translator.get_sig_set(dispatch.origin.clone(),dispatch.k).clone();
             let mut sig_set_iter = sig_set_iter.skip(dispatch.range.start);
             let mut result_vec = vec![];
             for _ in dispatch.range.clone() {
+                if cancel.is_cancelled() {
+                    break;
+                }
                 result_vec.push((translator.evaluator)(&translator, &sig_set_iter.next().unwrap()));
             }
-            output.send(DispatchResponse {
+            //A partial chunk is of no use to anyone
+            if result_vec.len() < dispatch.range.len() {
+                continue;
+            }
+            let _ = output.send(DispatchResponse {
                 origin : dispatch.origin,
                 results : result_vec,
                 range : dispatch.range
-            }).unwrap();
+            });
         }
     })
 }
@@ -1,10 +1,17 @@
 use std::collections::HashMap;
+use std::sync::mpsc::Sender;
+use std::time::Duration;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
 
 use bitvec::vec::BitVec;
 use petgraph::prelude::DiGraph;
 
-use crate::util::{Ruleset, SymbolIdx};
+use crate::util::{dot::automaton_to_dot, Ruleset, SymbolIdx, SymbolSet};
 use crate::DFA;
+#[derive(Clone)]
 pub enum DFAStructure {
     Dense(Vec<Vec<usize>>),
     Graph(DiGraph<(), SymbolIdx>),
@@ -32,8 +39,39 @@ impl DFAStructure {
             }
         }
     }
+    //Graphviz version of a (possibly unfinished) DFA. Graph transitions that haven't been found yet
+    //are left out, but a Dense table can't tell them apart from real transitions to q0, so they're
+    //drawn as edges to q0. Accepting states are only marked if the signature sets are given.
+    pub fn to_dot(&self, symbol_set: &SymbolSet, sig_sets: Option<&SSStructure>) -> String {
+        let accepting_states = sig_sets.map_or(vec![], |ss| ss.accepting_states());
+        let starting_state = if self.len() > 0 { Some(0) } else { None };
+        let transitions: Vec<(usize, usize, String)> = match self {
+            Self::Dense(array) => array
+                .iter()
+                .enumerate()
+                .flat_map(|(idx, state)| {
+                    state.iter().enumerate().map(move |(sym, target)| {
+                        (idx, *target, symbol_set.representations[sym].clone())
+                    })
+                })
+                .collect(),
+            Self::Graph(dfa_graph) => dfa_graph
+                .raw_edges()
+                .iter()
+                .map(|edge| {
+                    (
+                        edge.source().index(),
+                        edge.target().index(),
+                        symbol_set.representations[edge.weight as usize].clone(),
+                    )
+                })
+                .collect(),
+        };
+        automaton_to_dot(self.len(), starting_state, &accepting_states, transitions)
+    }
 }
 
+#[derive(Clone)]
 pub enum SSStructure {
     Boolean(Vec<BitVec>),
     BooleanMap(HashMap<BitVec, usize>),
@@ -71,6 +109,88 @@ impl SSStructure {
     }
 }
 
+//Everything a solver reports while it runs, in the order it happens.
+//Initialized comes first. Each iteration is a PartialDFA, one PhaseFinished per entry in Solver::PHASES,
+//then IterationFinished. A successful run ends with the completed PartialDFA and Finished.
+#[derive(Clone)]
+pub enum SolverEvent {
+    Initialized {
+        duration: Duration,
+    },
+    PhaseFinished {
+        name: &'static str,
+        duration: Duration,
+    },
+    IterationFinished {
+        states: usize,
+        new_states: usize,
+    },
+    //DFA as known at the start of an iteration, with the signature sets behind each state
+    PartialDFA(DFAStructure, SSStructure),
+    Finished {
+        states: usize,
+    },
+}
+
+pub trait SolverObserver: Send {
+    fn notify(&mut self, event: &SolverEvent);
+    //Solvers skip building events (partial DFAs especially) when nobody is listening
+    fn is_listening(&self) -> bool {
+        true
+    }
+}
+
+//No-op observer
+impl SolverObserver for () {
+    fn notify(&mut self, _event: &SolverEvent) {}
+    fn is_listening(&self) -> bool {
+        false
+    }
+}
+
+//Forwards events to another thread. A hung up receiver is ignored so the run can still finish.
+impl SolverObserver for Sender<SolverEvent> {
+    fn notify(&mut self, event: &SolverEvent) {
+        let _ = self.send(event.clone());
+    }
+}
+
+//Fans events out to several observers
+impl SolverObserver for Vec<Box<dyn SolverObserver>> {
+    fn notify(&mut self, event: &SolverEvent) {
+        for observer in self.iter_mut() {
+            if observer.is_listening() {
+                observer.notify(event);
+            }
+        }
+    }
+    fn is_listening(&self) -> bool {
+        self.iter().any(|observer| observer.is_listening())
+    }
+}
+
+//Shared flag for stopping a run early. Clones all refer to the same run.
+#[derive(Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+//What a run had built when it was cancelled. Solvers without boolean signature sets leave sig_sets empty.
+pub struct Cancelled {
+    pub dfa: DFAStructure,
+    pub sig_sets: Option<SSStructure>,
+}
+
 pub fn event_to_dfa(dfa_s: &DFAStructure, sig_sets: &SSStructure, rules: &Ruleset) -> DFA {
     let trans_table = dfa_s.to_dense(rules.symbol_set.length);
     let accepting_states = sig_sets.accepting_states();
@@ -1,7 +1,7 @@
 use std::collections::{HashMap, HashSet};
 
 use crate::{
-    solver::{DFAStructure, SSStructure},
+    solver::{CancelToken, Cancelled, DFAStructure, SSStructure, SolverEvent, SolverObserver},
     util::{Ruleset, SymbolIdx, DFA},
 };
 
@@ -28,7 +28,7 @@ impl SRSSolver for HashSolver {
     }
 
     fn new(mut ruleset: Ruleset, mut goal: DFA) -> Result<Self, DomainError> {
-        Self::ensure_expansion(&mut ruleset, &mut goal);
+        Self::ensure_expansion(&mut ruleset, &mut goal)?;
         let (min_input, max_input) = HashSolver::sized_init(&ruleset);
         Ok(HashSolver {
             min_input: min_input,
@@ -45,22 +45,22 @@ impl Solver for HashSolver {
         &self.rules.symbol_set
     }
     const PHASES: &'static [&'static str] = &["Entire Iteration"];
-    fn evaluate<'a, 'b>(&'a self, state: &'b Vec<u8>) -> bool {
+    fn evaluate<'a, 'b>(&'a self, state: &'b Vec<SymbolIdx>) -> bool {
         todo!()
     }
 
-    fn mutate(&self, state: Vec<u8>, input: SymbolIdx) -> Vec<u8> {
+    fn mutate(&self, state: Vec<SymbolIdx>, input: SymbolIdx) -> Vec<SymbolIdx> {
         todo!()
     }
     fn run_internal(
         mut self,
         sig_k: usize,
-        is_debug: bool,
-        dfa_events: std::sync::mpsc::Sender<(DFAStructure, SSStructure)>,
-        phase_events: std::sync::mpsc::Sender<std::time::Duration>,
         origin: Vec<SymbolIdx>,
-    ) -> DFA {
+        observer: &mut dyn SolverObserver,
+        cancel: CancelToken,
+    ) -> Result<DFA, Cancelled> {
         let init_begin_time = Instant::now();
+        let is_debug = observer.is_listening();
         let sig_set = self.rules.symbol_set.build_sig_k(sig_k);
 
         let mut trans_table: Vec<Vec<usize>> = Vec::new(); //omg it's me !!!
@@ -87,22 +87,28 @@ impl Solver for HashSolver {
         }
 
         if is_debug {
-            let _ = phase_events.send(Instant::now() - init_begin_time);
+            observer.notify(&SolverEvent::Initialized {
+                duration: init_begin_time.elapsed(),
+            });
         }
         while new_boards.len() > 0 {
             if is_debug {
-                dfa_events
-                    .send((
-                        DFAStructure::Dense(trans_table.clone()),
-                        SSStructure::BooleanMap(table_reference.clone()),
-                    ))
-                    .unwrap();
+                observer.notify(&SolverEvent::PartialDFA(
+                    DFAStructure::Dense(trans_table.clone()),
+                    SSStructure::BooleanMap(table_reference.clone()),
+                ));
             }
             let iter_begin_time = Instant::now();
             std::mem::swap(&mut old_boards, &mut new_boards);
             new_boards.clear();
             self.board_solutions = HashMap::new();
             for (start_idx, board) in &old_boards {
+                if cancel.is_cancelled() {
+                    return Err(Cancelled {
+                        dfa: DFAStructure::Dense(trans_table),
+                        sig_sets: Some(SSStructure::BooleanMap(table_reference)),
+                    });
+                }
                 //Finds ingoing end of board.
 
                 //Gets sig set of all boards with a single symbol added.
@@ -128,23 +134,31 @@ impl Solver for HashSolver {
             }
             if is_debug {
                 let dur = iter_begin_time.elapsed();
-                phase_events.send(dur).unwrap();
+                observer.notify(&SolverEvent::PhaseFinished {
+                    name: Self::PHASES[0],
+                    duration: dur,
+                });
+                observer.notify(&SolverEvent::IterationFinished {
+                    states: trans_table.len(),
+                    new_states: new_boards.len(),
+                });
             }
         }
         if is_debug {
-            dfa_events
-                .send((
-                    DFAStructure::Dense(trans_table.clone()),
-                    SSStructure::BooleanMap(table_reference.clone()),
-                ))
-                .unwrap();
+            observer.notify(&SolverEvent::PartialDFA(
+                DFAStructure::Dense(trans_table.clone()),
+                SSStructure::BooleanMap(table_reference.clone()),
+            ));
+            observer.notify(&SolverEvent::Finished {
+                states: trans_table.len(),
+            });
         }
-        DFA {
+        Ok(DFA {
             state_transitions: trans_table,
             accepting_states: accepting_states,
             starting_state: 0,
             symbol_set: self.rules.symbol_set.clone(),
-        }
+        })
     }
 }
 
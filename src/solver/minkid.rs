@@ -15,8 +15,11 @@ use petgraph::{
 
 use crate::util::{Ruleset, SymbolIdx, DFA};
 
-use super::{DFAStructure, Instant, SRSSolver, SSStructure, Solver};
-use crate::solver::srssolver::DomainError;
+use super::{
+    CancelToken, Cancelled, DFAStructure, Instant, SRSSolver, SSStructure, Solver, SolverEvent,
+    SolverObserver,
+};
+use crate::solver::srssolver::{rule_hash, DomainError};
 
 #[derive(Debug, Clone, Default)]
 struct SignatureSetElement {
@@ -58,7 +61,7 @@ impl SRSSolver for MinkidSolver {
     }
 
     fn new(mut ruleset: Ruleset, mut goal: DFA) -> Result<Self, DomainError> {
-        Self::ensure_expansion(&mut ruleset, &mut goal);
+        Self::ensure_expansion(&mut ruleset, &mut goal)?;
         let (min_input, max_input) = MinkidSolver::sized_init(&ruleset);
         Ok(MinkidSolver {
             goal: goal,
@@ -87,28 +90,28 @@ impl Solver for MinkidSolver {
         "Remove duplicates",
     ];
 
-    fn evaluate<'a, 'b>(&'a self, state: &'b Vec<u8>) -> bool {
+    fn evaluate<'a, 'b>(&'a self, state: &'b Vec<SymbolIdx>) -> bool {
         todo!();
     }
 
-    fn mutate(&self, state: Vec<u8>, input: SymbolIdx) -> Vec<u8> {
+    fn mutate(&self, state: Vec<SymbolIdx>, input: SymbolIdx) -> Vec<SymbolIdx> {
         SRSSolver::mutate(self, state, input)
     }
 
     fn run_internal(
         mut self,
         sig_k: usize,
-        is_debug: bool,
-        dfa_events: std::sync::mpsc::Sender<(DFAStructure, SSStructure)>,
-        phase_events: std::sync::mpsc::Sender<std::time::Duration>,
         origin: Vec<SymbolIdx>,
-    ) -> DFA {
+        observer: &mut dyn SolverObserver,
+        cancel: CancelToken,
+    ) -> Result<DFA, Cancelled> {
         let init_begin_time = Instant::now();
+        let is_debug = observer.is_listening();
         //graph of connections based on LHS->RHS links for all states
         //Usize is index in trans_table
 
         assert!(
-            core::cmp::PartialEq::<Vec<u8>>::eq(&origin, &vec![]),
+            core::cmp::PartialEq::<Vec<SymbolIdx>>::eq(&origin, &vec![]),
             "doesn't support non-null origin (sorry!)"
         );
 
@@ -116,7 +119,8 @@ impl Solver for MinkidSolver {
         self.build_ss_link_graph(sig_set);
         let real_self = Arc::new(self);
         let mut dfa_graph = DiGraph::<RwLock<MKDFAState>, SymbolIdx>::new();
-        let mut link_graph = DiGraph::<(), (Vec<SymbolIdx>, Vec<SymbolIdx>)>::new();
+        //Links carry the obligations each side has left, and whether the string has to end right after them
+        let mut link_graph = DiGraph::<(), (Vec<SymbolIdx>, Vec<SymbolIdx>, bool)>::new();
         dfa_graph.add_node(RwLock::new(MKDFAState {
             minkids: real_self.goal_minkids[real_self.goal.starting_state].clone(),
             goal_states: vec![real_self.goal.starting_state],
@@ -127,15 +131,19 @@ impl Solver for MinkidSolver {
         let mut iteration_lens = vec![0, 1];
 
         if is_debug {
-            let _ = phase_events.send(Instant::now() - init_begin_time);
+            observer.notify(&SolverEvent::Initialized {
+                duration: init_begin_time.elapsed(),
+            });
         }
 
         //While new elements are actually getting added to the DFA
         while iteration_lens[iteration_lens.len() - 2] < iteration_lens[iteration_lens.len() - 1] {
+            if cancel.is_cancelled() {
+                return Err(real_self.cancelled(&dfa_graph, sig_set));
+            }
             if is_debug {
-                dfa_events
-                    .send(real_self.translate_to_debug(&dfa_graph, &sig_set))
-                    .unwrap();
+                let (partial_dfa, sig_sets) = real_self.translate_to_debug(&dfa_graph, sig_set);
+                observer.notify(&SolverEvent::PartialDFA(partial_dfa, sig_sets));
             }
             let mut last_time = Instant::now();
             //First, adding all prospective DFA elements
@@ -199,14 +207,19 @@ impl Solver for MinkidSolver {
             {
                 //Root node that prospective state will be connected to
                 let start_node = NodeIndex::new(start_idx);
-                for rule_list in &real_self.rules.rules {
-                    let lhs_str = rule_list.0;
-                    for rhs_str in rule_list.1 {
+                for (anchor, lhs_str, rhs_list) in real_self.rules.all_rules() {
+                    //Rules anchored to the start only apply from the root, and nothing can come after
+                    //a rule anchored to the end, so its links only hold when the string ends there
+                    if anchor.is_some_and(|anchor| anchor.at_start()) && start_idx != 0 {
+                        continue;
+                    }
+                    let at_end = anchor.is_some_and(|anchor| anchor.at_end());
+                    for rhs_str in rhs_list {
                         let mut lhs = vec![start_node];
                         let mut rhs = vec![start_node];
                         let mut p_rule_len = 1;
                         while p_rule_len <= lhs_str.len() || p_rule_len <= rhs_str.len() {
-                            let mut potential_lhs_e = vec![];
+                            let mut potential_lhs = vec![];
 
                             for lhs_e in &lhs {
                                 if p_rule_len <= lhs_str.len() {
@@ -214,16 +227,18 @@ impl Solver for MinkidSolver {
                                         .edges_directed(*lhs_e, Outgoing)
                                         .find(|x| *x.weight() == lhs_str[p_rule_len - 1])
                                     {
-                                        potential_lhs_e.push(e)
+                                        potential_lhs.push(e.target())
                                     }
+                                } else if at_end {
+                                    potential_lhs.push(*lhs_e);
                                 } else {
-                                    potential_lhs_e.append(
-                                        &mut dfa_graph.edges_directed(*lhs_e, Outgoing).collect(),
+                                    potential_lhs.extend(
+                                        dfa_graph.edges_directed(*lhs_e, Outgoing).map(|e| e.target()),
                                     );
                                 };
                             }
 
-                            let mut potential_rhs_e = vec![];
+                            let mut potential_rhs = vec![];
 
                             for rhs_e in &rhs {
                                 if p_rule_len <= rhs_str.len() {
@@ -231,19 +246,19 @@ impl Solver for MinkidSolver {
                                         .edges_directed(*rhs_e, Outgoing)
                                         .find(|x| *x.weight() == rhs_str[p_rule_len - 1])
                                     {
-                                        potential_rhs_e.push(e)
+                                        potential_rhs.push(e.target())
                                     }
+                                } else if at_end {
+                                    potential_rhs.push(*rhs_e);
                                 } else {
-                                    potential_rhs_e.append(
-                                        &mut dfa_graph.edges_directed(*rhs_e, Outgoing).collect(),
+                                    potential_rhs.extend(
+                                        dfa_graph.edges_directed(*rhs_e, Outgoing).map(|e| e.target()),
                                     );
                                 };
                             }
-                            if potential_lhs_e.is_empty() || potential_rhs_e.is_empty() {
+                            if potential_lhs.is_empty() || potential_rhs.is_empty() {
                                 break;
                             }
-                            lhs.clear();
-                            rhs.clear();
                             let lhs_strip = if p_rule_len < lhs_str.len() {
                                 &lhs_str[p_rule_len..]
                             } else {
@@ -254,23 +269,20 @@ impl Solver for MinkidSolver {
                             } else {
                                 &rhs_str[..0]
                             };
-                            for potential_lhs_edge in &potential_lhs_e {
-                                for potential_rhs_edge in &potential_rhs_e {
+                            for potential_lhs_node in &potential_lhs {
+                                for potential_rhs_node in &potential_rhs {
                                     real_self.add_link(
                                         &mut link_graph,
-                                        potential_lhs_edge.target(),
-                                        potential_rhs_edge.target(),
+                                        *potential_lhs_node,
+                                        *potential_rhs_node,
                                         lhs_strip,
                                         rhs_strip,
+                                        at_end,
                                     );
                                 }
                             }
-                            for potential_lhs_edge in potential_lhs_e {
-                                lhs.push(potential_lhs_edge.target());
-                            }
-                            for potential_rhs_edge in potential_rhs_e {
-                                rhs.push(potential_rhs_edge.target());
-                            }
+                            lhs = potential_lhs;
+                            rhs = potential_rhs;
                             p_rule_len += 1;
                         }
                     }
@@ -278,9 +290,15 @@ impl Solver for MinkidSolver {
             }
             if is_debug {
                 let dur = last_time.elapsed();
-                phase_events.send(dur).unwrap();
+                observer.notify(&SolverEvent::PhaseFinished {
+                    name: Self::PHASES[0],
+                    duration: dur,
+                });
                 last_time = Instant::now();
             }
+            if cancel.is_cancelled() {
+                return Err(real_self.cancelled(&dfa_graph, sig_set));
+            }
             //Realized I am dumb as bricks! We need to propagate pure connections!!!
             //DUH!!!!!
             //Currently crawls the entire fucking link graph bc i am dumb and tired and really curious
@@ -292,6 +310,7 @@ impl Solver for MinkidSolver {
                     let target = link_graph.edge_endpoints(real_edge).unwrap().1;
                     if !(link_graph[real_edge].0.is_empty()
                         && link_graph[real_edge].1.is_empty()
+                        && !link_graph[real_edge].2
                         && target.index() < iteration_lens[iteration_lens.len() - 1])
                     {
                         continue;
@@ -329,10 +348,15 @@ impl Solver for MinkidSolver {
                                     rhs_extension,
                                     &vec![][..],
                                     &vec![][..],
+                                    false,
                                 )
                                 .0
                             {
-                                link_graph.add_edge(lhs_extension, rhs_extension, (vec![], vec![]));
+                                link_graph.add_edge(
+                                    lhs_extension,
+                                    rhs_extension,
+                                    (vec![], vec![], false),
+                                );
                                 propagation_pairs.push((lhs_extension, rhs_extension));
                             }
                         }
@@ -342,9 +366,15 @@ impl Solver for MinkidSolver {
 
             if is_debug {
                 let dur = last_time.elapsed();
-                phase_events.send(dur).unwrap();
+                observer.notify(&SolverEvent::PhaseFinished {
+                    name: Self::PHASES[1],
+                    duration: dur,
+                });
                 last_time = Instant::now();
             }
+            if cancel.is_cancelled() {
+                return Err(real_self.cancelled(&dfa_graph, sig_set));
+            }
             /*
             let mut debug_link_graph : DiGraph<String,(Vec<SymbolIdx>,Vec<SymbolIdx>)> = Graph::new();
             for i in 0..link_graph.node_count() {
@@ -438,12 +468,44 @@ impl Solver for MinkidSolver {
             }
             dfa_graph = Arc::into_inner(dfa_arc).unwrap();
             link_graph = Arc::into_inner(link_arc).unwrap();
+            //The signature set link graph can't use rules anchored to the start, which leaves the root short.
+            //Its children already account for them though, so the root gets c + s for every s its child on c has.
+            if iteration_lens.len() == 2
+                && real_self
+                    .rules
+                    .anchored_rules
+                    .keys()
+                    .any(|(anchor, _)| anchor.at_start())
+            {
+                let root = NodeIndex::new(0);
+                let mut root_minkids = HashSet::new();
+                for edge in dfa_graph.edges_directed(root, Outgoing) {
+                    let child_set = real_self
+                        .minkids_to_tt(sig_set, &dfa_graph[edge.target()].read().unwrap().minkids);
+                    for ss_idx in child_set.iter_ones() {
+                        let mut element = vec![*edge.weight()];
+                        element.extend(&sig_set[ss_idx]);
+                        let element_idx = real_self.rules.symbol_set.find_in_sig_set(element.iter());
+                        if element_idx < sig_set.len() {
+                            root_minkids.insert(real_self.ss_idx_to_link[element_idx]);
+                        }
+                    }
+                }
+                real_self
+                    .add_set_to_minkids(&mut dfa_graph[root].write().unwrap().minkids, &root_minkids);
+            }
             if is_debug {
                 let dur = last_time.elapsed();
-                phase_events.send(dur).unwrap();
+                observer.notify(&SolverEvent::PhaseFinished {
+                    name: Self::PHASES[2],
+                    duration: dur,
+                });
                 last_time = Instant::now();
             }
 
+            if cancel.is_cancelled() {
+                return Err(real_self.cancelled(&dfa_graph, sig_set));
+            }
             //Now, prune duplicates. Notably, there's no implementation of Hash on HashSets (extremely surprising to me), so hopefully this garbo solution doesn't take forever
             let mut new_count = 0;
             let mut prospective_state = *iteration_lens.last().unwrap();
@@ -489,6 +551,7 @@ impl Solver for MinkidSolver {
                                     equiv,
                                     &rust_scared.0[..],
                                     &rust_scared.1[..],
+                                    rust_scared.2,
                                 )
                                 .1
                             {
@@ -507,6 +570,7 @@ impl Solver for MinkidSolver {
                                     target,
                                     &rust_scared.0[..],
                                     &rust_scared.1[..],
+                                    rust_scared.2,
                                 )
                                 .1
                             {
@@ -526,7 +590,15 @@ impl Solver for MinkidSolver {
             iteration_lens.push(dfa_graph.node_count());
             if is_debug {
                 let dur = last_time.elapsed();
-                phase_events.send(dur).unwrap();
+                observer.notify(&SolverEvent::PhaseFinished {
+                    name: Self::PHASES[3],
+                    duration: dur,
+                });
+                observer.notify(&SolverEvent::IterationFinished {
+                    states: iteration_lens[iteration_lens.len() - 1],
+                    new_states: iteration_lens[iteration_lens.len() - 1]
+                        - iteration_lens[iteration_lens.len() - 2],
+                });
             }
             //Oh god is that it?
             //I am terrified of facing the music
@@ -534,9 +606,11 @@ impl Solver for MinkidSolver {
             //Actually working pass 8/5 (i'll admit, I took a weeklong break. Still pretty brutal tho)
         }
         if is_debug {
-            dfa_events
-                .send(real_self.translate_to_debug(&dfa_graph, &sig_set))
-                .unwrap();
+            let (partial_dfa, sig_sets) = real_self.translate_to_debug(&dfa_graph, sig_set);
+            observer.notify(&SolverEvent::PartialDFA(partial_dfa, sig_sets));
+            observer.notify(&SolverEvent::Finished {
+                states: dfa_graph.node_count(),
+            });
         }
         let mut trans_table =
             vec![vec![0; real_self.rules.symbol_set.length]; dfa_graph.node_count()];
@@ -551,12 +625,12 @@ impl Solver for MinkidSolver {
                 real_self.ss_idx_to_link[0],
             ));
         }
-        DFA {
+        Ok(DFA {
             state_transitions: trans_table,
             accepting_states: accepting_states,
             starting_state: 0,
             symbol_set: real_self.rules.symbol_set.clone(),
-        }
+        })
     }
 }
 
@@ -570,7 +644,8 @@ impl MinkidSolver {
             ss_link_graph.add_node(i);
         }
         for i in 0..sig_set.len() {
-            for result in self.single_rule_hash(&sig_set[i]) {
+            //Signature elements always come after something, so rules anchored to the start never apply to them
+            for result in rule_hash(&self.rules, &sig_set[i], false) {
                 let target_idx = self.rules.symbol_set.find_in_sig_set(result.iter());
                 if target_idx < sig_set.len() {
                     ss_link_graph.add_edge(NodeIndex::new(i), NodeIndex::new(target_idx), ());
@@ -737,7 +812,7 @@ impl MinkidSolver {
         self: &Arc<Self>,
         dfa_graph: &DiGraph<RwLock<MKDFAState>, SymbolIdx>,
         sig_set: &Vec<Vec<SymbolIdx>>,
-        connection: &(Vec<SymbolIdx>, Vec<SymbolIdx>),
+        connection: &(Vec<SymbolIdx>, Vec<SymbolIdx>, bool),
         lhs: NodeIndex,
         rhs: NodeIndex,
     ) -> bool {
@@ -761,6 +836,7 @@ impl MinkidSolver {
                     //If the ss element is actually big enough to comply with the obligation, and does
                     if sig_set[*ss_idx].len() >= connection.1.len()
                         && sig_set[*ss_idx][0..connection.1.len()] == connection.1
+                        && (!connection.2 || sig_set[*ss_idx].len() == connection.1.len())
                     {
                         //Build what the new element would look like
                         let mut new_ss = connection.0.clone();
@@ -798,11 +874,12 @@ impl MinkidSolver {
 
     fn add_link(
         self: &Arc<Self>,
-        link_graph: &mut DiGraph<(), (Vec<SymbolIdx>, Vec<SymbolIdx>)>,
+        link_graph: &mut DiGraph<(), (Vec<SymbolIdx>, Vec<SymbolIdx>, bool)>,
         lhs: NodeIndex,
         rhs: NodeIndex,
         lhs_obligation: &[SymbolIdx],
         rhs_obligation: &[SymbolIdx],
+        at_end: bool,
     ) -> (bool, bool) {
         let mut death_row = vec![];
         let mut should_add = true;
@@ -811,6 +888,10 @@ impl MinkidSolver {
         //1. we make any of them redundant by offering a more flexible alternative
         //2. any of them make our potential link redundant by already being more flexible
         for edge in link_graph.edges_connecting(lhs, rhs) {
+            //Links that need the string to end are only compared with each other
+            if edge.weight().2 != at_end {
+                continue;
+            }
             //redundancy check!
 
             let lhs_min = std::cmp::min(lhs_obligation.len(), edge.weight().0.len());
@@ -833,7 +914,11 @@ impl MinkidSolver {
         }
         if should_add {
             //This has made me realize these could definitely just be references... but whatever!
-            link_graph.add_edge(lhs, rhs, (lhs_obligation.to_vec(), rhs_obligation.to_vec()));
+            link_graph.add_edge(
+                lhs,
+                rhs,
+                (lhs_obligation.to_vec(), rhs_obligation.to_vec(), at_end),
+            );
         }
         for dead_edge in &death_row {
             link_graph.remove_edge(*dead_edge);
@@ -842,7 +927,7 @@ impl MinkidSolver {
         //This should only be possible if there's extraneous elements.
         (should_add, !death_row.is_empty())
     }
-    fn minkids_to_tt(&self, sig_set: &Vec<Vec<SymbolIdx>>, minkids: &HashSet<NodeIndex>) -> BitVec {
+    fn minkids_to_tt(&self, sig_set: &[Vec<SymbolIdx>], minkids: &HashSet<NodeIndex>) -> BitVec {
         let mut result = bitvec![0;sig_set.len()];
         let reversed_graph = petgraph::visit::Reversed(&self.ss_link_graph);
         let mut dfs = Dfs::empty(&reversed_graph);
@@ -856,15 +941,26 @@ impl MinkidSolver {
         }
         result
     }
+    fn cancelled(
+        &self,
+        dfa_graph: &DiGraph<RwLock<MKDFAState>, SymbolIdx>,
+        sig_set: &[Vec<SymbolIdx>],
+    ) -> Cancelled {
+        let (dfa, sig_sets) = self.translate_to_debug(dfa_graph, sig_set);
+        Cancelled {
+            dfa,
+            sig_sets: Some(sig_sets),
+        }
+    }
     fn translate_to_debug(
         &self,
         dfa_graph: &DiGraph<RwLock<MKDFAState>, SymbolIdx>,
-        sig_set: &Vec<Vec<SymbolIdx>>,
+        sig_set: &[Vec<SymbolIdx>],
     ) -> (DFAStructure, SSStructure) {
         let mut minkids_debug = vec![];
         for node in dfa_graph.node_indices() {
             minkids_debug
-                .push(self.minkids_to_tt(&sig_set, &dfa_graph[node].read().unwrap().minkids));
+                .push(self.minkids_to_tt(sig_set, &dfa_graph[node].read().unwrap().minkids));
         }
         (
             DFAStructure::Graph(dfa_graph.map(|_, _| (), |_, x| *x).clone()),
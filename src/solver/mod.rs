@@ -22,6 +22,8 @@ mod solver;
 pub use self::solver::*;
 mod srssolver;
 pub use self::srssolver::*;
+mod proof;
+pub use self::proof::*;
 mod genericsolver;
 pub use self::genericsolver::*;
 #[cfg(target_arch = "wasm32")]
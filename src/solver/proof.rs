@@ -0,0 +1,404 @@
+use std::collections::{HashMap, HashSet};
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs::File;
+
+use serde::{Deserialize, Serialize};
+
+use crate::util::{Ruleset, DFA};
+
+use super::srssolver::{
+    build_no_rule_dfa, build_path_graph, ensure_expansion, has_rule_cycle, terminal_mismatch, Path,
+};
+
+//The record produced by SRSSolver::correct_audit.
+//The first steps always assert the states of the expanded DFA, so State(i) is step i.
+//Serializing it gives a proof certificate that verify_certificate can check without a solver.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(from = "ProofCertificate")]
+pub struct ProofAudit {
+    pub steps: Vec<ProofStep>,
+    //Derived from the steps, so it's rebuilt on load rather than trusted
+    #[serde(skip)]
+    properties: Vec<HashSet<ProofProperty>>,
+}
+
+#[derive(Deserialize)]
+struct ProofCertificate {
+    steps: Vec<ProofStep>,
+}
+
+impl From<ProofCertificate> for ProofAudit {
+    fn from(certificate: ProofCertificate) -> Self {
+        let mut audit = ProofAudit::new();
+        for step in certificate.steps {
+            audit.add_step(step, false);
+        }
+        audit
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum ProofStepRationale {
+    Assertion,
+    RejectingExit,
+    AcceptingExit,
+    RejectingLooping,
+    AcceptingLooping,
+    EquivalentSet,
+    MatchesRegex,
+}
+
+pub type ProofIndex = usize;
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub enum ProofElement {
+    AddProperty(ProofProperty, Vec<ProofIndex>),
+    State(usize),
+    Path(ProofIndex, Vec<ProofIndex>),
+}
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub enum ProofProperty {
+    Accepting(bool),
+    Correct,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct ProofStep {
+    pub element: ProofElement,
+    pub sources: HashSet<ProofIndex>,
+    pub reason: ProofStepRationale,
+}
+
+impl std::fmt::Display for ProofAudit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for i in &self.steps {
+            if let ProofElement::AddProperty(ref prop, ref affected) = i.element {
+                write!(f, "Add Property {:?} to ", prop)?;
+                for affect in affected {
+                    write!(f, "{:?}", self.steps[*affect].element)?;
+                    if affect != affected.last().unwrap() {
+                        write!(f, ", ")?;
+                    }
+                }
+                write!(f, " | ")?;
+            } else {
+                write!(f, "{:?} | ", i.element)?;
+            };
+            write!(f, "Using {:?} Rule | Reasons: ", i.reason)?;
+            for source in &i.sources {
+                write!(f, "{:?}, ", self.steps[*source].element)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl ProofAudit {
+    pub(crate) fn new() -> Self {
+        ProofAudit {
+            steps: vec![],
+            properties: vec![],
+        }
+    }
+    pub(crate) fn add_step(&mut self, proof_step: ProofStep, should_emit: bool) {
+        if let ProofElement::AddProperty(ref prop, ref affected) = proof_step.element {
+            for i in affected {
+                //Certificates are loaded from outside, so a bad index is left for verify_certificate to report
+                if let Some(props) = self.properties.get_mut(*i) {
+                    props.insert(prop.clone());
+                }
+            }
+        }
+        if should_emit {
+            println!("{:?}", proof_step);
+        }
+        self.steps.push(proof_step);
+        self.properties.push(HashSet::new());
+    }
+    pub(crate) fn len(&self) -> usize {
+        self.steps.len()
+    }
+    pub fn find_element(&self, desired_element: ProofElement) -> Option<usize> {
+        self.steps.iter().position(|x| x.element == desired_element)
+    }
+    pub fn get_props(&self, step_idx: ProofIndex) -> HashSet<ProofProperty> {
+        self.properties[step_idx].clone()
+    }
+    //Whether every state in the audit was proven correct, i.e. the audit proves the DFA correct
+    pub fn is_complete(&self) -> bool {
+        self.steps
+            .iter()
+            .enumerate()
+            .filter(|(_, step)| matches!(step.element, ProofElement::State(_)))
+            .all(|(idx, _)| self.properties[idx].contains(&ProofProperty::Correct))
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(file: &mut File) -> serde_json::Result<Self> {
+        use std::io::BufReader;
+        serde_json::from_reader(BufReader::new(file))
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save(&self, file: &mut File) -> serde_json::Result<()> {
+        let writer = std::io::BufWriter::new(file);
+        serde_json::to_writer(writer, self)
+    }
+}
+
+//Whether a state of the expanded DFA can be proven correct by comparing it against the goal directly:
+//either it's accepting and everything that ends there is in the goal,
+//or it's rejecting, terminal, and nothing that ends there is in the goal
+pub(crate) fn matches_goal(
+    expanded_dfa: &DFA,
+    goal: &DFA,
+    path_graph: &[Vec<Path>],
+    state: usize,
+) -> bool {
+    let mut regex_clone = expanded_dfa.clone();
+    let mut temp_accepting = vec![false; expanded_dfa.state_transitions.len()];
+    temp_accepting[state] = true;
+    regex_clone.accepting_states = temp_accepting;
+    if expanded_dfa.accepting_states[state] {
+        return &regex_clone <= goal;
+    }
+    let is_terminal = path_graph[state]
+        .iter()
+        .all(|f| f.rhs_connections.is_empty());
+    //&(goal & !regex_clone) == goal is equivalent to regex_clone.intersection(goal) == empty_set
+    is_terminal && &(goal & &!&regex_clone) == goal
+}
+
+//Why verify_certificate rejected a certificate.
+//step is None when the steps are fine on their own but don't add up to a proof.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CertificateError {
+    pub step: Option<ProofIndex>,
+    pub message: String,
+}
+
+impl std::fmt::Display for CertificateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.step {
+            Some(step) => write!(f, "Step {}: {}", step, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+//Re-checks a proof certificate against the ruleset and goal without running or trusting a solver.
+//The DFA has to pass the terminal string and rule graph checks is_correct makes first.
+//The expanded DFA and its path graph are rebuilt from scratch, then every step is replayed,
+//checking its rationale against only the properties established by earlier steps.
+pub fn verify_certificate(
+    ruleset: &Ruleset,
+    goal: &DFA,
+    dfa: &DFA,
+    certificate: &ProofAudit,
+) -> Result<(), CertificateError> {
+    let fail = |step: Option<ProofIndex>, message: String| Err(CertificateError { step, message });
+
+    let mut ruleset = ruleset.clone();
+    let mut goal = goal.clone();
+    if let Err(e) = ensure_expansion(&mut ruleset, &mut goal) {
+        return fail(None, e.to_string(&goal.symbol_set));
+    }
+    if dfa.symbol_set != goal.symbol_set {
+        return fail(
+            None,
+            "The DFA's symbol set doesn't match the ruleset and goal".to_owned(),
+        );
+    }
+
+    //The same checks is_correct makes before looking at the proof itself
+    let no_rule_dfa = build_no_rule_dfa(&ruleset, &goal);
+    if let Some(string) = terminal_mismatch(&no_rule_dfa, &goal, dfa) {
+        return fail(
+            None,
+            format!(
+                "The DFA and goal disagree on {}, which no rules apply to",
+                goal.symbol_set.symbols_to_string(&string)
+            ),
+        );
+    }
+    let expanded_dfa = dfa.dfa_product(&no_rule_dfa, |s, _o| *s);
+    if has_rule_cycle(&ruleset, &expanded_dfa) {
+        return fail(
+            None,
+            "The rule graph has a cycle, so the DFA isn't minimal".to_owned(),
+        );
+    }
+    let path_graph = build_path_graph(&ruleset, &goal, &expanded_dfa, false);
+    let state_count = expanded_dfa.state_transitions.len();
+
+    let mut properties: Vec<HashSet<ProofProperty>> = vec![];
+    let mut path_steps: HashMap<(usize, Vec<usize>), ProofIndex> = HashMap::new();
+    //Every path the solver would have found; the certificate may only assert these
+    let mut real_paths = HashSet::new();
+    for (lhs, state_paths) in path_graph.iter().enumerate() {
+        for path in state_paths {
+            real_paths.insert((lhs, path.rhs_connections.clone()));
+        }
+    }
+
+    let has = |properties: &Vec<HashSet<ProofProperty>>, idx: ProofIndex, prop: &ProofProperty| {
+        properties[idx].contains(prop)
+    };
+    let proven_as = |properties: &Vec<HashSet<ProofProperty>>, idx: ProofIndex, accepting: bool| {
+        has(properties, idx, &ProofProperty::Correct)
+            && has(properties, idx, &ProofProperty::Accepting(accepting))
+    };
+
+    for (step_idx, step) in certificate.steps.iter().enumerate() {
+        let here = Some(step_idx);
+        if let Some(source) = step.sources.iter().find(|s| **s >= step_idx) {
+            return fail(
+                here,
+                format!("Cites step {}, which doesn't come before it", source),
+            );
+        }
+        //States have to come first, in order, so that state indices and step indices line up
+        if (step_idx < state_count) != matches!(step.element, ProofElement::State(_)) {
+            return fail(
+                here,
+                format!("The first {} steps must be exactly the states", state_count),
+            );
+        }
+        match &step.element {
+            ProofElement::State(state) => {
+                if *state != step_idx || step.reason != ProofStepRationale::Assertion {
+                    return fail(here, format!("q{} is asserted out of order", state));
+                }
+            }
+            ProofElement::Path(lhs, rhs_connections) => {
+                if step.reason != ProofStepRationale::Assertion {
+                    return fail(here, "Paths can only be asserted".to_owned());
+                }
+                let key = (*lhs, rhs_connections.clone());
+                if !real_paths.contains(&key) {
+                    return fail(
+                        here,
+                        format!("q{} has no path to {:?}", lhs, rhs_connections),
+                    );
+                }
+                if path_steps.insert(key, step_idx).is_some() {
+                    return fail(here, "The same path is asserted twice".to_owned());
+                }
+            }
+            ProofElement::AddProperty(prop, targets) => {
+                for target in targets {
+                    if *target >= step_idx {
+                        return fail(
+                            here,
+                            format!("Targets step {}, which doesn't come before it", target),
+                        );
+                    }
+                    let checked = match (prop, step.reason, &certificate.steps[*target].element) {
+                        (
+                            ProofProperty::Accepting(accepting),
+                            ProofStepRationale::Assertion,
+                            ProofElement::State(state) | ProofElement::Path(state, _),
+                        ) => expanded_dfa.accepting_states[*state] == *accepting,
+                        (
+                            ProofProperty::Correct,
+                            ProofStepRationale::MatchesRegex,
+                            ProofElement::State(state),
+                        ) => matches_goal(&expanded_dfa, &goal, &path_graph, *state),
+                        //A state is correct if all of its paths are, and the paths of a correct state are correct
+                        (
+                            ProofProperty::Correct,
+                            ProofStepRationale::EquivalentSet,
+                            ProofElement::State(state),
+                        ) => path_graph[*state].iter().all(|path| {
+                            path_steps
+                                .get(&(*state, path.rhs_connections.clone()))
+                                .is_some_and(|idx| has(&properties, *idx, &ProofProperty::Correct))
+                        }),
+                        (
+                            ProofProperty::Correct,
+                            ProofStepRationale::EquivalentSet,
+                            ProofElement::Path(lhs, _),
+                        ) => has(&properties, *lhs, &ProofProperty::Correct),
+                        (
+                            ProofProperty::Correct,
+                            ProofStepRationale::AcceptingExit,
+                            ProofElement::Path(lhs, rhs_connections),
+                        ) => {
+                            expanded_dfa.accepting_states[*lhs]
+                                && rhs_connections
+                                    .iter()
+                                    .any(|state| proven_as(&properties, *state, true))
+                        }
+                        (
+                            ProofProperty::Correct,
+                            ProofStepRationale::RejectingExit,
+                            ProofElement::Path(lhs, rhs_connections),
+                        ) => {
+                            !expanded_dfa.accepting_states[*lhs]
+                                && !rhs_connections.contains(lhs)
+                                && rhs_connections
+                                    .iter()
+                                    .all(|state| proven_as(&properties, *state, false))
+                        }
+                        (
+                            ProofProperty::Correct,
+                            reason @ (ProofStepRationale::AcceptingLooping
+                            | ProofStepRationale::RejectingLooping),
+                            ProofElement::Path(lhs, rhs_connections),
+                        ) => {
+                            let accepting = expanded_dfa.accepting_states[*lhs];
+                            let exits_proven = path_graph[*lhs]
+                                .iter()
+                                .filter(|path| !path.rhs_connections.contains(lhs))
+                                .all(|path| {
+                                    path_steps
+                                        .get(&(*lhs, path.rhs_connections.clone()))
+                                        .is_some_and(|idx| {
+                                            has(&properties, *idx, &ProofProperty::Correct)
+                                        })
+                                });
+                            //A rejecting loop also can't lead anywhere that might accept
+                            let loops_reject = accepting
+                                || path_graph[*lhs].iter().all(|path| {
+                                    !path.rhs_connections.contains(lhs)
+                                        || path.rhs_connections.iter().all(|state| {
+                                            state == lhs || proven_as(&properties, *state, false)
+                                        })
+                                });
+                            rhs_connections.contains(lhs)
+                                && accepting == (reason == ProofStepRationale::AcceptingLooping)
+                                && exits_proven
+                                && loops_reject
+                        }
+                        (_, reason, element) => {
+                            return fail(
+                                here,
+                                format!("{:?} can't add {:?} to {:?}", reason, prop, element),
+                            );
+                        }
+                    };
+                    if !checked {
+                        return fail(
+                            here,
+                            format!(
+                                "{:?} doesn't justify adding {:?} to {:?}",
+                                step.reason, prop, certificate.steps[*target].element
+                            ),
+                        );
+                    }
+                }
+                for target in targets {
+                    properties[*target].insert(prop.clone());
+                }
+            }
+        }
+        properties.push(HashSet::new());
+    }
+
+    for state in 0..state_count {
+        if state >= properties.len() || !properties[state].contains(&ProofProperty::Correct) {
+            return fail(None, format!("q{} is never proven correct", state));
+        }
+    }
+    Ok(())
+}
@@ -1,7 +1,7 @@
 use std::{
-    io::{Write},
+    io::{self, Write},
     marker::PhantomData,
-    sync::mpsc::{channel, Receiver, Sender},
+    sync::mpsc::{channel, Receiver},
 };
 
 use std::thread;
@@ -28,100 +28,69 @@ where
         &self,
         sig_k: usize,
         origin: State,
+        cancel: CancelToken,
     ) -> (
-        Receiver<(DFAStructure, SSStructure)>,
-        Receiver<std::time::Duration>,
-        thread::JoinHandle<DFA<Input, Output>>,
+        Receiver<SolverEvent>,
+        thread::JoinHandle<Result<DFA<Input, Output>, Cancelled>>,
     ) {
         let self_clone = self.clone();
-        let (dfa_tx, dfa_rx) = channel();
-        let (phase_tx, phase_rx) = channel();
+        let (mut event_tx, event_rx) = channel();
         (
-            dfa_rx,
-            phase_rx,
-            thread::spawn(move || self_clone.run_internal(sig_k, true, dfa_tx, phase_tx, origin)),
+            event_rx,
+            thread::spawn(move || self_clone.run_internal(sig_k, origin, &mut event_tx, cancel)),
         )
     }
 
     //Changing the function signature based on the architecture is disgusting!
     //But ya know what -- so is the state of Rust WASM, so i'm making do.
     #[cfg(target_arch = "wasm32")]
-    fn run_debug(
-        &self,
-        sig_k: usize,
-        origin: State,
-    ) -> (
-        Receiver<(DFAStructure, SSStructure)>,
-        Receiver<std::time::Duration>,
-    ) {
+    fn run_debug(&self, sig_k: usize, origin: State, cancel: CancelToken) -> Receiver<SolverEvent> {
         let self_clone = self.clone();
-        let (dfa_tx, dfa_rx) = channel();
-        let (phase_tx, phase_rx) = channel();
+        let (mut event_tx, event_rx) = channel();
         wasm_bindgen_futures::spawn_local(async move {
-            self_clone.run_internal(sig_k, true, dfa_tx, phase_tx);
+            let _ = self_clone.run_internal(sig_k, origin, &mut event_tx, cancel);
         });
-        (dfa_rx, phase_rx)
+        event_rx
     }
     fn run_internal(
         self,
         sig_k: usize,
-        is_debug: bool,
-        dfa_events: Sender<(DFAStructure, SSStructure)>,
-        phase_events: Sender<std::time::Duration>,
         origin: State,
-    ) -> DFA<Input, Output>;
+        observer: &mut dyn SolverObserver,
+        cancel: CancelToken,
+    ) -> Result<DFA<Input, Output>, Cancelled>;
     fn run(&self, sig_k: usize, origin: State) -> DFA<Input, Output> {
-        let (dfa_tx, _dfa_rx) = channel();
-        let (phase_tx, _phase_rx) = channel();
-        self.clone()
-            .run_internal(sig_k, false, dfa_tx, phase_tx, origin)
+        match self.run_cancellable(sig_k, origin, CancelToken::new()) {
+            Ok(dfa) => dfa,
+            Err(_) => unreachable!("run was cancelled without a token"),
+        }
+    }
+    //Same as run, but gives up (returning whatever was built so far) once cancel is triggered
+    fn run_cancellable(
+        &self,
+        sig_k: usize,
+        origin: State,
+        cancel: CancelToken,
+    ) -> Result<DFA<Input, Output>, Cancelled> {
+        self.run_observed(sig_k, origin, &mut (), cancel)
+    }
+    //Runs on the current thread, reporting progress to observer as it goes
+    fn run_observed(
+        &self,
+        sig_k: usize,
+        origin: State,
+        observer: &mut dyn SolverObserver,
+        cancel: CancelToken,
+    ) -> Result<DFA<Input, Output>, Cancelled> {
+        self.clone().run_internal(sig_k, origin, observer, cancel)
     }
 
-    #[cfg(not(target_arch = "wasm32"))]
     fn run_with_print(&self, sig_k: usize, origin: State) -> DFA<Input, Output> {
-        use std::io;
-
-        let (dfa_rx, phase_rx, run_handle) = self.run_debug(sig_k, origin);
-        let mut phase_idx;
-        let mut phase_lens = vec![];
-        let mut iterations = 0;
-        let mut last_len = 0;
-        if let Ok(time) = phase_rx.recv() {
-            println!("Initialization time: {}ms", time.as_millis());
-        }
-        while let Ok((partial_dfa, _sig_sets)) = dfa_rx.recv() {
-            let mut update_string = format!(
-                "Iteration {} | {} states solved, {} new",
-                iterations,
-                partial_dfa.len(),
-                partial_dfa.len() - last_len
-            );
-            last_len = partial_dfa.len();
-            phase_idx = 0;
-            print!("{}\r", update_string);
-            io::stdout().flush().unwrap();
-            while phase_idx < Self::PHASES.len() {
-                //Disconnection is guaranteed here -- should send final DFA then dc on both channels
-                match phase_rx.recv() {
-                    Ok(time) => {
-                        phase_lens.push(time);
-                    }
-                    _ => break,
-                }
-
-                update_string.push_str(&format!(
-                    " | {}: {}ms",
-                    Self::PHASES[phase_idx],
-                    phase_lens.last().unwrap().as_millis()
-                ));
-                print!("{}\r", update_string);
-                io::stdout().flush().unwrap();
-                phase_idx += 1;
-            }
-            iterations += 1;
-            println!("{}", update_string);
+        let mut printer = PrintObserver::default();
+        match self.run_observed(sig_k, origin, &mut printer, CancelToken::new()) {
+            Ok(dfa) => dfa,
+            Err(_) => unreachable!("run was cancelled without a token"),
         }
-        run_handle.join().unwrap()
     }
     fn get_symset(&self) -> &SymbolSet<Input>;
     fn mutate(&self, state: State, input: SymbolIdx) -> State;
@@ -170,7 +139,7 @@ where
         };
         let mut rollover = self.cur_vec.len();
         while rollover > 0
-            && self.cur_vec[rollover - 1] == (self.solver.get_symset().length - 1) as u8
+            && self.cur_vec[rollover - 1] == (self.solver.get_symset().length - 1) as SymbolIdx
         {
             self.cur_vec[rollover - 1] = 0;
             rollover -= 1;
@@ -193,3 +162,38 @@ where
         result
     }
 }
+
+//Prints a line per iteration with how long each phase took
+#[derive(Default)]
+pub struct PrintObserver {
+    iteration: usize,
+    phase_times: String,
+}
+
+impl SolverObserver for PrintObserver {
+    fn notify(&mut self, event: &SolverEvent) {
+        match event {
+            SolverEvent::Initialized { duration } => {
+                println!("Initialization time: {}ms", duration.as_millis());
+            }
+            SolverEvent::PhaseFinished { name, duration } => {
+                self.phase_times
+                    .push_str(&format!(" | {}: {}ms", name, duration.as_millis()));
+                print!("Iteration {}{}\r", self.iteration, self.phase_times);
+                io::stdout().flush().unwrap();
+            }
+            SolverEvent::IterationFinished { states, new_states } => {
+                println!(
+                    "Iteration {} | {} states solved, {} new{}",
+                    self.iteration, states, new_states, self.phase_times
+                );
+                self.iteration += 1;
+                self.phase_times.clear();
+            }
+            SolverEvent::PartialDFA(_, _) => {}
+            SolverEvent::Finished { states } => {
+                println!("Finished with {} states", states);
+            }
+        }
+    }
+}
@@ -1,10 +1,9 @@
-use std::{
-    collections::{HashMap, HashSet, VecDeque},
-    hash::Hash,
-};
+use std::collections::{HashMap, HashSet, VecDeque};
 
 
-use crate::util::{Ruleset, SymbolIdx, SymbolSet, DFA};
+use crate::util::{
+    dot::escape, termination::derivation_to_string, Anchor, Ruleset, SymbolIdx, SymbolSet, DFA, NFA,
+};
 
 
 //mod generic_bases;
@@ -17,6 +16,7 @@ use petgraph::{
 pub use web_time::Instant;
 
 
+use super::proof::*;
 use super::solver::Solver;
 
 pub trait SRSSolver
@@ -55,21 +55,8 @@ where
             state
         };
 
-    fn ensure_expansion(ruleset: &mut Ruleset, goal: &mut DFA) {
-        if ruleset.symbol_set == goal.symbol_set {
-            return;
-        }
-        if ruleset.symbol_set.is_subset(&goal.symbol_set) {
-            ruleset.expand_to_symset(goal.symbol_set.clone());
-        } else if goal.symbol_set.is_subset(&ruleset.symbol_set) {
-            goal.expand_to_symset(ruleset.symbol_set.clone());
-        } else {
-            let mut merge_symbols = goal.symbol_set.representations.clone();
-            merge_symbols.append(&mut ruleset.symbol_set.representations.clone());
-            let merge_symset = SymbolSet::<String>::new(merge_symbols);
-            ruleset.expand_to_symset(merge_symset.clone());
-            goal.expand_to_symset(merge_symset);
-        }
+    fn ensure_expansion(ruleset: &mut Ruleset, goal: &mut DFA) -> Result<(), DomainError> {
+        ensure_expansion(ruleset, goal)
     }
 
     fn get_ruleset(&self) -> &Ruleset;
@@ -77,39 +64,7 @@ where
     fn get_goal(&self) -> &DFA;
 
     fn single_rule_hash(&self, start_board: &Vec<SymbolIdx>) -> Vec<Vec<SymbolIdx>> {
-        let mut result = vec![];
-        if start_board.is_empty() {
-            if let Some(new_swaps) = self.get_ruleset().rules.get(&start_board[..]) {
-                for new_swap in new_swaps {
-                    result.push(new_swap.clone());
-                }
-            }
-            return result;
-        }
-
-        for lftmst_idx in 0..start_board.len() {
-            for slice_length in self.get_ruleset().min_input
-                ..core::cmp::min(self.get_ruleset().max_input, start_board.len() - lftmst_idx) + 1
-            {
-                if let Some(new_swaps) = self
-                    .get_ruleset()
-                    .rules
-                    .get(&start_board[lftmst_idx..(lftmst_idx + slice_length)])
-                {
-                    let new_board = start_board[0..lftmst_idx].to_vec();
-
-                    for new_swap in new_swaps {
-                        let mut newest_board = new_board.clone();
-                        newest_board.extend(new_swap);
-                        newest_board.extend(
-                            start_board[lftmst_idx + slice_length..start_board.len()].to_vec(),
-                        );
-                        result.push(newest_board);
-                    }
-                }
-            }
-        }
-        result
+        rule_hash(self.get_ruleset(), start_board, true)
     }
     //returns an annotated list of all possible moves from a string
     //annotation is as follows: starting idx of rule application, len of lhs of rule used, len of rhs of rule used, resulting board.
@@ -117,7 +72,7 @@ where
         &self,
         start_board: &Vec<SymbolIdx>,
     ) -> Vec<(usize, usize, usize, Vec<SymbolIdx>)> {
-        let mut result = vec![];
+        let mut result = anchored_rule_hash(self.get_ruleset(), start_board, true);
         if start_board.is_empty() {
             if let Some(new_swaps) = self.get_ruleset().rules.get(&start_board[..]) {
                 for new_swap in new_swaps {
@@ -153,8 +108,8 @@ where
     fn sized_init(rules: &Ruleset) -> (usize, usize) {
         let mut min_input: usize = usize::MAX;
         let mut max_input: usize = 0;
-        for i in &rules.rules {
-            let input_len = i.0.len();
+        for i in rules.all_rules() {
+            let input_len = i.1.len();
             if input_len < min_input {
                 min_input = input_len;
             }
@@ -239,89 +194,52 @@ where
         }
     }
     fn build_rule_graph<'a>(&'a self, possible_dfa: &'a DFA) -> DiGraph<usize, RuleGraphRoot<'a>> {
-        let mut rule_graph = DiGraph::<usize, RuleGraphRoot>::new();
-        //Add a node in the rule graph for each state
-        for index in 0..possible_dfa.state_transitions.len() {
-            rule_graph.add_node(index);
-        }
-        //See what single rule applications are possible when starting from any state in the DFA
-        //Example -- create a path between (q0, 110) and (q0,001) if one does not exist
-        for origin in 0..possible_dfa.state_transitions.len() {
-            //For each state
-            for rule_list in &self.get_ruleset().rules {
-                //For each rule
-                let lhs = rule_list.0;
-                //Drawing an arrow from LHS to RHS
-                //State at LHS = parent
-                //State at RHS = child
-
-                //Determine the state the LHS goes to from the origin (e.g. (q0, 110))
-                let mut parent = origin;
-                for i in 0..lhs.len() {
-                    parent = possible_dfa.state_transitions[parent][lhs[i] as usize];
-                }
-                //Multiple rhs for one lhs is possible, this accommodates for that
-                for rhs in rule_list.1 {
-                    let mut child = origin;
-                    //Determine the state the RHS goes to from the origin (e.g. (q0,001))
-                    for i in 0..rhs.len() {
-                        child = possible_dfa.state_transitions[child][rhs[i] as usize];
-                    }
-                    //update_edge adds an edge if one does not already exist
-                    rule_graph.update_edge(
-                        NodeIndex::new(parent),
-                        NodeIndex::new(child),
-                        RuleGraphRoot::new(
-                            lhs.clone(),
-                            rhs.clone(),
-                            parent,
-                            child,
-                            origin,
-                            &possible_dfa.symbol_set,
-                        ),
-                    );
+        build_rule_graph(self.get_ruleset(), possible_dfa)
+    }
+    //Graphviz version of the rule graph, with each edge labelled by the rule that created it.
+    //Edges that are part of a cycle (ignoring self-loops) are drawn in red, as those are what make is_correct fail.
+    fn rule_graph_to_dot(&self, possible_dfa: &DFA) -> String {
+        let rule_graph = self.build_rule_graph(possible_dfa);
+        let mut component = vec![usize::MAX; rule_graph.node_count()];
+        for (idx, scc) in petgraph::algo::tarjan_scc(&rule_graph).iter().enumerate() {
+            if scc.len() > 1 {
+                for node in scc {
+                    component[node.index()] = idx;
                 }
             }
         }
-        //Now we have built all single rule applications where the LHS and RHS are the last N characters of the strings they belong to
-        //e.g. (q0, 110) -> (q0,001)
-        //But there should still be a connection using these rules, even if the LHS and RHS are not the last characters used
-        //e.g. (q0, 1101) -> (q0, 0011)
-        //The code below performs those additions
-        let mut old_len = 0;
-        //If there are no new edges since the last time, there are no new possible edges
-        //as the code would run the exact same as last time, and not add anything
-        while old_len < rule_graph.edge_count() {
-            let new_len = rule_graph.edge_count();
-            //Iterating through all of the new edges (this is not endorsed by petgraph as a method, but it works 100% of the time)
-            for edge_idx in old_len..new_len {
-                //the weight is the metadata related to the path
-                let old_weight = rule_graph.raw_edges()[edge_idx].weight.clone();
-                let old_parent = rule_graph[rule_graph.raw_edges()[edge_idx].source()];
-                let old_child = rule_graph[rule_graph.raw_edges()[edge_idx].target()];
-
-                //For each symbol after the old LHS and RHS state, check to see if adding one symbol to both adds a new path
-                //e.g. If the old rule is (q0,x) -> (q0,y)
-                //then we add the edges (q0, x0) -> (q0, y0), (q0,x1) -> (q0, y1)
-                //x0/y0 is concatenated, e.g. if x = 100 then x0 = 1001
-                for sym in 0..self.get_ruleset().symbol_set.length {
-                    let new_parent = possible_dfa.state_transitions[old_parent][sym as usize];
-                    let new_child = possible_dfa.state_transitions[old_child][sym as usize];
-                    if !rule_graph
-                        .contains_edge(NodeIndex::new(new_parent), NodeIndex::new(new_child))
-                    {
-                        rule_graph.add_edge(
-                            NodeIndex::new(new_parent),
-                            NodeIndex::new(new_child),
-                            old_weight.clone(),
-                        );
-                    }
-                }
-            }
-            old_len = new_len;
+        let mut result = "digraph RuleGraph {\n    node [shape=circle];\n".to_owned();
+        for node in rule_graph.node_indices() {
+            let shape = if possible_dfa.accepting_states[rule_graph[node]] {
+                "doublecircle"
+            } else {
+                "circle"
+            };
+            result.push_str(&format!("    q{} [shape={}];\n", rule_graph[node], shape));
         }
-        rule_graph
+        for edge in rule_graph.edge_references() {
+            let (source, target) = (edge.source().index(), edge.target().index());
+            let color = if source != target
+                && component[source] != usize::MAX
+                && component[source] == component[target]
+            {
+                ", color=red"
+            } else {
+                ""
+            };
+            let root = edge.weight();
+            result.push_str(&format!(
+                "    q{} -> q{} [label=\"{}\"{}];\n",
+                rule_graph[edge.source()],
+                rule_graph[edge.target()],
+                escape(&root.label()),
+                color
+            ));
+        }
+        result.push_str("}\n");
+        result
     }
+
     fn is_superset<'a>(
         &'a self,
         test_dfa: &'a DFA,
@@ -344,313 +262,80 @@ where
         Ok(())
     }
     fn build_no_rule_dfa(&self) -> DFA {
-        if self.get_ruleset().rules.contains_key(&vec![]) {
-            DFA {
-                accepting_states: Vec::new(),
-                starting_state: 0,
-                state_transitions: vec![vec![0; self.get_goal().symbol_set.length]],
-                symbol_set: self.get_goal().symbol_set.clone(),
-            }
-        } else {
-            let mut state_buffers: Vec<Vec<SymbolIdx>> = vec![vec![SymbolIdx::MAX], vec![]];
-
-            let mut state_transitions = vec![vec![0; self.get_goal().symbol_set.length]; 2];
-            let mut last_states = 1;
-            while last_states != state_transitions.len() {
-                let old_states = last_states;
-                last_states = state_transitions.len();
-                for new_state in old_states..state_transitions.len() {
-                    for new_buffer_sym in 0..self.get_goal().symbol_set.length {
-                        let mut new_buffer = state_buffers[new_state].clone();
-                        let mut perfect_match_found = false;
-                        let mut match_found = false;
-                        new_buffer.push(new_buffer_sym as SymbolIdx);
-
-                        //If any group of our last characters is the lhs side of a rule, then obviously a rule can be performed
-                        //I.e. if buffer is 1,1,0,0,2
-                        //we check 1,1,0,0,2 & 1,0,0,2 & 0,0,2 & 0,2 & 2
-                        for i in 0..new_buffer.len() {
-                            if self.get_ruleset().rules.contains_key(&new_buffer[i..]) {
-                                state_transitions[new_state][new_buffer_sym] = 0;
-                                perfect_match_found = true;
-                                break;
-                            }
-                        }
-                        if perfect_match_found {
-                            continue;
-                        }
-                        //If there's not, we strip the buffer of any characters that we know will not be used as lhs
-                        //I.e. if buffer is 2,0,2 we know that first 2 is never used, so buffer should become 0,2
-                        while !match_found {
-                            for (lhs, _) in &self.get_ruleset().rules {
-                                if lhs.len() > new_buffer.len()
-                                    && lhs[..new_buffer.len()] == new_buffer
-                                {
-                                    match_found = true;
-                                    break;
-                                }
-                            }
-                            if !match_found {
-                                new_buffer.remove(0);
-                            }
-                        }
-                        match_found = false;
-                        for (idx, buffer) in state_buffers.iter().enumerate() {
-                            if &new_buffer == buffer {
-                                match_found = true;
-                                state_transitions[new_state][new_buffer_sym] = idx;
-                                break;
-                            }
-                        }
-                        if !match_found {
-                            state_buffers.push(new_buffer);
-                            state_transitions[new_state][new_buffer_sym] = state_transitions.len();
-                            state_transitions.push(vec![0; self.get_goal().symbol_set.length])
-                        }
-                    }
-                }
-            }
-
-            let mut temp_accepting = vec![true; state_transitions.len()];
-            temp_accepting[0] = false;
-            DFA {
-                accepting_states: temp_accepting,
-                starting_state: 1,
-                state_transitions: state_transitions,
-                symbol_set: self.get_goal().symbol_set.clone(),
-            }
-        }
+        build_no_rule_dfa(self.get_ruleset(), self.get_goal())
     }
-    fn build_path_graph(&self, possible_dfa: &DFA) -> Vec<Vec<Path>> {
-        let hack_lambda = |list: &Vec<usize>| {
-            let mut start = "[".to_owned();
-            for (idx, i) in list.iter().enumerate() {
-                start.push('q');
-                start.push_str(&i.to_string());
-                if idx + 1 < list.len() {
-                    start.push(',');
-                    start.push(' ');
-                }
-            }
-            start.push(']');
-            start
-        };
-        let mut new_paths = vec![(possible_dfa.starting_state, 0)];
-        let mut old_paths = vec![];
-        let mut paths = vec![vec![]; possible_dfa.state_transitions.len()];
-        let mut iteration_num = 0;
-        paths[possible_dfa.starting_state].push(Path {
-            buffer: vec![],
-            rhs_connections: vec![],
-            buffer_origin: possible_dfa.starting_state,
-            goal_state: self.get_goal().starting_state,
-        });
-        while !new_paths.is_empty() {
-            std::mem::swap(&mut old_paths, &mut new_paths);
-            new_paths.clear();
-            println!("# Iteration {}", iteration_num);
-            if iteration_num > 0 {
-                println!(
-                    "Everything created in Iteration {} will now be tested.",
-                    iteration_num - 1
-                )
-            } else {
-                println!("We start with the starting state and no buffer.");
-            }
-
-            iteration_num += 1;
-            for old_path in &old_paths {
-                println!(
-                    "## Testing {{Origin:q{}, Buffer:{:?}, LHS: q{}, RHS: {}}}",
-                    paths[old_path.0][old_path.1].buffer_origin,
-                    paths[old_path.0][old_path.1].buffer,
-                    old_path.0,
-                    hack_lambda(&paths[old_path.0][old_path.1].rhs_connections)
-                );
-                for symbol in 0..possible_dfa.symbol_set.length {
-                    println!(
-                        "### On {}:",
-                        self.get_goal().symbol_set.representations[symbol]
-                    );
-                    let mut new_buffer = paths[old_path.0][old_path.1].buffer.clone();
-                    new_buffer.push(symbol as SymbolIdx);
-
-                    let new_goal_state = self.get_goal().state_transitions
-                        [paths[old_path.0][old_path.1].goal_state][symbol];
-
-                    let mut new_path = Path {
-                        buffer: new_buffer,
-                        rhs_connections: vec![],
-                        buffer_origin: paths[old_path.0][old_path.1].buffer_origin,
-                        goal_state: new_goal_state,
-                    };
-
-                    //Find where this path would be added
-                    let dest_idx = possible_dfa.state_transitions[old_path.0][symbol];
-                    println!(
-                        "    New LHS is q{} ( (q{}, {}) -> q{} )",
-                        dest_idx, old_path.0, symbol, dest_idx
-                    );
-                    //Follow all old pure links
-
-                    if paths[old_path.0][old_path.1].rhs_connections.len() > 0 {
-                        print!("#### Adding {} to all RHS connections: ", symbol)
-                    }
-                    for old_rhs_connection in &paths[old_path.0][old_path.1].rhs_connections {
-                        let new_connection =
-                            possible_dfa.state_transitions[*old_rhs_connection][symbol];
-                        print!(
-                            "( (q{}, {}) -> q{} ), ",
-                            old_rhs_connection, symbol, new_connection
-                        );
-                        if !new_path.rhs_connections.contains(&new_connection) {
-                            new_path.rhs_connections.push(new_connection);
-                            new_path.rhs_connections.sort();
-                        }
-                    }
-                    if paths[old_path.0][old_path.1].rhs_connections.len() > 0 {
-                        println!("");
-                    }
-                    println!(
-                        "    The new buffer is {:?} ({:?} + {})",
-                        &new_path.buffer[..],
-                        &new_path.buffer[..(new_path.buffer.len() - 1)],
-                        symbol
-                    );
-                    //If any group of our buffer is the lhs side of a rule, then obviously a rule can be performed
-                    //I.e. if buffer is 1,1,0,0,2
-                    //we check 1,1,0,0,2 & 1,0,0,2 & 0,0,2 & 0,2 & 2
-                    for i in 0..new_path.buffer.len() {
-                        //If a buffer matches a left-hand side, determine all rhs states could be reached by the origin + buffer after a single rule application
-                        match self.get_ruleset().rules.get(&new_path.buffer[i..]) {
-                            Some(rhs_list) => {
-                                //Find where the origin where the lhs substring would begin
-                                let mut relevant_origin =
-                                    paths[old_path.0][old_path.1].buffer_origin;
-                                for j in 0..i {
-                                    relevant_origin = possible_dfa.state_transitions
-                                        [relevant_origin]
-                                        [new_path.buffer[j] as usize];
-                                }
-                                for rhs in rhs_list {
-                                    let mut rhs_end_idx = relevant_origin;
-                                    println!("     Last {} elements of buffer are {:?}, which can become {:?}:",new_path.buffer.len() - i, &new_path.buffer[i..], rhs);
-                                    print!("     Following RHS side from origin: ");
-                                    for rhs_element in rhs {
-                                        print!("(q{},{}) -> ", rhs_end_idx, rhs_element);
-                                        rhs_end_idx = possible_dfa.state_transitions[rhs_end_idx]
-                                            [*rhs_element as usize];
-                                        print!("q{} ", rhs_end_idx);
-                                    }
-                                    println!("\n");
-                                    if !new_path.rhs_connections.contains(&rhs_end_idx) {
-                                        print!(
-                                            "      Because (q{},{:?}) -> q{}",
-                                            relevant_origin,
-                                            &new_path.buffer[i..],
-                                            dest_idx
-                                        );
-                                        println!(" and (q{},{:?}) -> q{}, we know that q{} -> q{} is a part of this path (adding to RHS)",relevant_origin,&rhs_list,rhs_end_idx, dest_idx, rhs_end_idx);
-                                        new_path.rhs_connections.push(rhs_end_idx);
-                                        new_path.rhs_connections.sort();
-                                    } else {
-                                        println!("      q{} is already in the RHS connections, so this is redundant",rhs_end_idx);
-                                    }
-                                }
-                            }
-                            None => {}
-                        }
-                    }
-                    //We strip the buffer of any characters that we know will not be used as lhs
-                    //I.e. if buffer is 2,0,2 we know that first 2 is never used, so buffer should become 0,2
-                    let mut match_found = false;
-                    while !match_found {
-                        for (lhs, _) in &self.get_ruleset().rules {
-                            //is the whole buffer relevant as the of the lhs of a rule?
-                            if lhs.len() > new_path.buffer.len()
-                                && lhs[..new_path.buffer.len()] == new_path.buffer
-                            {
-                                match_found = true;
-                                break;
-                            }
-                        }
-                        if !match_found {
-                            //Move
-                            print!("    First element of buffer is unnecessary (no LHS starts with {:?} + a single character) ",new_path.buffer);
-                            let unnecesary_char = new_path.buffer.remove(0);
-                            print!("-- reducing buffer to {:?}", new_path.buffer);
-                            let temp = new_path.buffer_origin;
-                            new_path.buffer_origin = possible_dfa.state_transitions
-                                [new_path.buffer_origin][unnecesary_char as usize];
-                            println!(
-                                ", making origin q{} ( (q{},{}) -> q{} )",
-                                new_path.buffer_origin,
-                                temp,
-                                unnecesary_char,
-                                new_path.buffer_origin
-                            );
-                        }
-                    }
-
-                    if !paths[dest_idx].contains(&new_path) {
-                        println!("\n##### {{Origin:q{}, Buffer:{:?}, LHS: q{}, RHS: {}}} is new! Added to paths",new_path.buffer_origin,new_path.buffer,dest_idx,hack_lambda(&new_path.rhs_connections));
-                        new_paths.push((dest_idx, paths[dest_idx].len()));
-                        paths[dest_idx].push(new_path);
-                    } else {
-                        println!("\n##### {{Origin:q{}, Buffer:{:?}, LHS: q{}, RHS: {}}} already exists -- nothing new created here",new_path.buffer_origin,new_path.buffer,dest_idx,hack_lambda(&new_path.rhs_connections));
-                    }
-                }
-            }
-        }
-        paths
+    //When explain is set, each step of the construction is narrated to stdout
+    fn build_path_graph(&self, possible_dfa: &DFA, explain: bool) -> Vec<Vec<Path>> {
+        build_path_graph(self.get_ruleset(), self.get_goal(), possible_dfa, explain)
     }
     //I'm hoping to give more nuanced proofs/proof failures soon
     //An audit trail (maybe integrated with that massive excel sheet I made) would be ideal
     fn is_correct(&self, possible_dfa: &DFA) -> bool {
-        let no_rule_dfa = self.build_no_rule_dfa();
-        //If the set of terminal strings is not correct in the possible_dfa
-        if &no_rule_dfa & self.get_goal() != &no_rule_dfa & possible_dfa {
-            //Throw the whole thing out!
-            return false;
-        }
-        //Make sure that terminal states have their own state associated with them.
-        let expanded_dfa = possible_dfa.dfa_product(&no_rule_dfa, |s, _o| *s);
+        proof_failures(self, possible_dfa).1.is_empty()
+    }
 
-        //Ensure that there are no cycles in the DFA (if they exist, proof fails & it is guaranteed that DFA is not minimal)
-        let rule_graph = self.build_rule_graph(&expanded_dfa);
-        if petgraph::algo::is_cyclic_directed(&rule_graph) {
-            return false;
+    //Like is_correct, but tries to back up a failed proof with a string the DFA gets wrong.
+    //Strings that the proof points at are tried first, then every string up to max_len.
+    fn check_correct(&self, possible_dfa: &DFA, max_len: usize) -> Result<(), Refutation> {
+        let (expanded_dfa, failures) = proof_failures(self, possible_dfa);
+        if failures.is_empty() {
+            return Ok(());
         }
-
-        let path_graph = self.build_path_graph(&expanded_dfa);
-
-        //Because we only care about whether or not there's a single issue, the ordering of the proof doesn't matter
-        //All states can assume that the others are all correct -- if one fails, it's not correct either way
-        //And if all are correct, then we right to do it in an unordered fashion
-        for (state_idx, state_paths) in path_graph.iter().enumerate() {
-            //If the state is supposed to be accepting
-            if expanded_dfa.accepting_states[state_idx] {
-                for path in state_paths {
-                    if !self.get_goal().accepting_states[path.goal_state] //If the path is not a part of the goal regex
-                       && path.rhs_connections.iter().all(|f| *f != state_idx) //And the path is not looping
-                       && path.rhs_connections.iter().all(|f| !expanded_dfa.accepting_states[*f])
-                    //And can only go to provably rejecting strings
-                    {
-                        return false;
-                    }
+        let mut candidates = vec![];
+        for failure in &failures {
+            match failure {
+                //Terminal strings can't be rewritten, so the goal decides them directly
+                ProofFailure::Terminal(string) => {
+                    return Err(Refutation::Counterexample(Counterexample {
+                        string: string.clone(),
+                        dfa_accepts: possible_dfa.contains(string),
+                        rule_path: if self.get_goal().contains(string) {
+                            Some(vec![string.clone()])
+                        } else {
+                            None
+                        },
+                    }));
                 }
-            } else {
-                for path in state_paths {
-                    if self.get_goal().accepting_states[path.goal_state] || //If the path is a part of the goal regex
-                       path.rhs_connections.iter().any(|f|  expanded_dfa.accepting_states[*f])
-                    //or can go to an accepting state
-                    {
-                        return false;
-                    }
+                ProofFailure::Path(path) => {
+                    let mut candidate = expanded_dfa.shortest_path_to_state(path.buffer_origin);
+                    candidate.extend(&path.buffer);
+                    candidates.push(candidate);
                 }
+                ProofFailure::Cycle => {}
             }
         }
-        true
+        //Fall back on the shortest string into every state, then on brute force
+        for state in 0..expanded_dfa.state_transitions.len() {
+            candidates.push(expanded_dfa.shortest_path_to_state(state));
+        }
+        for candidate in candidates
+            .into_iter()
+            .chain(possible_dfa.symbol_set.sig_set_iter(max_len))
+        {
+            if let Some(counterexample) = test_candidate(self, possible_dfa, candidate) {
+                return Err(Refutation::Counterexample(counterexample));
+            }
+        }
+        Err(Refutation::Unproven)
+    }
+
+    //Runs the solver at increasing k until the result can be proven correct.
+    //If no k up to max_k gives a provably correct DFA, every attempt is handed back instead.
+    fn find_k(&self, start_k: usize, max_k: usize) -> Result<KSearch, Vec<(usize, DFA)>> {
+        let mut history = vec![];
+        for sig_k in start_k..=max_k {
+            let dfa = self.run(sig_k, vec![]);
+            let proven = self.is_correct(&dfa);
+            history.push((sig_k, dfa));
+            if proven {
+                return Ok(KSearch {
+                    k: sig_k,
+                    dfa: history.last().unwrap().1.clone(),
+                    history,
+                });
+            }
+        }
+        Err(history)
     }
 
     fn correct_audit<'a>(&self, possible_dfa: &DFA, emit_steps: bool) -> ProofAudit {
@@ -658,12 +343,9 @@ where
         let no_rule_dfa = self.build_no_rule_dfa();
         let expanded_dfa = possible_dfa.dfa_product(&no_rule_dfa, |s, _o| *s);
 
-        let path_graph = self.build_path_graph(&expanded_dfa);
+        let path_graph = self.build_path_graph(&expanded_dfa, emit_steps);
 
-        let mut audit = ProofAudit {
-            steps: vec![],
-            properties: vec![],
-        };
+        let mut audit = ProofAudit::new();
 
         //Add in all of the states into our proof
         for i in 0..expanded_dfa.state_transitions.len() {
@@ -749,25 +431,10 @@ where
 
         //Next, find all states that can be proven correct bc they either 1) match the goal regex perfectly or
         // 2) are terminal, and have no strings that match the goal regex
-        let mut regex_clone = expanded_dfa.clone(); //This is a modifiable version of the DFA used for checking individual states
         let mut regex_correct_states = vec![]; //This stores a list of all proven states for later
         for i in 0..expanded_dfa.state_transitions.len() {
-            let mut temp_accepting = vec![false; expanded_dfa.state_transitions.len()];
-            temp_accepting[i] = true;
-            regex_clone.accepting_states = temp_accepting;
-            //If the state is accepting and all strings in that state are accepted by the goal regex
-            let accepting_correct =
-                expanded_dfa.accepting_states[i] && &regex_clone <= self.get_goal();
-            //If the state is rejecting, terminal and all strings in that states are not accepted by the goal regex
-            let is_terminal = path_graph[i].iter().all(|f| f.rhs_connections.len() == 0);
-            let rejecting_correct = is_terminal
-                && !expanded_dfa.accepting_states[i]
-                && &(self.get_goal() & &!&regex_clone) == self.get_goal();
-            //&(self.get_goal() - &regex_clone) == self.get_goal() is equivalent to regex_clone.intersection(self.get_goal) == empty_set
-            //But I haven't made an empty set constant for the DFA yet.
-
             //If the state can be guaranteed correct
-            if accepting_correct || rejecting_correct {
+            if matches_goal(&expanded_dfa, self.get_goal(), &path_graph, i) {
                 regex_correct_states.push(i);
                 //Inform that it's correct
                 audit.add_step(
@@ -827,7 +494,7 @@ where
 
         while let Some(cur_index) = possibly_provable.pop_back() {
             //If this has been proven elsewhere, forget about it!
-            if audit.properties[cur_index].contains(&ProofProperty::Correct) {
+            if audit.get_props(cur_index).contains(&ProofProperty::Correct) {
                 continue;
             }
             //If the ProofElement in question is a state
@@ -1053,96 +720,601 @@ where
     }
 }
 
-pub struct ProofAudit {
-    steps: Vec<ProofStep>,
-    properties: Vec<HashSet<ProofProperty>>,
-}
+//Every string a single rule application can turn start_board into.
+//at_start is false when start_board is only the end of a longer string,
+//so it has no start for anchored rules to match.
+pub(crate) fn rule_hash(
+    ruleset: &Ruleset,
+    start_board: &[SymbolIdx],
+    at_start: bool,
+) -> Vec<Vec<SymbolIdx>> {
+    let mut result: Vec<Vec<SymbolIdx>> = anchored_rule_hash(ruleset, start_board, at_start)
+        .into_iter()
+        .map(|(_, _, _, board)| board)
+        .collect();
+    if start_board.is_empty() {
+        if let Some(new_swaps) = ruleset.rules.get(start_board) {
+            for new_swap in new_swaps {
+                result.push(new_swap.clone());
+            }
+        }
+        return result;
+    }
+
+    for lftmst_idx in 0..start_board.len() {
+        for slice_length in ruleset.min_input
+            ..core::cmp::min(ruleset.max_input, start_board.len() - lftmst_idx) + 1
+        {
+            if let Some(new_swaps) = ruleset
+                .rules
+                .get(&start_board[lftmst_idx..(lftmst_idx + slice_length)])
+            {
+                let new_board = start_board[0..lftmst_idx].to_vec();
 
-#[derive(Debug, PartialEq, Eq)]
-enum ProofStepRationale {
-    Assertion,
-    RejectingExit,
-    AcceptingExit,
-    RejectingLooping,
-    AcceptingLooping,
-    EquivalentSet,
-    MatchesRegex,
+                for new_swap in new_swaps {
+                    let mut newest_board = new_board.clone();
+                    newest_board.extend(new_swap);
+                    newest_board.extend(
+                        start_board[lftmst_idx + slice_length..start_board.len()].to_vec(),
+                    );
+                    result.push(newest_board);
+                }
+            }
+        }
+    }
+    result
 }
 
-type ProofIndex = usize;
+//The state reached by reading symbols from state
+fn follow(dfa: &DFA, state: usize, symbols: &[SymbolIdx]) -> usize {
+    symbols
+        .iter()
+        .fold(state, |state, sym| dfa.state_transitions[state][*sym as usize])
+}
 
-#[derive(Debug, PartialEq, Eq, Clone)]
-enum ProofElement {
-    AddProperty(ProofProperty, Vec<ProofIndex>),
-    State(usize),
-    Path(ProofIndex, Vec<ProofIndex>),
+//Like single_rule_hash_annotated, but only for anchored rules, which can each match in at most one place
+fn anchored_rule_hash(
+    ruleset: &Ruleset,
+    start_board: &[SymbolIdx],
+    at_start: bool,
+) -> Vec<(usize, usize, usize, Vec<SymbolIdx>)> {
+    let mut result = vec![];
+    for ((anchor, lhs), rhs_list) in &ruleset.anchored_rules {
+        if let Some(idx) = anchor.find(lhs, start_board, at_start) {
+            for rhs in rhs_list {
+                let mut new_board = start_board[..idx].to_vec();
+                new_board.extend(rhs);
+                new_board.extend(&start_board[idx + lhs.len()..]);
+                result.push((idx, lhs.len(), rhs.len(), new_board));
+            }
+        }
+    }
+    result
 }
-#[derive(Debug, PartialEq, Eq, Hash, Clone)]
-enum ProofProperty {
-    Accepting(bool),
-    Correct,
+
+//Gives the ruleset and goal the same symbol set, failing if it's too big for SymbolIdx
+pub(crate) fn ensure_expansion(ruleset: &mut Ruleset, goal: &mut DFA) -> Result<(), DomainError> {
+    if ruleset.symbol_set == goal.symbol_set {
+    } else if ruleset.symbol_set.is_subset(&goal.symbol_set) {
+        ruleset.expand_to_symset(goal.symbol_set.clone());
+    } else if goal.symbol_set.is_subset(&ruleset.symbol_set) {
+        goal.expand_to_symset(ruleset.symbol_set.clone());
+    } else {
+        let mut merge_symbols = goal.symbol_set.representations.clone();
+        merge_symbols.append(&mut ruleset.symbol_set.representations.clone());
+        merge_symbols.sort();
+        merge_symbols.dedup();
+        let merge_symset = SymbolSet::<String>::new(merge_symbols);
+        ruleset.expand_to_symset(merge_symset.clone());
+        goal.expand_to_symset(merge_symset);
+    }
+    check_symbol_count(&ruleset.symbol_set)
 }
 
-#[derive(Debug, PartialEq, Eq)]
-struct ProofStep {
-    element: ProofElement,
-    sources: HashSet<ProofIndex>,
-    reason: ProofStepRationale,
+pub(crate) fn check_symbol_count(symbol_set: &SymbolSet) -> Result<(), DomainError> {
+    if symbol_set.length > SymbolIdx::MAX as usize + 1 {
+        Err(DomainError::TooManySymbols(symbol_set.length))
+    } else {
+        Ok(())
+    }
 }
 
-impl std::fmt::Display for ProofAudit {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for i in &self.steps {
-            if let ProofElement::AddProperty(ref prop, ref affected) = i.element {
-                write!(f, "Add Property {:?} to ", prop)?;
-                for affect in affected {
-                    write!(f, "{:?}", self.steps[*affect].element)?;
-                    if affect != affected.last().unwrap() {
-                        write!(f, ", ")?;
-                    }
+pub(crate) fn build_rule_graph<'a>(
+    ruleset: &Ruleset,
+    possible_dfa: &'a DFA,
+) -> DiGraph<usize, RuleGraphRoot<'a>> {
+    let mut rule_graph = DiGraph::<usize, RuleGraphRoot>::new();
+    //Add a node in the rule graph for each state
+    for index in 0..possible_dfa.state_transitions.len() {
+        rule_graph.add_node(index);
+    }
+    //See what single rule applications are possible when starting from any state in the DFA
+    //Example -- create a path between (q0, 110) and (q0,001) if one does not exist
+    for origin in 0..possible_dfa.state_transitions.len() {
+        //For each state
+        for (anchor, lhs, rhs_list) in ruleset.all_rules() {
+            //For each rule
+            //Rules anchored to the start can only begin from the starting state,
+            //and rules anchored to the end are added at the very end
+            match anchor {
+                Some(anchor) if anchor.at_end() => continue,
+                Some(_) if origin != possible_dfa.starting_state => continue,
+                _ => {}
+            }
+            //Drawing an arrow from LHS to RHS
+            //State at LHS = parent
+            //State at RHS = child
+
+            //Determine the state the LHS goes to from the origin (e.g. (q0, 110))
+            let mut parent = origin;
+            for sym in lhs {
+                parent = possible_dfa.state_transitions[parent][*sym as usize];
+            }
+            //Multiple rhs for one lhs is possible, this accommodates for that
+            for rhs in rhs_list {
+                let mut child = origin;
+                //Determine the state the RHS goes to from the origin (e.g. (q0,001))
+                for sym in rhs {
+                    child = possible_dfa.state_transitions[child][*sym as usize];
                 }
-                write!(f, " | ")?;
-            } else {
-                write!(f, "{:?} | ", i.element)?;
-            };
-            write!(f, "Using {:?} Rule | Reasons: ", i.reason)?;
-            for source in &i.sources {
-                write!(f, "{:?}, ", self.steps[*source].element)?;
+                //update_edge adds an edge if one does not already exist
+                rule_graph.update_edge(
+                    NodeIndex::new(parent),
+                    NodeIndex::new(child),
+                    RuleGraphRoot::new(
+                        lhs.clone(),
+                        rhs.clone(),
+                        anchor,
+                        parent,
+                        child,
+                        origin,
+                        &possible_dfa.symbol_set,
+                    ),
+                );
             }
-            write!(f, "\n")?;
         }
-        Ok(())
     }
+    //Now we have built all single rule applications where the LHS and RHS are the last N characters of the strings they belong to
+    //e.g. (q0, 110) -> (q0,001)
+    //But there should still be a connection using these rules, even if the LHS and RHS are not the last characters used
+    //e.g. (q0, 1101) -> (q0, 0011)
+    //The code below performs those additions
+    let mut old_len = 0;
+    //If there are no new edges since the last time, there are no new possible edges
+    //as the code would run the exact same as last time, and not add anything
+    while old_len < rule_graph.edge_count() {
+        let new_len = rule_graph.edge_count();
+        //Iterating through all of the new edges (this is not endorsed by petgraph as a method, but it works 100% of the time)
+        for edge_idx in old_len..new_len {
+            //the weight is the metadata related to the path
+            let old_weight = rule_graph.raw_edges()[edge_idx].weight.clone();
+            let old_parent = rule_graph[rule_graph.raw_edges()[edge_idx].source()];
+            let old_child = rule_graph[rule_graph.raw_edges()[edge_idx].target()];
+
+            //For each symbol after the old LHS and RHS state, check to see if adding one symbol to both adds a new path
+            //e.g. If the old rule is (q0,x) -> (q0,y)
+            //then we add the edges (q0, x0) -> (q0, y0), (q0,x1) -> (q0, y1)
+            //x0/y0 is concatenated, e.g. if x = 100 then x0 = 1001
+            for sym in 0..ruleset.symbol_set.length {
+                let new_parent = possible_dfa.state_transitions[old_parent][sym];
+                let new_child = possible_dfa.state_transitions[old_child][sym];
+                if !rule_graph
+                    .contains_edge(NodeIndex::new(new_parent), NodeIndex::new(new_child))
+                {
+                    rule_graph.add_edge(
+                        NodeIndex::new(new_parent),
+                        NodeIndex::new(new_child),
+                        old_weight.clone(),
+                    );
+                }
+            }
+        }
+        old_len = new_len;
+    }
+    //Rules anchored to the end only rewrite whole strings, so their edges can't be extended like the others
+    for ((anchor, lhs), rhs_list) in &ruleset.anchored_rules {
+        if !anchor.at_end() {
+            continue;
+        }
+        for origin in 0..possible_dfa.state_transitions.len() {
+            if anchor.at_start() && origin != possible_dfa.starting_state {
+                continue;
+            }
+            let parent = follow(possible_dfa, origin, lhs);
+            for rhs in rhs_list {
+                let child = follow(possible_dfa, origin, rhs);
+                if !rule_graph.contains_edge(NodeIndex::new(parent), NodeIndex::new(child)) {
+                    rule_graph.add_edge(
+                        NodeIndex::new(parent),
+                        NodeIndex::new(child),
+                        RuleGraphRoot::new(
+                            lhs.clone(),
+                            rhs.clone(),
+                            Some(*anchor),
+                            parent,
+                            child,
+                            origin,
+                            &possible_dfa.symbol_set,
+                        ),
+                    );
+                }
+            }
+        }
+    }
+    rule_graph
+}
+
+pub(crate) fn build_no_rule_dfa(ruleset: &Ruleset, goal: &DFA) -> DFA {
+    let no_rule_dfa = build_unanchored_no_rule_dfa(ruleset, goal);
+    if ruleset.anchored_rules.is_empty() || ruleset.rules.contains_key(&vec![]) {
+        return no_rule_dfa;
+    }
+    //Whether an anchored rule applies depends on where its lhs is rather than what's around it,
+    //so the strings they apply to are found separately and taken out
+    let mut matches = NFA::new(goal.symbol_set.clone());
+    //Any number of symbols before an lhs that isn't anchored to the start, or after one that isn't anchored to the end
+    let anywhere = matches.add_state(false);
+    let matched = matches.add_state(true);
+    for sym in 0..goal.symbol_set.length {
+        matches.add_transition(anywhere, sym as SymbolIdx, anywhere);
+        matches.add_transition(matched, sym as SymbolIdx, matched);
+    }
+    matches.add_epsilon_transition(matches.starting_state, anywhere);
+    for (anchor, lhs) in ruleset.anchored_rules.keys() {
+        let mut state = if anchor.at_start() {
+            matches.starting_state
+        } else {
+            anywhere
+        };
+        for sym in lhs {
+            let next = matches.add_state(false);
+            matches.add_transition(state, *sym, next);
+            state = next;
+        }
+        if anchor.at_end() {
+            matches.accepting_states[state] = true;
+        } else {
+            matches.add_epsilon_transition(state, matched);
+        }
+    }
+    let mut matches = matches.to_dfa();
+    matches.minimize();
+    //Minimized so that every string that can't become terminal again shares one state,
+    //which the proof relies on when it looks for cycles
+    let mut no_rule_dfa = &no_rule_dfa & &!&matches;
+    no_rule_dfa.minimize();
+    no_rule_dfa
 }
 
-impl ProofAudit {
-    fn add_step(&mut self, proof_step: ProofStep, should_emit: bool) {
-        if let ProofElement::AddProperty(ref prop, ref affected) = proof_step.element {
-            for i in affected {
-                self.properties[*i].insert(prop.clone());
+fn build_unanchored_no_rule_dfa(ruleset: &Ruleset, goal: &DFA) -> DFA {
+    if ruleset.rules.contains_key(&vec![]) {
+        DFA {
+            accepting_states: Vec::new(),
+            starting_state: 0,
+            state_transitions: vec![vec![0; goal.symbol_set.length]],
+            symbol_set: goal.symbol_set.clone(),
+        }
+    } else {
+        let mut state_buffers: Vec<Vec<SymbolIdx>> = vec![vec![SymbolIdx::MAX], vec![]];
+
+        let mut state_transitions = vec![vec![0; goal.symbol_set.length]; 2];
+        let mut last_states = 1;
+        while last_states != state_transitions.len() {
+            let old_states = last_states;
+            last_states = state_transitions.len();
+            for new_state in old_states..state_transitions.len() {
+                for new_buffer_sym in 0..goal.symbol_set.length {
+                    let mut new_buffer = state_buffers[new_state].clone();
+                    let mut perfect_match_found = false;
+                    let mut match_found = false;
+                    new_buffer.push(new_buffer_sym as SymbolIdx);
+
+                    //If any group of our last characters is the lhs side of a rule, then obviously a rule can be performed
+                    //I.e. if buffer is 1,1,0,0,2
+                    //we check 1,1,0,0,2 & 1,0,0,2 & 0,0,2 & 0,2 & 2
+                    for i in 0..new_buffer.len() {
+                        if ruleset.rules.contains_key(&new_buffer[i..]) {
+                            state_transitions[new_state][new_buffer_sym] = 0;
+                            perfect_match_found = true;
+                            break;
+                        }
+                    }
+                    if perfect_match_found {
+                        continue;
+                    }
+                    //If there's not, we strip the buffer of any characters that we know will not be used as lhs
+                    //I.e. if buffer is 2,0,2 we know that first 2 is never used, so buffer should become 0,2
+                    while !match_found && !new_buffer.is_empty() {
+                        for lhs in ruleset.rules.keys() {
+                            if lhs.len() > new_buffer.len() && lhs[..new_buffer.len()] == new_buffer
+                            {
+                                match_found = true;
+                                break;
+                            }
+                        }
+                        if !match_found {
+                            new_buffer.remove(0);
+                        }
+                    }
+                    match_found = false;
+                    for (idx, buffer) in state_buffers.iter().enumerate() {
+                        if &new_buffer == buffer {
+                            match_found = true;
+                            state_transitions[new_state][new_buffer_sym] = idx;
+                            break;
+                        }
+                    }
+                    if !match_found {
+                        state_buffers.push(new_buffer);
+                        state_transitions[new_state][new_buffer_sym] = state_transitions.len();
+                        state_transitions.push(vec![0; goal.symbol_set.length])
+                    }
+                }
             }
         }
-        if should_emit {
-            println!("{:?}", proof_step);
+
+        let mut temp_accepting = vec![true; state_transitions.len()];
+        temp_accepting[0] = false;
+        DFA {
+            accepting_states: temp_accepting,
+            starting_state: 1,
+            state_transitions,
+            symbol_set: goal.symbol_set.clone(),
         }
-        self.steps.push(proof_step);
-        self.properties.push(HashSet::new());
     }
-    fn len(&self) -> usize {
-        self.steps.len()
+}
+
+pub(crate) fn build_path_graph(
+    ruleset: &Ruleset,
+    goal: &DFA,
+    possible_dfa: &DFA,
+    explain: bool,
+) -> Vec<Vec<Path>> {
+    macro_rules! explain {
+        ($($arg:tt)*) => { if explain { print!($($arg)*) } };
     }
-    fn find_element(&self, desired_element: ProofElement) -> Option<usize> {
-        self.steps.iter().position(|x| x.element == desired_element)
+    macro_rules! explainln {
+        ($($arg:tt)*) => { if explain { println!($($arg)*) } };
     }
-    fn get_props(&self, step_idx: ProofIndex) -> HashSet<ProofProperty> {
-        self.properties[step_idx].clone()
+    let hack_lambda = |list: &Vec<usize>| {
+        let mut start = "[".to_owned();
+        for (idx, i) in list.iter().enumerate() {
+            start.push('q');
+            start.push_str(&i.to_string());
+            if idx + 1 < list.len() {
+                start.push(',');
+                start.push(' ');
+            }
+        }
+        start.push(']');
+        start
+    };
+    let mut new_paths = vec![(possible_dfa.starting_state, 0)];
+    let mut old_paths = vec![];
+    let mut paths = vec![vec![]; possible_dfa.state_transitions.len()];
+    let mut iteration_num = 0;
+    paths[possible_dfa.starting_state].push(Path {
+        buffer: vec![],
+        rhs_connections: vec![],
+        carried_connections: vec![],
+        at_start: true,
+        buffer_origin: possible_dfa.starting_state,
+        goal_state: goal.starting_state,
+    });
+    while !new_paths.is_empty() {
+        std::mem::swap(&mut old_paths, &mut new_paths);
+        new_paths.clear();
+        explainln!("# Iteration {}", iteration_num);
+        if iteration_num > 0 {
+            explainln!(
+                "Everything created in Iteration {} will now be tested.",
+                iteration_num - 1
+            )
+        } else {
+            explainln!("We start with the starting state and no buffer.");
+        }
+
+        iteration_num += 1;
+        for old_path in &old_paths {
+            explainln!(
+                "## Testing {{Origin:q{}, Buffer:{:?}, LHS: q{}, RHS: {}}}",
+                paths[old_path.0][old_path.1].buffer_origin,
+                paths[old_path.0][old_path.1].buffer,
+                old_path.0,
+                hack_lambda(&paths[old_path.0][old_path.1].rhs_connections)
+            );
+            for symbol in 0..possible_dfa.symbol_set.length {
+                explainln!("### On {}:", goal.symbol_set.representations[symbol]);
+                let mut new_buffer = paths[old_path.0][old_path.1].buffer.clone();
+                new_buffer.push(symbol as SymbolIdx);
+
+                let new_goal_state =
+                    goal.state_transitions[paths[old_path.0][old_path.1].goal_state][symbol];
+
+                let mut new_path = Path {
+                    buffer: new_buffer,
+                    rhs_connections: vec![],
+                    carried_connections: vec![],
+                    at_start: paths[old_path.0][old_path.1].at_start,
+                    buffer_origin: paths[old_path.0][old_path.1].buffer_origin,
+                    goal_state: new_goal_state,
+                };
+
+                //Find where this path would be added
+                let dest_idx = possible_dfa.state_transitions[old_path.0][symbol];
+                explainln!(
+                    "    New LHS is q{} ( (q{}, {}) -> q{} )",
+                    dest_idx,
+                    old_path.0,
+                    symbol,
+                    dest_idx
+                );
+                //Follow all old pure links
+
+                if !paths[old_path.0][old_path.1].carried_connections.is_empty() {
+                    explain!("#### Adding {} to all RHS connections: ", symbol)
+                }
+                for old_rhs_connection in &paths[old_path.0][old_path.1].carried_connections {
+                    let new_connection =
+                        possible_dfa.state_transitions[*old_rhs_connection][symbol];
+                    explain!(
+                        "( (q{}, {}) -> q{} ), ",
+                        old_rhs_connection,
+                        symbol,
+                        new_connection
+                    );
+                    if !new_path.rhs_connections.contains(&new_connection) {
+                        new_path.rhs_connections.push(new_connection);
+                        new_path.rhs_connections.sort();
+                    }
+                }
+                if !paths[old_path.0][old_path.1].carried_connections.is_empty() {
+                    explainln!("");
+                }
+                explainln!(
+                    "    The new buffer is {:?} ({:?} + {})",
+                    &new_path.buffer[..],
+                    &new_path.buffer[..(new_path.buffer.len() - 1)],
+                    symbol
+                );
+                //If any group of our buffer is the lhs side of a rule, then obviously a rule can be performed
+                //I.e. if buffer is 1,1,0,0,2
+                //we check 1,1,0,0,2 & 1,0,0,2 & 0,0,2 & 0,2 & 2
+                for i in 0..new_path.buffer.len() {
+                    //If a buffer matches a left-hand side, determine all rhs states could be reached by the origin + buffer after a single rule application
+                    if let Some(rhs_list) = ruleset.rules.get(&new_path.buffer[i..]) {
+                        //Find where the origin where the lhs substring would begin
+                        let mut relevant_origin = paths[old_path.0][old_path.1].buffer_origin;
+                        for j in 0..i {
+                            relevant_origin = possible_dfa.state_transitions[relevant_origin]
+                                [new_path.buffer[j] as usize];
+                        }
+                        for rhs in rhs_list {
+                            let mut rhs_end_idx = relevant_origin;
+                            explainln!("     Last {} elements of buffer are {:?}, which can become {:?}:",new_path.buffer.len() - i, &new_path.buffer[i..], rhs);
+                            explain!("     Following RHS side from origin: ");
+                            for rhs_element in rhs {
+                                explain!("(q{},{}) -> ", rhs_end_idx, rhs_element);
+                                rhs_end_idx = possible_dfa.state_transitions[rhs_end_idx]
+                                    [*rhs_element as usize];
+                                explain!("q{} ", rhs_end_idx);
+                            }
+                            explainln!("\n");
+                            if !new_path.rhs_connections.contains(&rhs_end_idx) {
+                                explain!(
+                                    "      Because (q{},{:?}) -> q{}",
+                                    relevant_origin,
+                                    &new_path.buffer[i..],
+                                    dest_idx
+                                );
+                                explainln!(" and (q{},{:?}) -> q{}, we know that q{} -> q{} is a part of this path (adding to RHS)",relevant_origin,&rhs_list,rhs_end_idx, dest_idx, rhs_end_idx);
+                                new_path.rhs_connections.push(rhs_end_idx);
+                                new_path.rhs_connections.sort();
+                            } else {
+                                explainln!("      q{} is already in the RHS connections, so this is redundant",rhs_end_idx);
+                            }
+                        }
+                    }
+                }
+                //Anchored rules match when their lhs has just been finished, as long as that's at the start of the string
+                //for rules anchored to the start. Rules anchored to the end only rewrite the string read so far,
+                //so what they connect to isn't carried over to longer strings.
+                let mut end_connections = vec![];
+                for ((anchor, lhs), rhs_list) in &ruleset.anchored_rules {
+                    if !new_path.buffer.ends_with(lhs)
+                        || (anchor.at_start()
+                            && !(new_path.at_start && new_path.buffer.len() == lhs.len()))
+                    {
+                        continue;
+                    }
+                    let relevant_origin = follow(
+                        possible_dfa,
+                        new_path.buffer_origin,
+                        &new_path.buffer[..new_path.buffer.len() - lhs.len()],
+                    );
+                    for rhs in rhs_list {
+                        let rhs_end_idx = follow(possible_dfa, relevant_origin, rhs);
+                        explainln!(
+                            "     {} matches the end of the buffer, so (q{},{:?}) -> q{} is a part of this path",
+                            anchor.lhs_to_string(&format!("{:?}", lhs)),
+                            relevant_origin,
+                            rhs,
+                            rhs_end_idx
+                        );
+                        let connections = if anchor.at_end() {
+                            &mut end_connections
+                        } else {
+                            &mut new_path.rhs_connections
+                        };
+                        if !connections.contains(&rhs_end_idx) {
+                            connections.push(rhs_end_idx);
+                            connections.sort();
+                        }
+                    }
+                }
+                new_path.carried_connections = new_path.rhs_connections.clone();
+                for end_connection in end_connections {
+                    if !new_path.rhs_connections.contains(&end_connection) {
+                        new_path.rhs_connections.push(end_connection);
+                        new_path.rhs_connections.sort();
+                    }
+                }
+                //We strip the buffer of any characters that we know will not be used as lhs
+                //I.e. if buffer is 2,0,2 we know that first 2 is never used, so buffer should become 0,2
+                let mut match_found = false;
+                while !match_found && !new_path.buffer.is_empty() {
+                    for lhs in ruleset.rules.keys() {
+                        //is the whole buffer relevant as the of the lhs of a rule?
+                        if lhs.len() > new_path.buffer.len()
+                            && lhs[..new_path.buffer.len()] == new_path.buffer
+                        {
+                            match_found = true;
+                            break;
+                        }
+                    }
+                    //Rules anchored to the start stop being relevant once anything's been stripped
+                    match_found |= ruleset.anchored_rules.keys().any(|(anchor, lhs)| {
+                        (new_path.at_start || !anchor.at_start())
+                            && lhs.len() > new_path.buffer.len()
+                            && lhs[..new_path.buffer.len()] == new_path.buffer
+                    });
+                    if !match_found {
+                        new_path.at_start = false;
+                        //Move
+                        explain!("    First element of buffer is unnecessary (no LHS starts with {:?} + a single character) ",new_path.buffer);
+                        let unnecesary_char = new_path.buffer.remove(0);
+                        explain!("-- reducing buffer to {:?}", new_path.buffer);
+                        let temp = new_path.buffer_origin;
+                        new_path.buffer_origin = possible_dfa.state_transitions
+                            [new_path.buffer_origin][unnecesary_char as usize];
+                        explainln!(
+                            ", making origin q{} ( (q{},{}) -> q{} )",
+                            new_path.buffer_origin,
+                            temp,
+                            unnecesary_char,
+                            new_path.buffer_origin
+                        );
+                    }
+                }
+
+                if !paths[dest_idx].contains(&new_path) {
+                    explainln!("\n##### {{Origin:q{}, Buffer:{:?}, LHS: q{}, RHS: {}}} is new! Added to paths",new_path.buffer_origin,new_path.buffer,dest_idx,hack_lambda(&new_path.rhs_connections));
+                    new_paths.push((dest_idx, paths[dest_idx].len()));
+                    paths[dest_idx].push(new_path);
+                } else {
+                    explainln!("\n##### {{Origin:q{}, Buffer:{:?}, LHS: q{}, RHS: {}}} already exists -- nothing new created here",new_path.buffer_origin,new_path.buffer,dest_idx,hack_lambda(&new_path.rhs_connections));
+                }
+            }
+        }
     }
+    paths
 }
 
 #[derive(Debug, Clone)]
 pub struct RuleGraphRoot<'a> {
     lhs: Vec<SymbolIdx>,
     rhs: Vec<SymbolIdx>,
+    anchor: Option<Anchor>,
     init_lhs_state: usize,
     init_rhs_state: usize,
     origin: usize,
@@ -1153,6 +1325,7 @@ impl<'a> RuleGraphRoot<'a> {
     fn new(
         lhs: Vec<SymbolIdx>,
         rhs: Vec<SymbolIdx>,
+        anchor: Option<Anchor>,
         init_lhs_state: usize,
         init_rhs_state: usize,
         origin: usize,
@@ -1161,17 +1334,38 @@ impl<'a> RuleGraphRoot<'a> {
         RuleGraphRoot {
             lhs: lhs,
             rhs: rhs,
+            anchor,
             init_lhs_state: init_lhs_state,
             init_rhs_state: init_rhs_state,
             origin: origin,
             symset: symset,
         }
     }
+    fn lhs_to_string(&self) -> String {
+        let lhs = self.symset.symbols_to_string(&self.lhs);
+        match self.anchor {
+            Some(anchor) => anchor.lhs_to_string(&lhs),
+            None => lhs,
+        }
+    }
+    //The rule as it'd be written in an SRS file, without the quotes symbols_to_string adds
+    fn label(&self) -> String {
+        let unquoted = |symbols: &Vec<SymbolIdx>| {
+            let string = self.symset.symbols_to_string(symbols);
+            string[1..string.len() - 1].to_owned()
+        };
+        let lhs = unquoted(&self.lhs);
+        let lhs = match self.anchor {
+            Some(anchor) => anchor.lhs_to_string(&lhs),
+            None => lhs,
+        };
+        format!("{} -> {}", lhs, unquoted(&self.rhs))
+    }
 }
 impl<'a> std::fmt::Display for RuleGraphRoot<'a> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "LHS ")?;
-        write!(f, "{}", self.symset.symbols_to_string(&self.lhs))?;
+        write!(f, "{}", self.lhs_to_string())?;
         write!(f, " | RHS ")?;
         write!(f, "{}", &self.symset.symbols_to_string(&self.rhs))?;
         write!(f, " | initial LHS state ")?;
@@ -1186,7 +1380,12 @@ impl<'a> std::fmt::Display for RuleGraphRoot<'a> {
 pub enum DomainError {
     Generating((Vec<SymbolIdx>, Vec<SymbolIdx>)),
     Deleting((Vec<SymbolIdx>, Vec<SymbolIdx>)),
-    Cyclic((Vec<SymbolIdx>, Vec<SymbolIdx>)),
+    //A derivation from a string back to itself, or to a longer string containing it
+    Cyclic(Vec<Vec<SymbolIdx>>),
+    //The alphabet has more symbols than SymbolIdx can index
+    TooManySymbols(usize),
+    //Cells that don't make up a column of a MultiTrack board
+    InvalidCells(Vec<SymbolIdx>),
 }
 
 impl DomainError {
@@ -1203,20 +1402,219 @@ impl DomainError {
                 symset.symbols_to_string(&lhs),
                 symset.symbols_to_string(&rhs)
             )),
-            DomainError::Cyclic((lhs, rhs)) => result.push_str(&format!(
-                "cyclic rules. Rules \"{0} - {1}\" and \"{1} - {0}\" create a cycle.",
-                symset.symbols_to_string(&lhs),
-                symset.symbols_to_string(&rhs)
+            DomainError::Cyclic(derivation) => result.push_str(&format!(
+                "cyclic rules. SRS can rewrite forever with {}.",
+                derivation_to_string(derivation, symset)
             )),
+            DomainError::TooManySymbols(length) => result.push_str(&format!(
+                "alphabets of {} symbols. At most {} are supported, or more with the u16-symbols or u32-symbols features.",
+                length,
+                SymbolIdx::MAX as usize + 1
+            )),
+            DomainError::InvalidCells(cells) => {
+                return format!("The cells {:?} don't make up a column of the board.", cells)
+            }
+        }
+        result
+    }
+}
+
+//A string that a candidate DFA misclassifies
+#[derive(Clone, Debug)]
+pub struct Counterexample {
+    pub string: Vec<SymbolIdx>,
+    //What the DFA says about string. The truth is the opposite.
+    pub dfa_accepts: bool,
+    //Sequence of single rule applications from string to a goal string, if it can reach one
+    pub rule_path: Option<Vec<Vec<SymbolIdx>>>,
+}
+
+impl Counterexample {
+    pub fn to_string(&self, symset: &SymbolSet) -> String {
+        let mut result = match self.dfa_accepts {
+            true => format!(
+                "DFA accepts {}, but it cannot reach the goal.",
+                symset.symbols_to_string(&self.string)
+            ),
+            false => format!(
+                "DFA rejects {}, but it can reach the goal:",
+                symset.symbols_to_string(&self.string)
+            ),
+        };
+        if let Some(rule_path) = &self.rule_path {
+            for step in rule_path {
+                result.push_str(&format!("\n  {}", symset.symbols_to_string(step)));
+            }
         }
         result
     }
 }
 
+//Why SRSSolver::check_correct couldn't prove a DFA correct
+#[derive(Clone, Debug)]
+pub enum Refutation {
+    Counterexample(Counterexample),
+    //The proof failed, but no misclassified string turned up. The DFA may still be correct.
+    Unproven,
+}
+
+enum ProofFailure {
+    //A terminal string (no rules apply) that the DFA and goal disagree on
+    Terminal(Vec<SymbolIdx>),
+    Cycle,
+    Path(Path),
+}
+
+//Runs the is_correct proof, collecting everything that breaks it rather than stopping at the first problem.
+//Also hands back the candidate DFA expanded by the no-rule DFA, which the failures refer to.
+fn proof_failures<S: SRSSolver>(solver: &S, possible_dfa: &DFA) -> (DFA, Vec<ProofFailure>) {
+    let no_rule_dfa = solver.build_no_rule_dfa();
+    //If the set of terminal strings is not correct in the possible_dfa
+    if let Some(string) = terminal_mismatch(&no_rule_dfa, solver.get_goal(), possible_dfa) {
+        //Throw the whole thing out!
+        return (possible_dfa.clone(), vec![ProofFailure::Terminal(string)]);
+    }
+    let mut failures = vec![];
+    //Make sure that terminal states have their own state associated with them.
+    let expanded_dfa = possible_dfa.dfa_product(&no_rule_dfa, |s, _o| *s);
+
+    //Ensure that there are no cycles in the DFA (if they exist, proof fails & it is guaranteed that DFA is not minimal)
+    if has_rule_cycle(solver.get_ruleset(), &expanded_dfa) {
+        failures.push(ProofFailure::Cycle);
+    }
+
+    let path_graph = solver.build_path_graph(&expanded_dfa, false);
+
+    //The ordering of the proof doesn't matter
+    //All states can assume that the others are all correct -- if one fails, it's not correct either way
+    //And if all are correct, then we right to do it in an unordered fashion
+    for (state_idx, state_paths) in path_graph.into_iter().enumerate() {
+        //If the state is supposed to be accepting
+        if expanded_dfa.accepting_states[state_idx] {
+            for path in state_paths {
+                if !solver.get_goal().accepting_states[path.goal_state] //If the path is not a part of the goal regex
+                   && path.rhs_connections.iter().all(|f| *f != state_idx) //And the path is not looping
+                   && path.rhs_connections.iter().all(|f| !expanded_dfa.accepting_states[*f])
+                //And can only go to provably rejecting strings
+                {
+                    failures.push(ProofFailure::Path(path));
+                }
+            }
+        } else {
+            for path in state_paths {
+                if solver.get_goal().accepting_states[path.goal_state] || //If the path is a part of the goal regex
+                   path.rhs_connections.iter().any(|f|  expanded_dfa.accepting_states[*f])
+                //or can go to an accepting state
+                {
+                    failures.push(ProofFailure::Path(path));
+                }
+            }
+        }
+    }
+    (expanded_dfa, failures)
+}
+
+//The shortest terminal string (one no rules apply to) that the DFA and goal disagree on, if any
+pub(crate) fn terminal_mismatch(
+    no_rule_dfa: &DFA,
+    goal: &DFA,
+    possible_dfa: &DFA,
+) -> Option<Vec<SymbolIdx>> {
+    let mismatch = &(no_rule_dfa & goal) ^ &(no_rule_dfa & possible_dfa);
+    let state = mismatch.accepting_states.iter().position(|x| *x)?;
+    Some(mismatch.shortest_path_to_state(state))
+}
+
+//An edge in the rule graph from one state to another means a string in the first can be rewritten
+//into a string in the second, so every suffix that takes the second to the goal does the same for
+//the first. Going around a cycle of different states would make them all equivalent.
+//A self-loop only says a state can reach everything it can reach, which is always true, so it's
+//left out; otherwise any DFA with a rule that stays in one state would be rejected. Edges from
+//rules anchored to the end don't say anything about longer strings, so they're left out too.
+pub(crate) fn has_rule_cycle(ruleset: &Ruleset, expanded_dfa: &DFA) -> bool {
+    let mut rule_graph = build_rule_graph(ruleset, expanded_dfa);
+    rule_graph.retain_edges(|g, e| {
+        let (a, b) = g.edge_endpoints(e).unwrap();
+        a != b && !g[e].anchor.is_some_and(|anchor| anchor.at_end())
+    });
+    petgraph::algo::is_cyclic_directed(&rule_graph)
+}
+
+//Most strings that get visited while searching for the goal before giving up on a candidate
+const GOAL_SEARCH_LIMIT: usize = 1 << 16;
+
+fn test_candidate<S: SRSSolver>(
+    solver: &S,
+    possible_dfa: &DFA,
+    candidate: Vec<SymbolIdx>,
+) -> Option<Counterexample> {
+    let dfa_accepts = possible_dfa.contains(&candidate);
+    match goal_search(solver, &candidate, GOAL_SEARCH_LIMIT) {
+        Ok(Some(rule_path)) if !dfa_accepts => Some(Counterexample {
+            string: candidate,
+            dfa_accepts,
+            rule_path: Some(rule_path),
+        }),
+        Ok(None) if dfa_accepts => Some(Counterexample {
+            string: candidate,
+            dfa_accepts,
+            rule_path: None,
+        }),
+        _ => None,
+    }
+}
+
+//Breadth-first search through rule applications, without trusting any candidate DFA.
+//Ok(Some(path)) when a goal string is reachable, Ok(None) when every reachable string was checked,
+//and Err(()) if the limit ran out before either could be decided.
+fn goal_search<S: SRSSolver>(
+    solver: &S,
+    start: &Vec<SymbolIdx>,
+    limit: usize,
+) -> Result<Option<Vec<Vec<SymbolIdx>>>, ()> {
+    let mut parents = HashMap::new();
+    parents.insert(start.clone(), start.clone());
+    let mut frontier = VecDeque::from([start.clone()]);
+    while let Some(string) = frontier.pop_front() {
+        if solver.get_goal().contains(&string) {
+            let mut path = vec![string];
+            while path.last().unwrap() != start {
+                path.push(parents[path.last().unwrap()].clone());
+            }
+            path.reverse();
+            return Ok(Some(path));
+        }
+        for next in solver.single_rule_hash(&string) {
+            if !parents.contains_key(&next) {
+                if parents.len() >= limit {
+                    return Err(());
+                }
+                parents.insert(next.clone(), string.clone());
+                frontier.push_back(next);
+            }
+        }
+    }
+    Ok(None)
+}
+
+//Result of SRSSolver::find_k
+#[derive(Clone)]
+pub struct KSearch {
+    //Smallest k tried whose DFA was proven correct
+    pub k: usize,
+    pub dfa: DFA,
+    //Every (k, DFA) pair that was tried, in order
+    pub history: Vec<(usize, DFA)>,
+}
+
 #[derive(PartialEq, Eq, Clone)]
 pub struct Path {
     buffer: Vec<SymbolIdx>,
-    rhs_connections: Vec<usize>,
+    pub(crate) rhs_connections: Vec<usize>,
+    //The rhs_connections that still hold once more symbols are read, i.e. not from rules anchored to the end
+    carried_connections: Vec<usize>,
+    //Whether the buffer still starts at the beginning of the string
+    at_start: bool,
     buffer_origin: usize,
     goal_state: usize,
 }
@@ -4,7 +4,7 @@ use bitvec::prelude::*;
 use petgraph::{algo::toposort, graph::NodeIndex, prelude::DiGraph, Direction};
 
 use crate::{
-    solver::{DFAStructure, SSStructure},
+    solver::{CancelToken, Cancelled, DFAStructure, SSStructure, SolverEvent, SolverObserver},
     util::{Ruleset, SymbolIdx, DFA},
 };
 
@@ -27,6 +27,9 @@ impl SRSSolver for SubsetSolver {
         &self.goal
     }
 
+    //The rule graph has to be acyclic, so SRSs that find_loop finds a loop in are turned down.
+    //Not finding one doesn't prove the SRS terminates: one with no loop and no termination proof
+    //either is still accepted, and Ruleset::termination is what tells the two apart.
     fn new(mut ruleset: Ruleset, mut goal: DFA) -> Result<Self, DomainError> {
         if let Some((lhs, rhs)) = ruleset.has_non_length_preserving_rule() {
             if lhs.len() < rhs.len() {
@@ -36,10 +39,10 @@ impl SRSSolver for SubsetSolver {
             }
         }
 
-        if let Some(problem) = ruleset.has_definitely_cyclic_rule() {
-            return Err(DomainError::Cyclic(problem));
+        if let Some(derivation) = ruleset.find_loop() {
+            return Err(DomainError::Cyclic(derivation));
         }
-        Self::ensure_expansion(&mut ruleset, &mut goal);
+        Self::ensure_expansion(&mut ruleset, &mut goal)?;
 
         let (min_input, max_input) = SubsetSolver::sized_init(&ruleset);
         Ok(SubsetSolver {
@@ -67,28 +70,28 @@ impl Solver for SubsetSolver {
         "Clean up",
     ];
 
-    fn evaluate<'a, 'b>(&'a self, state: &'b Vec<u8>) -> bool {
+    fn evaluate<'a, 'b>(&'a self, state: &'b Vec<SymbolIdx>) -> bool {
         todo!()
     }
 
-    fn mutate(&self, state: Vec<u8>, input: SymbolIdx) -> Vec<u8> {
+    fn mutate(&self, state: Vec<SymbolIdx>, input: SymbolIdx) -> Vec<SymbolIdx> {
         todo!()
     }
 
     fn run_internal(
         mut self,
         sig_k: usize,
-        is_debug: bool,
-        dfa_events: std::sync::mpsc::Sender<(DFAStructure, SSStructure)>,
-        phase_events: std::sync::mpsc::Sender<std::time::Duration>,
         origin: Vec<SymbolIdx>,
-    ) -> DFA {
+        observer: &mut dyn SolverObserver,
+        cancel: CancelToken,
+    ) -> Result<DFA, Cancelled> {
         let init_begin_time = Instant::now();
+        let is_debug = observer.is_listening();
         //graph of connections based on LHS->RHS links for all states
         //Usize is index in trans_table
 
         assert!(
-            core::cmp::PartialEq::<Vec<u8>>::eq(&origin, &vec![]),
+            core::cmp::PartialEq::<Vec<SymbolIdx>>::eq(&origin, &vec![]),
             "doesn't support non-null origin (sorry!)"
         );
         let sig_set = &self.rules.symbol_set.build_sig_k(sig_k);
@@ -120,16 +123,19 @@ impl Solver for SubsetSolver {
 
         //while there are still states to process
         if is_debug {
-            let _ = phase_events.send(Instant::now() - init_begin_time);
+            observer.notify(&SolverEvent::Initialized {
+                duration: init_begin_time.elapsed(),
+            });
         }
         while last_finished < last_known {
+            if cancel.is_cancelled() {
+                return Err(self.cancelled());
+            }
             if is_debug {
-                dfa_events
-                    .send((
-                        DFAStructure::Dense(self.trans_table.clone()),
-                        SSStructure::BooleanMap(self.unique_sigs.clone()),
-                    ))
-                    .unwrap();
+                observer.notify(&SolverEvent::PartialDFA(
+                    DFAStructure::Dense(self.trans_table.clone()),
+                    SSStructure::BooleanMap(self.unique_sigs.clone()),
+                ));
             }
 
             let begin_time = Instant::now();
@@ -151,13 +157,19 @@ impl Solver for SubsetSolver {
             }
             let mut old_len = link_graph.edge_count();
             for origin in 0..last_known {
-                for rule_list in &self.rules.rules {
-                    let lhs = rule_list.0;
-                    for rhs in rule_list.1 {
+                for (anchor, lhs, rhs_list) in self.rules.all_rules() {
+                    //Links have to hold whatever comes after, so rules anchored to the end are left to the search.
+                    //Rules anchored to the start can only link from the empty string.
+                    match anchor {
+                        Some(anchor) if anchor.at_end() => continue,
+                        Some(_) if origin != 0 => continue,
+                        _ => {}
+                    }
+                    for rhs in rhs_list {
                         let mut parent = origin;
                         let mut child = origin;
                         let mut valid = true;
-                        for i in 0..rule_list.0.len() {
+                        for i in 0..lhs.len() {
                             if parent >= last_known || child >= last_known {
                                 valid = false;
                                 break;
@@ -203,7 +215,7 @@ impl Solver for SubsetSolver {
             for origin_idx in last_finished..last_known {
                 for (sym, move_idx) in self.trans_table[origin_idx].iter().enumerate() {
                     for elem in &smaller_sig {
-                        let mut elem_in_origin = vec![sym as u8];
+                        let mut elem_in_origin = vec![sym as SymbolIdx];
                         elem_in_origin.extend(elem.iter());
                         let old_idx = self.rules.symbol_set.find_in_sig_set(elem_in_origin.iter());
                         let new_idx = self.rules.symbol_set.find_in_sig_set(elem.iter());
@@ -220,7 +232,13 @@ impl Solver for SubsetSolver {
 
             if is_debug {
                 let dur = begin_time.elapsed();
-                phase_events.send(dur).unwrap();
+                observer.notify(&SolverEvent::PhaseFinished {
+                    name: Self::PHASES[0],
+                    duration: dur,
+                });
+            }
+            if cancel.is_cancelled() {
+                return Err(self.cancelled());
             }
             let second_time = Instant::now();
             //Next is updating prospective states with all known information.
@@ -300,7 +318,13 @@ impl Solver for SubsetSolver {
 
             if is_debug {
                 let dur = second_time.elapsed();
-                phase_events.send(dur).unwrap();
+                observer.notify(&SolverEvent::PhaseFinished {
+                    name: Self::PHASES[1],
+                    duration: dur,
+                });
+            }
+            if cancel.is_cancelled() {
+                return Err(self.cancelled());
             }
             let third_time = Instant::now();
 
@@ -352,7 +376,13 @@ impl Solver for SubsetSolver {
 
             if is_debug {
                 let dur = third_time.elapsed();
-                phase_events.send(dur).unwrap();
+                observer.notify(&SolverEvent::PhaseFinished {
+                    name: Self::PHASES[2],
+                    duration: dur,
+                });
+            }
+            if cancel.is_cancelled() {
+                return Err(self.cancelled());
             }
             let fourth_time = Instant::now();
 
@@ -377,29 +407,37 @@ impl Solver for SubsetSolver {
             new_recent_strings.clear();
             if is_debug {
                 let dur: std::time::Duration = fourth_time.elapsed();
-                phase_events.send(dur).unwrap();
+                observer.notify(&SolverEvent::PhaseFinished {
+                    name: Self::PHASES[3],
+                    duration: dur,
+                });
+                observer.notify(&SolverEvent::IterationFinished {
+                    states: last_known,
+                    new_states: new_known,
+                });
             }
         }
-        let mut accepting_states = Vec::new();
-        for (key, _val) in self.unique_sigs.iter() {
-            accepting_states.push(key[0])
+        let mut accepting_states = vec![false; self.unique_sigs.len()];
+        for (key, val) in self.unique_sigs.iter() {
+            accepting_states[*val] = key[0];
         }
         let trans_table = self.trans_table.clone();
         if is_debug {
-            dfa_events
-                .send((
-                    DFAStructure::Dense(self.trans_table.clone()),
-                    SSStructure::BooleanMap(self.unique_sigs.clone()),
-                ))
-                .unwrap();
+            observer.notify(&SolverEvent::PartialDFA(
+                DFAStructure::Dense(self.trans_table.clone()),
+                SSStructure::BooleanMap(self.unique_sigs.clone()),
+            ));
+            observer.notify(&SolverEvent::Finished {
+                states: trans_table.len(),
+            });
         }
         //self.sig_sets = vec![]; BAD AND TEMPORARY
-        DFA {
+        Ok(DFA {
             state_transitions: trans_table,
             accepting_states: accepting_states,
             starting_state: 0,
             symbol_set: self.rules.symbol_set.clone(),
-        }
+        })
     }
 
     fn get_symset(&self) -> &crate::SymbolSet {
@@ -408,6 +446,13 @@ impl Solver for SubsetSolver {
 }
 
 impl SubsetSolver {
+    //Same snapshot that gets sent out as a dfa event
+    fn cancelled(&self) -> Cancelled {
+        Cancelled {
+            dfa: DFAStructure::Dense(self.trans_table.clone()),
+            sig_sets: Some(SSStructure::BooleanMap(self.unique_sigs.clone())),
+        }
+    }
     fn bfs_solver_sub(
         &mut self,
         start_board: &Vec<SymbolIdx>,
@@ -0,0 +1,292 @@
+use std::{cmp::Ordering, collections::VecDeque};
+
+use super::{confluence::overlaps, Ruleset, SymbolIdx};
+
+//How completion decides which side of an equation becomes the lhs. Precedences list every symbol,
+//lowest first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReductionOrder {
+    //Longer strings are bigger, with strings of the same length compared lexicographically
+    LengthLex(Vec<SymbolIdx>),
+    //Heavier strings are bigger, with ties broken like LengthLex
+    WeightLex {
+        weights: Vec<u64>,
+        precedence: Vec<SymbolIdx>,
+    },
+}
+
+impl ReductionOrder {
+    //Whether the order makes sense for an alphabet of symbol_count symbols: the precedence has to
+    //list each of them exactly once, and each needs a weight
+    pub fn check(&self, symbol_count: usize) -> Result<(), CompletionError> {
+        let precedence = match self {
+            ReductionOrder::LengthLex(precedence) => precedence,
+            ReductionOrder::WeightLex {
+                weights,
+                precedence,
+            } => {
+                if weights.len() != symbol_count {
+                    return Err(CompletionError::Weights(weights.len()));
+                }
+                precedence
+            }
+        };
+        let mut listed = vec![false; symbol_count];
+        for sym in precedence {
+            match listed.get_mut(*sym as usize) {
+                Some(seen) if !*seen => *seen = true,
+                _ => return Err(CompletionError::Precedence(precedence.clone())),
+            }
+        }
+        if precedence.len() != symbol_count {
+            return Err(CompletionError::Precedence(precedence.clone()));
+        }
+        Ok(())
+    }
+
+    //Panics for strings with symbols check would've turned down
+    pub fn compare(&self, left: &[SymbolIdx], right: &[SymbolIdx]) -> Ordering {
+        let (weights, precedence) = match self {
+            ReductionOrder::LengthLex(precedence) => (None, precedence),
+            ReductionOrder::WeightLex {
+                weights,
+                precedence,
+            } => (Some(weights), precedence),
+        };
+        let weight = |string: &[SymbolIdx]| match weights {
+            Some(weights) => string.iter().map(|sym| weights[*sym as usize]).sum(),
+            None => 0,
+        };
+        let mut rank = vec![0; precedence.len()];
+        for (position, sym) in precedence.iter().enumerate() {
+            rank[*sym as usize] = position;
+        }
+        let ranked = |string: &[SymbolIdx]| -> Vec<usize> {
+            string.iter().map(|sym| rank[*sym as usize]).collect()
+        };
+        weight(left)
+            .cmp(&weight(right))
+            .then(left.len().cmp(&right.len()))
+            .then_with(|| ranked(left).cmp(&ranked(right)))
+    }
+}
+
+//Why Ruleset::complete couldn't start
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompletionError {
+    //The precedence doesn't list every symbol exactly once
+    Precedence(Vec<SymbolIdx>),
+    //How many weights were given, when every symbol needs exactly one
+    Weights(usize),
+    //The ruleset has anchored rules, which completion can't handle
+    AnchoredRules,
+}
+
+impl std::fmt::Display for CompletionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CompletionError::Precedence(precedence) => write!(
+                f,
+                "The precedence {:?} has to list every symbol exactly once",
+                precedence
+            ),
+            CompletionError::Weights(count) => {
+                write!(f, "{} weights were given, but every symbol needs one", count)
+            }
+            CompletionError::AnchoredRules => {
+                write!(f, "Completion doesn't work with anchored rules")
+            }
+        }
+    }
+}
+
+//When to give up on completion, since it doesn't have to finish
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompletionLimits {
+    //Most equations to turn into rules or throw away
+    pub max_steps: usize,
+    pub max_rules: usize,
+    //Longest lhs a rule can have
+    pub max_rule_length: usize,
+}
+
+impl Default for CompletionLimits {
+    fn default() -> Self {
+        CompletionLimits {
+            max_steps: 10_000,
+            max_rules: 1_000,
+            max_rule_length: 20,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionLimit {
+    Steps,
+    Rules,
+    RuleLength,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Completion {
+    //A confluent ruleset oriented by the order, with the same equivalence classes as the original
+    //one. Rules may point the other way from how they were written (see Ruleset::reversed_rules),
+    //so a DFA solved from it describes which strings are equivalent to the goal, not which ones
+    //the original SRS can reach it from.
+    Complete(Ruleset),
+    //How far completion got before hitting a limit. The rules along with the pending equations
+    //are equivalent to the original ones, but the rules might not be on their own.
+    Incomplete {
+        rules: Ruleset,
+        pending: Vec<(Vec<SymbolIdx>, Vec<SymbolIdx>)>,
+        limit: CompletionLimit,
+        steps: usize,
+    },
+}
+
+impl Ruleset {
+    //Rules the order would have completion turn around, since their rhs is the bigger side
+    pub fn reversed_rules(&self, order: &ReductionOrder) -> Vec<(Vec<SymbolIdx>, Vec<SymbolIdx>)> {
+        let mut reversed: Vec<(Vec<SymbolIdx>, Vec<SymbolIdx>)> = self
+            .rules
+            .iter()
+            .flat_map(|(lhs, rhs_list)| rhs_list.iter().map(move |rhs| (lhs, rhs)))
+            .filter(|(lhs, rhs)| order.compare(lhs, rhs) == Ordering::Less)
+            .map(|(lhs, rhs)| (lhs.clone(), rhs.clone()))
+            .collect();
+        reversed.sort();
+        reversed
+    }
+
+    //Knuth-Bendix completion. Rules are read as equations, so "equivalent" means the same strings
+    //can be turned into each other with rules applied in either direction; the result is meant for
+    //asking about normal forms, like whether a string's normal form is in the goal. Rulesets with
+    //anchored rules are turned down, since the critical pairs those would add depend on where
+    //they are in the string.
+    pub fn complete(
+        &self,
+        order: ReductionOrder,
+        limits: CompletionLimits,
+    ) -> Result<Completion, CompletionError> {
+        if !self.anchored_rules.is_empty() {
+            return Err(CompletionError::AnchoredRules);
+        }
+        order.check(self.symbol_set.length)?;
+        let mut equations: VecDeque<(Vec<SymbolIdx>, Vec<SymbolIdx>)> = VecDeque::new();
+        let mut initial: Vec<(&Vec<SymbolIdx>, &Vec<SymbolIdx>)> = self
+            .rules
+            .iter()
+            .flat_map(|(lhs, rhs_list)| rhs_list.iter().map(move |rhs| (lhs, rhs)))
+            .collect();
+        initial.sort();
+        for (lhs, rhs) in initial {
+            equations.push_back((lhs.clone(), rhs.clone()));
+        }
+        let mut rules: Vec<(Vec<SymbolIdx>, Vec<SymbolIdx>)> = vec![];
+        let mut steps = 0;
+        while !equations.is_empty() {
+            let limit = if steps == limits.max_steps {
+                Some(CompletionLimit::Steps)
+            } else if rules.len() >= limits.max_rules {
+                Some(CompletionLimit::Rules)
+            } else {
+                None
+            };
+            if let Some(limit) = limit {
+                return Ok(Completion::Incomplete {
+                    rules: to_ruleset(&rules, self),
+                    pending: equations.into(),
+                    limit,
+                    steps,
+                });
+            }
+            let (left, right) = equations.pop_front().unwrap();
+            steps += 1;
+            let (left, right) = (reduce(&rules, &left), reduce(&rules, &right));
+            let (lhs, rhs) = match order.compare(&left, &right) {
+                Ordering::Equal => continue,
+                Ordering::Greater => (left, right),
+                Ordering::Less => (right, left),
+            };
+            if lhs.len() > limits.max_rule_length {
+                equations.push_front((lhs, rhs));
+                return Ok(Completion::Incomplete {
+                    rules: to_ruleset(&rules, self),
+                    pending: equations.into(),
+                    limit: CompletionLimit::RuleLength,
+                    steps,
+                });
+            }
+            //Rules the new one can rewrite the lhs of are turned back into equations
+            let (kept, collapsed): (Vec<_>, Vec<_>) = rules
+                .into_iter()
+                .partition(|(old_lhs, _)| !contains(old_lhs, &lhs));
+            rules = kept;
+            equations.extend(collapsed);
+            let new_rule = (lhs, rhs);
+            for (old_lhs, old_rhs) in rules.iter().chain([&new_rule]) {
+                let (old, new) = (
+                    (&old_lhs[..], &old_rhs[..]),
+                    (&new_rule.0[..], &new_rule.1[..]),
+                );
+                for pair in overlaps(old, new).into_iter().chain(overlaps(new, old)) {
+                    equations.push_back((pair.left.result, pair.right.result));
+                }
+            }
+            rules.push(new_rule);
+        }
+        //Tidy up into a reduced system: no lhs contains another, and every rhs is a normal form
+        let mut reduced: Vec<(Vec<SymbolIdx>, Vec<SymbolIdx>)> = vec![];
+        for (idx, (lhs, rhs)) in rules.iter().enumerate() {
+            let redundant = rules.iter().enumerate().any(|(other_idx, (other_lhs, _))| {
+                other_idx != idx
+                    && contains(lhs, other_lhs)
+                    && (other_lhs != lhs || other_idx < idx)
+            });
+            if !redundant {
+                reduced.push((lhs.clone(), reduce(&rules, rhs)));
+            }
+        }
+        reduced.sort();
+        Ok(Completion::Complete(to_ruleset(&reduced, self)))
+    }
+
+    //Rewrites string with the leftmost rule that applies (and the first of its rhs) until none do.
+    //For a confluent, terminating ruleset like the ones complete gives, this is the only string
+    //it can be rewritten into that can't be rewritten any further.
+    pub fn normal_form(&self, string: &[SymbolIdx]) -> Vec<SymbolIdx> {
+        let mut rules: Vec<(Vec<SymbolIdx>, Vec<SymbolIdx>)> = self
+            .rules
+            .iter()
+            .map(|(lhs, rhs_list)| (lhs.clone(), rhs_list[0].clone()))
+            .collect();
+        rules.sort();
+        reduce(&rules, string)
+    }
+}
+
+fn contains(string: &[SymbolIdx], factor: &[SymbolIdx]) -> bool {
+    factor.is_empty() || string.windows(factor.len()).any(|window| window == factor)
+}
+
+//Rewrites the leftmost place any rule matches, with the first rule that matches there,
+//until nothing does
+fn reduce(rules: &[(Vec<SymbolIdx>, Vec<SymbolIdx>)], string: &[SymbolIdx]) -> Vec<SymbolIdx> {
+    let mut string = string.to_vec();
+    'rewrite: loop {
+        for position in 0..=string.len() {
+            let mut matching = rules
+                .iter()
+                .filter(|(lhs, _)| string[position..].starts_with(lhs));
+            if let Some((lhs, rhs)) = matching.next() {
+                string.splice(position..position + lhs.len(), rhs.iter().copied());
+                continue 'rewrite;
+            }
+        }
+        return string;
+    }
+}
+
+fn to_ruleset(rules: &[(Vec<SymbolIdx>, Vec<SymbolIdx>)], original: &Ruleset) -> Ruleset {
+    Ruleset::from_vec(rules.to_vec(), original.symbol_set.clone())
+}
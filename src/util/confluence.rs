@@ -0,0 +1,258 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use super::{termination::derivation_to_string, Ruleset, SymbolIdx, SymbolSet, Termination};
+use crate::solver::rule_hash;
+
+//How many strings each side of a critical pair is rewritten into before giving up on joining them
+const JOIN_SEARCH_STRINGS: usize = 10_000;
+
+//A string two rules can both be applied to in overlapping places, and what each turns it into.
+//Either the end of one lhs is the start of the other, or one lhs is inside the other.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CriticalPair {
+    pub peak: Vec<SymbolIdx>,
+    pub left: RuleApplication,
+    pub right: RuleApplication,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleApplication {
+    pub lhs: Vec<SymbolIdx>,
+    pub rhs: Vec<SymbolIdx>,
+    //Where the lhs starts in the peak
+    pub position: usize,
+    pub result: Vec<SymbolIdx>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Confluence {
+    //Every critical pair joins and the SRS terminates, so by Newman's lemma it's confluent
+    Confluent,
+    //Every critical pair joins, but without termination that only makes it locally confluent
+    LocallyConfluent,
+    //Every critical pair of the unanchored rules joins, but there are anchored rules, which
+    //aren't checked, so this says nothing about the whole SRS
+    AnchoredUnchecked,
+    NotJoinable(Vec<UnjoinablePair>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnjoinablePair {
+    pub pair: CriticalPair,
+    //Derivations from the peak through each side of the pair to a string that can't be rewritten
+    //any further, or just to the side itself if no such string was found
+    pub left_derivation: Vec<Vec<SymbolIdx>>,
+    pub right_derivation: Vec<Vec<SymbolIdx>>,
+    //Whether everything both sides can be rewritten into was searched, in which case the
+    //pair can't be joined at all and the SRS definitely isn't confluent. Never set when there are
+    //anchored rules, since those might join the pair wherever they apply.
+    pub exhaustive: bool,
+}
+
+//Anchored rules are left out of all of this, since whether they apply to a peak depends on
+//where it sits in the rest of the string. confluence won't call an SRS with any confluent.
+impl Ruleset {
+    //Every critical pair whose sides differ, in order of the rules' lhs and rhs
+    pub fn critical_pairs(&self) -> Vec<CriticalPair> {
+        let mut rules: Vec<(&Vec<SymbolIdx>, &Vec<SymbolIdx>)> = self
+            .rules
+            .iter()
+            .flat_map(|(lhs, rhs_list)| rhs_list.iter().map(move |rhs| (lhs, rhs)))
+            .collect();
+        rules.sort();
+        let mut pairs = vec![];
+        let mut seen = HashSet::new();
+        let mut add = |pair: CriticalPair| {
+            if pair.left.result == pair.right.result {
+                return;
+            }
+            let mut sides = [pair.left.result.clone(), pair.right.result.clone()];
+            sides.sort();
+            if seen.insert((pair.peak.clone(), sides)) {
+                pairs.push(pair);
+            }
+        };
+        for (l1, r1) in &rules {
+            for (l2, r2) in &rules {
+                for pair in overlaps((l1, r1), (l2, r2)) {
+                    add(pair);
+                }
+            }
+        }
+        pairs
+    }
+
+    //Checks whether every critical pair can be rewritten into a common string within max_steps
+    //rule applications on each side
+    pub fn confluence(&self, max_steps: usize) -> Confluence {
+        let unanchored = Ruleset {
+            anchored_rules: HashMap::new(),
+            ..self.clone()
+        };
+        let mut unjoinable = vec![];
+        for pair in unanchored.critical_pairs() {
+            let left = explore(&unanchored, &pair.left.result, max_steps);
+            let right = explore(&unanchored, &pair.right.result, max_steps);
+            if left.parents.keys().any(|string| right.parents.contains_key(string)) {
+                continue;
+            }
+            let derivation = |side: &Exploration, result: &Vec<SymbolIdx>| {
+                let mut derivation = vec![];
+                let mut cur = side.normal_form.clone().unwrap_or(result.clone());
+                while let Some(parent) = side.parents[&cur].clone() {
+                    derivation.push(cur);
+                    cur = parent;
+                }
+                derivation.push(cur);
+                derivation.push(pair.peak.clone());
+                derivation.reverse();
+                derivation
+            };
+            unjoinable.push(UnjoinablePair {
+                left_derivation: derivation(&left, &pair.left.result),
+                right_derivation: derivation(&right, &pair.right.result),
+                exhaustive: left.exhaustive && right.exhaustive && self.anchored_rules.is_empty(),
+                pair,
+            });
+        }
+        if !unjoinable.is_empty() {
+            Confluence::NotJoinable(unjoinable)
+        } else if !self.anchored_rules.is_empty() {
+            Confluence::AnchoredUnchecked
+        } else if let Termination::Terminating(_) = unanchored.termination() {
+            Confluence::Confluent
+        } else {
+            Confluence::LocallyConfluent
+        }
+    }
+}
+
+impl Confluence {
+    pub fn to_string(&self, symset: &SymbolSet) -> String {
+        match self {
+            Confluence::Confluent => {
+                "The SRS is confluent: every critical pair joins, and it terminates.".to_owned()
+            }
+            Confluence::LocallyConfluent => concat!(
+                "The SRS is locally confluent: every critical pair joins, ",
+                "but it couldn't be shown to terminate."
+            )
+            .to_owned(),
+            Confluence::AnchoredUnchecked => concat!(
+                "Every critical pair of the unanchored rules joins, ",
+                "but the anchored rules weren't checked."
+            )
+            .to_owned(),
+            Confluence::NotJoinable(pairs) => {
+                let mut result = format!("{} critical pairs didn't join:", pairs.len());
+                for unjoinable in pairs {
+                    result.push_str(&format!(
+                        "\n{} and {}{}",
+                        derivation_to_string(&unjoinable.left_derivation, symset),
+                        derivation_to_string(&unjoinable.right_derivation, symset),
+                        if unjoinable.exhaustive {
+                            ""
+                        } else {
+                            " (might join with more steps)"
+                        }
+                    ));
+                }
+                result
+            }
+        }
+    }
+}
+
+//The critical pairs of l1 - r1 with l2 - r2, where l2 is inside l1 or starts in the middle of it.
+//Pairs whose sides are the same are included.
+pub(crate) fn overlaps(
+    (l1, r1): (&[SymbolIdx], &[SymbolIdx]),
+    (l2, r2): (&[SymbolIdx], &[SymbolIdx]),
+) -> Vec<CriticalPair> {
+    let mut pairs = vec![];
+    //l2 inside l1, other than the same rule applied in the same place
+    for position in 0..(l1.len() + 1).saturating_sub(l2.len()) {
+        if l1[position..position + l2.len()] != l2[..] || (position == 0 && l1 == l2 && r1 == r2) {
+            continue;
+        }
+        pairs.push(CriticalPair {
+            peak: l1.to_vec(),
+            left: apply(l1, l1, r1, 0),
+            right: apply(l1, l2, r2, position),
+        });
+    }
+    //The end of l1 overlapping the start of l2
+    for overlap in 1..l1.len().min(l2.len()) {
+        if l1[l1.len() - overlap..] != l2[..overlap] {
+            continue;
+        }
+        let mut peak = l1.to_vec();
+        peak.extend(&l2[overlap..]);
+        pairs.push(CriticalPair {
+            left: apply(&peak, l1, r1, 0),
+            right: apply(&peak, l2, r2, l1.len() - overlap),
+            peak,
+        });
+    }
+    pairs
+}
+
+//The peak with lhs at position replaced by rhs
+fn apply(
+    peak: &[SymbolIdx],
+    lhs: &[SymbolIdx],
+    rhs: &[SymbolIdx],
+    position: usize,
+) -> RuleApplication {
+    let mut result = peak[..position].to_vec();
+    result.extend(rhs);
+    result.extend(&peak[position + lhs.len()..]);
+    RuleApplication {
+        lhs: lhs.to_vec(),
+        rhs: rhs.to_vec(),
+        position,
+        result,
+    }
+}
+
+struct Exploration {
+    //Every string reached, along with the one it was rewritten from
+    parents: HashMap<Vec<SymbolIdx>, Option<Vec<SymbolIdx>>>,
+    //The first string reached that can't be rewritten, if any
+    normal_form: Option<Vec<SymbolIdx>>,
+    //Whether nothing was cut off by max_steps or JOIN_SEARCH_STRINGS
+    exhaustive: bool,
+}
+
+fn explore(ruleset: &Ruleset, start: &[SymbolIdx], max_steps: usize) -> Exploration {
+    let mut exploration = Exploration {
+        parents: HashMap::from([(start.to_vec(), None)]),
+        normal_form: None,
+        exhaustive: true,
+    };
+    let mut queue = VecDeque::from([(start.to_vec(), 0)]);
+    while let Some((string, steps)) = queue.pop_front() {
+        let next_strings = rule_hash(ruleset, &string, true);
+        if next_strings.is_empty() && exploration.normal_form.is_none() {
+            exploration.normal_form = Some(string.clone());
+        }
+        if steps == max_steps {
+            exploration.exhaustive &= next_strings
+                .iter()
+                .all(|next| exploration.parents.contains_key(next));
+            continue;
+        }
+        for next in next_strings {
+            if exploration.parents.contains_key(&next) {
+                continue;
+            }
+            if exploration.parents.len() == JOIN_SEARCH_STRINGS {
+                exploration.exhaustive = false;
+                break;
+            }
+            exploration.parents.insert(next.clone(), Some(string.clone()));
+            queue.push_back((next, steps + 1));
+        }
+    }
+    exploration
+}
@@ -1,15 +1,18 @@
-use crate::{SymbolIdx, SymbolSet};
+use crate::{
+    util::{JflapError, NFA},
+    SymbolIdx, SymbolSet,
+};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::{
     collections::{HashMap, HashSet},
+    hash::Hash,
     io::Read,
 };
-use xml::{
-    reader::EventReader,
-    writer::{EmitterConfig, XmlEvent},
-};
+use xml::writer::{EmitterConfig, XmlEvent};
 
 use bitvec::prelude::*;
+use num_bigint::{BigUint, RandBigInt};
+use rand::Rng;
 use serde_json::Result;
 use std::io::Write;
 
@@ -23,11 +26,13 @@ pub struct DFA<Input = String, Output = bool> {
 }
 
 
-enum JFLAPTrans {
-    From,
-    To,
-    Read,
-    Unknown,
+//Result of DFA::distinguishability_k
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Distinguishability {
+    pub k: usize,
+    //Two reachable states that no string shorter than k tells apart, and a suffix of length k
+    //that does. None if all the reachable states are equivalent.
+    pub witness: Option<(usize, usize, Vec<SymbolIdx>)>,
 }
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -57,8 +62,8 @@ where
         visited.insert((self.starting_state, other.starting_state));
         while let Some(pair) = stack.pop() {
             if self.accepting_states[pair.0] != other.accepting_states[pair.1] {
-                self_more |= self.accepting_states[pair.0] >= self.accepting_states[pair.1];
-                other_more |= self.accepting_states[pair.0] <= self.accepting_states[pair.1];
+                self_more |= self.accepting_states[pair.0] >= other.accepting_states[pair.1];
+                other_more |= self.accepting_states[pair.0] <= other.accepting_states[pair.1];
                 if self_more && other_more {
                     return None;
                 }
@@ -94,11 +99,11 @@ where
     type Output = DFA<I, O>;
     fn not(self) -> Self::Output {
         let mut clone = self.clone();
-        let mut inverted_accepting_states = Vec::new();
-        for i in 0..self.state_transitions.len() {
-            inverted_accepting_states[i] = !self.accepting_states[i].clone();
-        }
-        clone.accepting_states = inverted_accepting_states;
+        clone.accepting_states = self
+            .accepting_states
+            .iter()
+            .map(|accepting| !accepting.clone())
+            .collect();
         clone
     }
 }
@@ -207,121 +212,17 @@ impl DFA {
         self.symbol_set = expanded_ss;
     }
 
-    pub fn load_jflap_from_string(input_xml: &str) -> Self {
-        let mut trans_table;
-        let mut accepting_states = vec![];
-        let mut num_states = 0;
-        let mut starting_state = 0;
-        let mut e_reader = EventReader::from_str(input_xml);
-        let mut cur_state = 0;
-        let mut trans_vec = vec![];
-        let mut unique_reps = HashSet::new();
-        let mut jflap_trans = JFLAPTrans::Unknown;
-
-        let mut state_ids: HashMap<String, usize> = HashMap::new();
-
-        while let Ok(cur_event) = e_reader.next() {
-            match cur_event {
-                xml::reader::XmlEvent::EndDocument => break,
-                xml::reader::XmlEvent::Whitespace(_) => {}
-                xml::reader::XmlEvent::StartDocument {
-                    version: _,
-                    encoding: _,
-                    standalone: _,
-                } => {}
-                xml::reader::XmlEvent::Comment(_) => {}
-                xml::reader::XmlEvent::StartElement {
-                    name,
-                    attributes,
-                    namespace: _,
-                } => match &name.local_name[..] {
-                    "state" => {
-                        let cur_str = &attributes
-                            .iter()
-                            .find(|&x| x.name.local_name == "id")
-                            .unwrap()
-                            .value;
-                        cur_state = cur_str.parse().unwrap();
-                        state_ids.insert(cur_str.clone(), num_states);
-                        num_states += 1;
-                    }
-                    "initial" => starting_state = cur_state,
-                    "final" => {
-                        accepting_states[num_states - 1] = true;
-                    }
-                    "transition" => trans_vec.push((0, 0, "".to_owned())),
-                    "from" => jflap_trans = JFLAPTrans::From,
-                    "to" => jflap_trans = JFLAPTrans::To,
-                    "read" => jflap_trans = JFLAPTrans::Read,
-                    _ => {}
-                },
-                xml::reader::XmlEvent::EndElement { name } => match &name.local_name[..] {
-                    "from" | "to" | "read" => jflap_trans = JFLAPTrans::Unknown,
-                    _ => {}
-                },
-                xml::reader::XmlEvent::Characters(chars) => match jflap_trans {
-                    JFLAPTrans::From => {
-                        trans_vec.last_mut().unwrap().0 = *state_ids.get(&chars).unwrap()
-                    }
-                    JFLAPTrans::To => {
-                        trans_vec.last_mut().unwrap().1 = *state_ids.get(&chars).unwrap()
-                    }
-                    JFLAPTrans::Read => {
-                        trans_vec.last_mut().unwrap().2 = chars.clone();
-                        unique_reps.insert(chars);
-                    }
-                    JFLAPTrans::Unknown => {}
-                },
-                _ => {}
-            }
-        }
-        let mut reps_vec: Vec<String> = unique_reps.into_iter().collect();
-        reps_vec.sort();
-
-        trans_table = vec![vec![usize::MAX; reps_vec.len()]; num_states];
-
-        for transition in &trans_vec {
-            trans_table[transition.0][reps_vec.iter().position(|x| x == &transition.2).unwrap()] =
-                transition.1;
-        }
-        //If dfa is incomplete
-        if trans_vec.len() < reps_vec.len() * trans_table.len() {
-            let mut error_state_already = None;
-            for state_idx in 0..trans_table.len() {
-                if !accepting_states[state_idx]
-                    && trans_table[state_idx]
-                        .iter()
-                        .all(|f| f == &state_idx || f == &usize::MAX)
-                {
-                    error_state_already = Some(state_idx);
-                }
-            }
-            let error_state = match error_state_already {
-                Some(e_state) => e_state,
-                None => {
-                    trans_table.push(vec![trans_table.len(); reps_vec.len()]);
-                    trans_table.len() - 1
-                }
-            };
-            for state_trans in &mut trans_table {
-                state_trans.iter_mut().for_each(|f| {
-                    if *f == usize::MAX {
-                        *f = error_state
-                    }
-                });
-            }
-        }
+    //JFLAP automata don't have to be deterministic (or complete), so they're read as an NFA.
+    //Deterministic ones keep their states as they are, with missing transitions going to a
+    //single rejecting state; anything else goes through subset construction.
+    pub fn parse_jflap(input_xml: &str) -> std::result::Result<Self, JflapError> {
+        let nfa = NFA::parse_jflap(input_xml)?;
+        Ok(nfa.to_dfa_if_deterministic().unwrap_or_else(|| nfa.to_dfa()))
+    }
 
-        let temp = SymbolSet {
-            length: reps_vec.len(),
-            representations: reps_vec,
-        };
-        DFA {
-            starting_state: starting_state,
-            state_transitions: trans_table,
-            accepting_states: accepting_states,
-            symbol_set: temp,
-        }
+    //Panics on anything parse_jflap would report
+    pub fn load_jflap_from_string(input_xml: &str) -> Self {
+        Self::parse_jflap(input_xml).unwrap()
     }
 
     pub fn save_jflap_to_bytes(&self) -> Vec<u8> {
@@ -374,6 +275,25 @@ impl DFA {
         output_str
     }
 
+    //Graphviz version of the DFA, e.g. for rendering with `dot -Tsvg`
+    pub fn to_dot(&self) -> String {
+        let symbols = &self.symbol_set.representations;
+        crate::util::dot::automaton_to_dot(
+            self.state_transitions.len(),
+            Some(self.starting_state),
+            &self.accepting_states,
+            self.state_transitions
+                .iter()
+                .enumerate()
+                .flat_map(|(idx, state)| {
+                    state
+                        .iter()
+                        .enumerate()
+                        .map(move |(sym, target)| (idx, *target, symbols[sym].clone()))
+                }),
+        )
+    }
+
     pub fn jflap_save(&self, file: &mut File) {
         //let mut file = fs::File::create(filename.clone().to_owned() + ".jff").unwrap();
         let _ = file.write(&self.save_jflap_to_bytes());
@@ -433,84 +353,6 @@ where
         }
     }
 
-    pub fn minimize(&mut self) {
-        let mut new_partition_membership = vec![0; self.state_transitions.len()];
-        let mut old_partition_membership = vec![0; self.state_transitions.len()];
-        let mut new_partitions: Vec<Vec<usize>> = vec![vec![], vec![]];
-        let mut old_partitions = vec![];
-
-        let mut old_outputs = Vec::new();
-        for i in 0..self.state_transitions.len() {
-            let idx = match (&mut old_outputs.clone().into_iter())
-                .position(|x| x == self.accepting_states[i])
-            {
-                Some(idx) => idx,
-                None => {
-                    old_outputs.push(self.accepting_states[i].clone());
-                    old_outputs.len() - 1
-                }
-            };
-            new_partition_membership[i] = idx;
-            new_partitions[idx].push(i)
-        }
-
-        while new_partitions.len() > old_partitions.len() {
-            std::mem::swap(&mut old_partitions, &mut new_partitions);
-            std::mem::swap(&mut old_partition_membership, &mut new_partition_membership);
-            new_partitions.clear();
-            for partition in &old_partitions {
-                if partition.len() == 1 {
-                    new_partition_membership[partition[0]] = new_partitions.len();
-                    new_partitions.push(partition.clone());
-                    continue;
-                }
-                let mut truth_vals: Vec<Vec<usize>> = vec![];
-                let mut split_partitions: Vec<Vec<usize>> = vec![];
-                for state in partition {
-                    let mut truth_val = vec![0; self.symbol_set.length];
-                    for symbol in 0..self.symbol_set.length {
-                        truth_val[symbol] =
-                            old_partition_membership[self.state_transitions[*state][symbol]];
-                    }
-                    let mut match_found = false;
-                    for tv_idx in 0..truth_vals.len() {
-                        if truth_val == truth_vals[tv_idx] {
-                            split_partitions[tv_idx].push(*state);
-                            new_partition_membership[*state] = new_partitions.len() + tv_idx;
-                            match_found = true;
-                            break;
-                        }
-                    }
-                    if !match_found {
-                        new_partition_membership[*state] = new_partitions.len() + truth_vals.len();
-                        truth_vals.push(truth_val);
-                        split_partitions.push(vec![*state]);
-                    }
-                }
-                new_partitions.append(&mut split_partitions);
-            }
-        }
-        let mut new_accepting = Vec::new();
-        for (_p_index, partition) in new_partitions.iter().enumerate() {
-            new_accepting.push(self.accepting_states[partition[0]].clone());
-        }
-
-        self.starting_state = new_partition_membership[self.starting_state];
-        let mut new_transition_table = vec![];
-
-        for partition in new_partitions {
-            let mut new_transitions = vec![0; self.symbol_set.length];
-            for symbol in 0..self.symbol_set.length {
-                new_transitions[symbol] =
-                    new_partition_membership[self.state_transitions[partition[0]][symbol]];
-            }
-
-            new_transition_table.push(new_transitions);
-        }
-        self.state_transitions = new_transition_table;
-        self.accepting_states = new_accepting;
-    }
-
     pub fn ss_eq(
         &self,
         other: &Self,
@@ -678,6 +520,657 @@ where
     //pub fn one_rule_expand(&self, rules : &Ruleset) -> DFA{ return self.clone()}
 }
 
+impl<I, O> DFA<I, O>
+where
+    O: Clone + Eq + Hash,
+{
+    //Hopcroft's algorithm, so O(n·|Σ|·log n). States are split up by their output first,
+    //then by which blocks their transitions lead to until nothing else can be split.
+    //Unreachable states are kept (merged with whatever they're equivalent to), and the
+    //resulting states are numbered by the lowest original state they contain.
+    pub fn minimize(&mut self) {
+        let state_count = self.state_transitions.len();
+        let symbol_count = self.symbol_set.length;
+        if state_count == 0 {
+            return;
+        }
+
+        //Incoming transitions for each (symbol, state), stored flat
+        let mut incoming_start = vec![0; symbol_count * state_count + 1];
+        for transitions in &self.state_transitions {
+            for (sym, target) in transitions.iter().enumerate() {
+                incoming_start[sym * state_count + target + 1] += 1;
+            }
+        }
+        for i in 1..incoming_start.len() {
+            incoming_start[i] += incoming_start[i - 1];
+        }
+        let mut incoming = vec![0; symbol_count * state_count];
+        let mut fill = incoming_start.clone();
+        for (state, transitions) in self.state_transitions.iter().enumerate() {
+            for (sym, target) in transitions.iter().enumerate() {
+                incoming[fill[sym * state_count + target]] = state;
+                fill[sym * state_count + target] += 1;
+            }
+        }
+
+        //Partition of the states, where each block is a range of elements.
+        //Marked states in a block are moved to the front of its range, up to mid.
+        let mut elements: Vec<usize> = (0..state_count).collect();
+        let mut location: Vec<usize> = (0..state_count).collect();
+        let mut block_of = vec![0; state_count];
+        let mut first = vec![];
+        let mut mid = vec![];
+        let mut end = vec![];
+
+        let mut output_blocks: HashMap<&O, usize> = HashMap::new();
+        let mut by_output: Vec<Vec<usize>> = vec![];
+        for state in 0..state_count {
+            let block = *output_blocks
+                .entry(&self.accepting_states[state])
+                .or_insert(by_output.len());
+            if block == by_output.len() {
+                by_output.push(vec![]);
+            }
+            by_output[block].push(state);
+        }
+        let mut pos = 0;
+        for (block, states) in by_output.iter().enumerate() {
+            first.push(pos);
+            mid.push(pos);
+            for state in states {
+                elements[pos] = *state;
+                location[*state] = pos;
+                block_of[*state] = block;
+                pos += 1;
+            }
+            end.push(pos);
+        }
+
+        //Every block but the largest starts out as a splitter
+        let largest = (0..first.len())
+            .max_by_key(|b| end[*b] - first[*b])
+            .unwrap();
+        let mut waiting: Vec<usize> = (0..first.len()).filter(|b| *b != largest).collect();
+        let mut is_waiting = vec![false; first.len()];
+        for block in &waiting {
+            is_waiting[*block] = true;
+        }
+
+        let mut touched = vec![];
+        while let Some(splitter) = waiting.pop() {
+            is_waiting[splitter] = false;
+            let splitter_states: Vec<usize> = elements[first[splitter]..end[splitter]].to_vec();
+            for sym in 0..symbol_count {
+                //Mark every state with a transition into the splitter on sym
+                for target in &splitter_states {
+                    let idx = sym * state_count + target;
+                    for source in &incoming[incoming_start[idx]..incoming_start[idx + 1]] {
+                        let block = block_of[*source];
+                        let loc = location[*source];
+                        if loc < mid[block] {
+                            continue;
+                        }
+                        if mid[block] == first[block] {
+                            touched.push(block);
+                        }
+                        let swapped = elements[mid[block]];
+                        elements.swap(loc, mid[block]);
+                        location[swapped] = loc;
+                        location[*source] = mid[block];
+                        mid[block] += 1;
+                    }
+                }
+                //Split the marked states of each touched block off into a new block
+                for block in touched.drain(..) {
+                    if mid[block] == end[block] {
+                        mid[block] = first[block];
+                        continue;
+                    }
+                    let new_block = first.len();
+                    first.push(first[block]);
+                    mid.push(first[block]);
+                    end.push(mid[block]);
+                    first[block] = mid[block];
+                    for state in &elements[first[new_block]..end[new_block]] {
+                        block_of[*state] = new_block;
+                    }
+                    is_waiting.push(false);
+                    let to_wait = if is_waiting[block]
+                        || end[new_block] - first[new_block] <= end[block] - first[block]
+                    {
+                        new_block
+                    } else {
+                        block
+                    };
+                    is_waiting[to_wait] = true;
+                    waiting.push(to_wait);
+                }
+            }
+        }
+
+        //Number the blocks by their lowest state
+        let mut renumber = vec![usize::MAX; first.len()];
+        let mut representatives = vec![];
+        for state in 0..state_count {
+            if renumber[block_of[state]] == usize::MAX {
+                renumber[block_of[state]] = representatives.len();
+                representatives.push(state);
+            }
+        }
+        self.state_transitions = representatives
+            .iter()
+            .map(|state| {
+                self.state_transitions[*state]
+                    .iter()
+                    .map(|target| renumber[block_of[*target]])
+                    .collect()
+            })
+            .collect();
+        self.accepting_states = representatives
+            .iter()
+            .map(|state| self.accepting_states[*state].clone())
+            .collect();
+        self.starting_state = renumber[block_of[self.starting_state]];
+    }
+
+    //Drops every state that can't be reached from the starting state.
+    //The states that are left keep their relative order.
+    pub fn trim(&mut self) {
+        let reachable = self.reachable_states(self.starting_state);
+        let order: Vec<usize> = (0..self.state_transitions.len())
+            .filter(|state| reachable[*state])
+            .collect();
+        let mut renumber = vec![0; self.state_transitions.len()];
+        for (new_state, state) in order.iter().enumerate() {
+            renumber[*state] = new_state;
+        }
+        self.renumber(&order, &renumber);
+    }
+
+    //Minimizes, then numbers the states in BFS order from the start (taking symbols in
+    //index order). Any two DFAs for the same language over the same symbol set end up identical.
+    pub fn canonicalize(&mut self) {
+        self.minimize();
+        let mut renumber = vec![usize::MAX; self.state_transitions.len()];
+        let mut order = vec![self.starting_state];
+        renumber[self.starting_state] = 0;
+        let mut next = 0;
+        while next < order.len() {
+            for target in &self.state_transitions[order[next]] {
+                if renumber[*target] == usize::MAX {
+                    renumber[*target] = order.len();
+                    order.push(*target);
+                }
+            }
+            next += 1;
+        }
+        self.renumber(&order, &renumber);
+    }
+
+    //Smallest k such that every two inequivalent reachable states (so every two states of the
+    //minimal DFA) are told apart by some string of length at most k. This is the k the solvers
+    //would need to tell them apart from their signature sets alone.
+    pub fn distinguishability_k(&self) -> Distinguishability {
+        let reachable = self.reachable_states(self.starting_state);
+        //classes[i][state] groups the states no string of length at most i tells apart
+        let mut outputs: HashMap<&O, usize> = HashMap::new();
+        let mut classes = vec![(0..self.state_transitions.len())
+            .map(|state| {
+                let next_class = outputs.len();
+                *outputs
+                    .entry(&self.accepting_states[state])
+                    .or_insert(next_class)
+            })
+            .collect::<Vec<usize>>()];
+        let mut class_count = (0..self.state_transitions.len())
+            .filter(|state| reachable[*state])
+            .map(|state| classes[0][state])
+            .collect::<HashSet<usize>>()
+            .len();
+        loop {
+            let last = classes.last().unwrap();
+            let mut signatures: HashMap<(usize, Vec<usize>), usize> = HashMap::new();
+            let next = (0..self.state_transitions.len())
+                .map(|state| {
+                    if !reachable[state] {
+                        return usize::MAX;
+                    }
+                    let signature = (
+                        last[state],
+                        self.state_transitions[state]
+                            .iter()
+                            .map(|target| last[*target])
+                            .collect(),
+                    );
+                    let next_class = signatures.len();
+                    *signatures.entry(signature).or_insert(next_class)
+                })
+                .collect();
+            if signatures.len() == class_count {
+                break;
+            }
+            class_count = signatures.len();
+            classes.push(next);
+        }
+        let k = classes.len() - 1;
+        if class_count == 1 {
+            return Distinguishability { k, witness: None };
+        }
+
+        //Find two states first told apart at length k, by comparing each state against the lowest
+        //state it agreed with up to length k - 1
+        let mut first_in_class = HashMap::new();
+        let mut pair = None;
+        for state in (0..self.state_transitions.len()).filter(|state| reachable[*state]) {
+            let earlier_class = if k == 0 { 0 } else { classes[k - 1][state] };
+            let first = *first_in_class.entry(earlier_class).or_insert(state);
+            if classes[k][first] != classes[k][state] {
+                pair = Some((first, state));
+                break;
+            }
+        }
+        let (first, second) = pair.unwrap();
+
+        //They agree up to length level - 1 but not level, so some symbol leads to states that
+        //agree up to level - 2 but not level - 1, and so on down to differing outputs
+        let (mut ours, mut theirs) = (first, second);
+        let mut suffix = vec![];
+        for level in (1..=k).rev() {
+            let sym = (0..self.symbol_set.length)
+                .find(|sym| {
+                    classes[level - 1][self.state_transitions[ours][*sym]]
+                        != classes[level - 1][self.state_transitions[theirs][*sym]]
+                })
+                .unwrap();
+            suffix.push(sym as SymbolIdx);
+            ours = self.state_transitions[ours][sym];
+            theirs = self.state_transitions[theirs][sym];
+        }
+        Distinguishability {
+            k,
+            witness: Some((first, second, suffix)),
+        }
+    }
+
+    pub(crate) fn reachable_states(&self, from: usize) -> Vec<bool> {
+        let mut reachable = vec![false; self.state_transitions.len()];
+        reachable[from] = true;
+        let mut stack = vec![from];
+        while let Some(state) = stack.pop() {
+            for target in &self.state_transitions[state] {
+                if !reachable[*target] {
+                    reachable[*target] = true;
+                    stack.push(*target);
+                }
+            }
+        }
+        reachable
+    }
+
+    //Keeps the states in `order` (old numbers), where renumber maps old numbers to new ones
+    fn renumber(&mut self, order: &[usize], renumber: &[usize]) {
+        self.state_transitions = order
+            .iter()
+            .map(|state| {
+                self.state_transitions[*state]
+                    .iter()
+                    .map(|target| renumber[*target])
+                    .collect()
+            })
+            .collect();
+        self.accepting_states = order
+            .iter()
+            .map(|state| self.accepting_states[*state].clone())
+            .collect();
+        self.starting_state = renumber[self.starting_state];
+    }
+}
+
+//Decision procedures. Whenever the answer is no, the error is a shortest string showing why
+//(the first one in symbol order, if there's a tie).
+//Comparisons between two DFAs assume they share a symbol set, as PartialOrd does.
+impl<I> DFA<I, bool> {
+    //Err is the shortest accepted string
+    pub fn is_empty(&self) -> std::result::Result<(), Vec<SymbolIdx>> {
+        match shortest_witness(
+            self.starting_state,
+            self.symbol_set.length,
+            |state, sym| self.state_transitions[state][sym],
+            |state| self.accepting_states[state],
+        ) {
+            Some(witness) => Err(witness),
+            None => Ok(()),
+        }
+    }
+
+    //Err is the shortest rejected string
+    pub fn is_universal(&self) -> std::result::Result<(), Vec<SymbolIdx>> {
+        match shortest_witness(
+            self.starting_state,
+            self.symbol_set.length,
+            |state, sym| self.state_transitions[state][sym],
+            |state| !self.accepting_states[state],
+        ) {
+            Some(witness) => Err(witness),
+            None => Ok(()),
+        }
+    }
+
+    //Err is the shortest accepted string that goes through a loop. Repeating that loop
+    //gives infinitely many more accepted strings.
+    pub fn is_finite(&self) -> std::result::Result<(), Vec<SymbolIdx>> {
+        let mut graph = petgraph::graph::DiGraph::<(), ()>::new();
+        for _ in 0..self.state_transitions.len() {
+            graph.add_node(());
+        }
+        let mut in_loop = vec![false; self.state_transitions.len()];
+        for (state, transitions) in self.state_transitions.iter().enumerate() {
+            for target in transitions {
+                graph.add_edge((state as u32).into(), (*target as u32).into(), ());
+                in_loop[state] |= *target == state;
+            }
+        }
+        for scc in petgraph::algo::tarjan_scc(&graph) {
+            if scc.len() > 1 {
+                for state in scc {
+                    in_loop[state.index()] = true;
+                }
+            }
+        }
+        //Tracks whether a loop has been passed through yet alongside the state
+        match shortest_witness(
+            (self.starting_state, in_loop[self.starting_state]),
+            self.symbol_set.length,
+            |(state, looped), sym| {
+                let next = self.state_transitions[state][sym];
+                (next, looped || in_loop[next])
+            },
+            |(state, looped)| looped && self.accepting_states[state],
+        ) {
+            Some(witness) => Err(witness),
+            None => Ok(()),
+        }
+    }
+
+    //Err is the shortest string self accepts and other doesn't
+    pub fn is_subset_of(&self, other: &Self) -> std::result::Result<(), Vec<SymbolIdx>> {
+        match self.pair_witness(other, |ours, theirs| ours && !theirs) {
+            Some(witness) => Err(witness),
+            None => Ok(()),
+        }
+    }
+
+    //The shortest string exactly one of the DFAs accepts, if their languages differ at all
+    pub fn difference_witness(&self, other: &Self) -> Option<Vec<SymbolIdx>> {
+        self.pair_witness(other, |ours, theirs| ours != theirs)
+    }
+
+    //How many strings of exactly length n are accepted
+    pub fn count_accepted(&self, n: usize) -> BigUint {
+        self.count_accepted_series(n).pop().unwrap()
+    }
+
+    //How many strings of each length from 0 to max_len (inclusive) are accepted
+    pub fn count_accepted_series(&self, max_len: usize) -> Vec<BigUint> {
+        //How many strings of the current length end up in each state
+        let mut counts = vec![BigUint::ZERO; self.state_transitions.len()];
+        counts[self.starting_state] = BigUint::from(1u8);
+        let mut series = vec![];
+        for len in 0..=max_len {
+            series.push(
+                counts
+                    .iter()
+                    .enumerate()
+                    .filter(|(state, _)| self.accepting_states[*state])
+                    .map(|(_, count)| count)
+                    .sum(),
+            );
+            if len == max_len {
+                break;
+            }
+            let mut next_counts = vec![BigUint::ZERO; self.state_transitions.len()];
+            for (state, count) in counts.iter().enumerate() {
+                if *count == BigUint::ZERO {
+                    continue;
+                }
+                for target in &self.state_transitions[state] {
+                    next_counts[*target] += count;
+                }
+            }
+            counts = next_counts;
+        }
+        series
+    }
+
+    //A uniformly random accepted string of exactly length len, if there are any
+    pub fn sample_accepted<R>(&self, len: usize, rng: &mut R) -> Option<Vec<SymbolIdx>>
+    where
+        R: Rng + ?Sized,
+    {
+        self.sample(len, rng, true)
+    }
+
+    //A uniformly random rejected string of exactly length len, if there are any
+    pub fn sample_rejected<R>(&self, len: usize, rng: &mut R) -> Option<Vec<SymbolIdx>>
+    where
+        R: Rng + ?Sized,
+    {
+        self.sample(len, rng, false)
+    }
+
+    //Picks each symbol with probability proportional to how many of the wanted strings
+    //continue with it, which makes every one of them equally likely
+    fn sample<R>(&self, len: usize, rng: &mut R, accepted: bool) -> Option<Vec<SymbolIdx>>
+    where
+        R: Rng + ?Sized,
+    {
+        //completions[r][state] is how many strings of length r lead from state to the wanted output
+        let mut completions: Vec<Vec<BigUint>> = vec![self
+            .accepting_states
+            .iter()
+            .map(|output| BigUint::from((*output == accepted) as u8))
+            .collect()];
+        for remaining in 1..=len {
+            let next = self
+                .state_transitions
+                .iter()
+                .map(|transitions| {
+                    transitions
+                        .iter()
+                        .map(|target| &completions[remaining - 1][*target])
+                        .sum()
+                })
+                .collect();
+            completions.push(next);
+        }
+
+        let mut state = self.starting_state;
+        if completions[len][state] == BigUint::ZERO {
+            return None;
+        }
+        let mut result = Vec::with_capacity(len);
+        for remaining in (0..len).rev() {
+            let mut choice = rng.gen_biguint_below(&completions[remaining + 1][state]);
+            for (sym, target) in self.state_transitions[state].iter().enumerate() {
+                let count = &completions[remaining][*target];
+                if choice < *count {
+                    result.push(sym as SymbolIdx);
+                    state = *target;
+                    break;
+                }
+                choice -= count;
+            }
+        }
+        Some(result)
+    }
+
+    //Every accepted string of length at most max_len, in lexicographic (dictionary) order.
+    //Branches that can't reach an accepting state in time are never explored, so there's
+    //at most O(max_len) work between strings.
+    pub fn accepted_strings(&self, max_len: usize) -> impl Iterator<Item = Vec<SymbolIdx>> + '_ {
+        AcceptedStrings {
+            dfa: self,
+            max_len,
+            distance: self.distance_to_accepting(),
+            word: vec![],
+            states: vec![self.starting_state],
+            started: false,
+        }
+    }
+
+    //Length of the shortest string leading from each state to an accepting one
+    fn distance_to_accepting(&self) -> Vec<usize> {
+        let mut incoming = vec![vec![]; self.state_transitions.len()];
+        for (state, transitions) in self.state_transitions.iter().enumerate() {
+            for target in transitions {
+                incoming[*target].push(state);
+            }
+        }
+        let mut distance = vec![usize::MAX; self.state_transitions.len()];
+        let mut queue = std::collections::VecDeque::new();
+        for (state, accepting) in self.accepting_states.iter().enumerate() {
+            if *accepting {
+                distance[state] = 0;
+                queue.push_back(state);
+            }
+        }
+        while let Some(state) = queue.pop_front() {
+            for source in &incoming[state] {
+                if distance[*source] == usize::MAX {
+                    distance[*source] = distance[state] + 1;
+                    queue.push_back(*source);
+                }
+            }
+        }
+        distance
+    }
+
+    fn pair_witness<F>(&self, other: &Self, differs: F) -> Option<Vec<SymbolIdx>>
+    where
+        F: Fn(bool, bool) -> bool,
+    {
+        shortest_witness(
+            (self.starting_state, other.starting_state),
+            self.symbol_set.length,
+            |(ours, theirs), sym| {
+                (
+                    self.state_transitions[ours][sym],
+                    other.state_transitions[theirs][sym],
+                )
+            },
+            |(ours, theirs)| differs(self.accepting_states[ours], other.accepting_states[theirs]),
+        )
+    }
+}
+
+//BFS for the shortest string leading to a state where found is true.
+//Symbols are tried in order, so ties go to the first string in symbol order.
+fn shortest_witness<S, T, F>(
+    start: S,
+    symbol_count: usize,
+    transition: T,
+    found: F,
+) -> Option<Vec<SymbolIdx>>
+where
+    S: Copy + Eq + Hash,
+    T: Fn(S, usize) -> S,
+    F: Fn(S) -> bool,
+{
+    let mut backpath: HashMap<S, (S, SymbolIdx)> = HashMap::new();
+    let mut queue = std::collections::VecDeque::from([start]);
+    let mut visited = HashSet::from([start]);
+    while let Some(state) = queue.pop_front() {
+        if found(state) {
+            let mut path = vec![];
+            let mut cur_state = state;
+            while let Some((back_state, sym)) = backpath.get(&cur_state) {
+                path.push(*sym);
+                cur_state = *back_state;
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for sym in 0..symbol_count {
+            let next = transition(state, sym);
+            if visited.insert(next) {
+                backpath.insert(next, (state, sym as SymbolIdx));
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+//Depth-first walk over every string that can still be extended to an accepted one in time.
+//states[i] is the state after reading word[..i].
+struct AcceptedStrings<'a, I> {
+    dfa: &'a DFA<I, bool>,
+    max_len: usize,
+    distance: Vec<usize>,
+    word: Vec<SymbolIdx>,
+    states: Vec<usize>,
+    started: bool,
+}
+
+impl<I> AcceptedStrings<'_, I> {
+    //First symbol from `from` onwards that leads somewhere useful after the current word
+    fn next_symbol(&self, from: usize) -> Option<usize> {
+        let remaining = self.max_len - self.word.len() - 1;
+        let state = *self.states.last().unwrap();
+        (from..self.dfa.symbol_set.length)
+            .find(|sym| self.distance[self.dfa.state_transitions[state][*sym]] <= remaining)
+    }
+
+    //Moves on to the next word in lexicographic order, returning false once there are none left
+    fn advance(&mut self) -> bool {
+        if self.word.len() < self.max_len {
+            if let Some(sym) = self.next_symbol(0) {
+                self.push(sym);
+                return true;
+            }
+        }
+        while let Some(last) = self.word.pop() {
+            self.states.pop();
+            if let Some(sym) = self.next_symbol(last as usize + 1) {
+                self.push(sym);
+                return true;
+            }
+        }
+        false
+    }
+
+    fn push(&mut self, sym: usize) {
+        let state = *self.states.last().unwrap();
+        self.word.push(sym as SymbolIdx);
+        self.states.push(self.dfa.state_transitions[state][sym]);
+    }
+}
+
+impl<I> Iterator for AcceptedStrings<'_, I> {
+    type Item = Vec<SymbolIdx>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.started {
+            self.started = true;
+            if self.distance[self.dfa.starting_state] > self.max_len {
+                return None;
+            }
+            if self.dfa.accepting_states[self.dfa.starting_state] {
+                return Some(vec![]);
+            }
+        }
+        loop {
+            if !self.advance() {
+                return None;
+            }
+            if self.dfa.accepting_states[*self.states.last().unwrap()] {
+                return Some(self.word.clone());
+            }
+        }
+    }
+}
+
 impl<'de, I, O> DFA<I, O>
 where
     I: Clone + Serialize + DeserializeOwned,
@@ -692,6 +1185,15 @@ where
         let writer = std::io::BufWriter::new(file);
         serde_json::to_writer(writer, self)
     }
+    //Saves the canonical minimal form, so equal languages are saved identically
+    pub fn save_canonical(&self, file: &mut File) -> Result<()>
+    where
+        O: Eq + Hash,
+    {
+        let mut canonical = self.clone();
+        canonical.canonicalize();
+        canonical.save(file)
+    }
 }
 
 impl<I, O> PartialEq for DFA<I, O>
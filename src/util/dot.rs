@@ -0,0 +1,53 @@
+use std::collections::HashMap;
+
+//Shared pieces of the Graphviz (DOT) exports for DFAs, partial DFAs and rule graphs
+
+//Escapes a label so it can be placed inside a quoted DOT string
+pub(crate) fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+//Writes an automaton as a DOT digraph. Parallel edges are merged into one edge labelled with every symbol on them.
+//States past the end of accepting_states (i.e. ones whose nature isn't known yet) are drawn as plain states.
+pub(crate) fn automaton_to_dot(
+    state_count: usize,
+    starting_state: Option<usize>,
+    accepting_states: &[bool],
+    transitions: impl IntoIterator<Item = (usize, usize, String)>,
+) -> String {
+    let mut merged: Vec<(usize, usize, Vec<String>)> = vec![];
+    let mut merged_idx: HashMap<(usize, usize), usize> = HashMap::new();
+    for (from, to, label) in transitions {
+        match merged_idx.get(&(from, to)) {
+            Some(idx) => merged[*idx].2.push(label),
+            None => {
+                merged_idx.insert((from, to), merged.len());
+                merged.push((from, to, vec![label]));
+            }
+        }
+    }
+
+    let mut result = "digraph DFA {\n    rankdir=LR;\n    node [shape=circle];\n".to_owned();
+    if let Some(start) = starting_state {
+        result.push_str("    start [shape=point];\n");
+        result.push_str(&format!("    start -> q{};\n", start));
+    }
+    for state in 0..state_count {
+        let shape = if accepting_states.get(state) == Some(&true) {
+            "doublecircle"
+        } else {
+            "circle"
+        };
+        result.push_str(&format!("    q{} [shape={}];\n", state, shape));
+    }
+    for (from, to, labels) in merged {
+        result.push_str(&format!(
+            "    q{} -> q{} [label=\"{}\"];\n",
+            from,
+            to,
+            escape(&labels.join(", "))
+        ));
+    }
+    result.push_str("}\n");
+    result
+}
@@ -1,8 +1,23 @@
+mod completion;
+mod confluence;
 mod dfa;
+pub(crate) mod dot;
+mod nerode;
+mod nfa;
+mod regex;
 mod ruleset;
 mod symset;
+pub(crate) mod termination;
 
-pub use crate::util::dfa::DFA;
-pub use crate::util::ruleset::Ruleset;
+pub use crate::util::completion::{
+    Completion, CompletionError, CompletionLimit, CompletionLimits, ReductionOrder,
+};
+pub use crate::util::confluence::{Confluence, CriticalPair, RuleApplication, UnjoinablePair};
+pub use crate::util::dfa::{Distinguishability, DFA};
+pub use crate::util::nerode::{DistinguishedPair, NerodeReport, NerodeState};
+pub use crate::util::nfa::{JflapError, NFA};
+pub use crate::util::regex::RegexError;
+pub use crate::util::ruleset::{Anchor, ExpansionStats, ParseError, Ruleset};
 pub use crate::util::symset::SymbolIdx;
 pub use crate::util::symset::SymbolSet;
+pub use crate::util::termination::{Matrix, Termination, TerminationProof};
@@ -0,0 +1,208 @@
+use std::collections::VecDeque;
+
+use bitvec::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{SymbolIdx, SymbolSet, DFA};
+
+//Myhill-Nerode style explanation of a DFA, e.g. for writing up what a solver found.
+//Each state is described by the shortest string reaching it and by its signature set (which
+//suffixes up to length k it accepts), which is exactly what the solvers tell states apart with.
+//Every pair of states also gets a shortest suffix that only one of them accepts.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct NerodeReport {
+    pub k: usize,
+    pub starting_state: usize,
+    pub states: Vec<NerodeState>,
+    pub pairs: Vec<DistinguishedPair>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct NerodeState {
+    pub accepting: bool,
+    //None for states that can't be reached
+    pub prefix: Option<String>,
+    //Every suffix of length at most k that's accepted from this state, in sig_set_iter order
+    pub signature: Vec<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DistinguishedPair {
+    pub states: (usize, usize),
+    //None if the states are equivalent, which only happens in DFAs that aren't minimal
+    pub suffix: Option<String>,
+}
+
+impl DFA {
+    pub fn nerode_report(&self, k: usize) -> NerodeReport {
+        let reachable = self.reachable_states(self.starting_state);
+        let sig_set: Vec<Vec<SymbolIdx>> = self.symbol_set.sig_set_iter(k).collect();
+        let states = (0..self.state_transitions.len())
+            .map(|state| NerodeState {
+                accepting: self.accepting_states[state],
+                prefix: if reachable[state] {
+                    Some(readable(
+                        &self.symbol_set,
+                        &self.shortest_path_to_state(state),
+                    ))
+                } else {
+                    None
+                },
+                signature: sig_set
+                    .iter()
+                    .filter(|suffix| self.contains_from_start(suffix, state))
+                    .map(|suffix| readable(&self.symbol_set, suffix))
+                    .collect(),
+            })
+            .collect();
+
+        let suffixes = DistinguishingSuffixes::new(self);
+        let state_count = self.state_transitions.len();
+        let mut pairs = vec![];
+        for ours in 0..state_count {
+            for theirs in ours + 1..state_count {
+                pairs.push(DistinguishedPair {
+                    states: (ours, theirs),
+                    suffix: suffixes
+                        .get(ours, theirs)
+                        .map(|suffix| readable(&self.symbol_set, &suffix)),
+                });
+            }
+        }
+        NerodeReport {
+            k,
+            starting_state: self.starting_state,
+            states,
+            pairs,
+        }
+    }
+}
+
+impl NerodeReport {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = format!(
+            "# Myhill-Nerode report\n\n{} states, starting at state {}. Signature sets hold every accepted suffix of length at most k = {}.\n\n",
+            self.states.len(),
+            self.starting_state,
+            self.k
+        );
+        markdown += "| State | Accepting | Shortest prefix | Signature set |\n| - | - | - | - |\n";
+        for (idx, state) in self.states.iter().enumerate() {
+            markdown += &format!(
+                "| {} | {} | {} | {} |\n",
+                idx,
+                if state.accepting { "yes" } else { "no" },
+                state
+                    .prefix
+                    .as_ref()
+                    .map_or("(unreachable)".to_owned(), |prefix| md_escape(prefix)),
+                state
+                    .signature
+                    .iter()
+                    .map(|suffix| md_escape(suffix))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            );
+        }
+        markdown += "\n| States | Shortest distinguishing suffix |\n| - | - |\n";
+        for pair in &self.pairs {
+            markdown += &format!(
+                "| {}, {} | {} |\n",
+                pair.states.0,
+                pair.states.1,
+                pair.suffix
+                    .as_ref()
+                    .map_or("(equivalent)".to_owned(), |suffix| md_escape(suffix))
+            );
+        }
+        markdown
+    }
+}
+
+//Symbol names separated by spaces, with ε for the empty string
+fn readable(symbol_set: &SymbolSet, symbols: &[SymbolIdx]) -> String {
+    if symbols.is_empty() {
+        return "ε".to_owned();
+    }
+    symbols
+        .iter()
+        .map(|sym| symbol_set.representations[*sym as usize].clone())
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+fn md_escape(text: &str) -> String {
+    format!("`{}`", text.replace('|', "\\|"))
+}
+
+//Shortest suffixes telling pairs of states apart. Only the first symbol of each suffix and the pair
+//it leads to are stored, since the suffixes themselves would take up too much room on big DFAs.
+struct DistinguishingSuffixes {
+    state_count: usize,
+    //Indexed by ours * state count + theirs
+    next: Vec<Option<(SymbolIdx, usize)>>,
+    found: BitVec,
+}
+
+impl DistinguishingSuffixes {
+    //BFS backwards over pairs of states, starting from every pair with different outputs
+    fn new(dfa: &DFA) -> Self {
+        let state_count = dfa.state_transitions.len();
+        let mut incoming = vec![vec![vec![]; state_count]; dfa.symbol_set.length];
+        for (state, transitions) in dfa.state_transitions.iter().enumerate() {
+            for (sym, target) in transitions.iter().enumerate() {
+                incoming[sym][*target].push(state);
+            }
+        }
+
+        let mut next: Vec<Option<(SymbolIdx, usize)>> = vec![None; state_count * state_count];
+        let mut found = bitvec![0; state_count * state_count];
+        let mut queue = VecDeque::new();
+        for ours in 0..state_count {
+            for theirs in 0..state_count {
+                if dfa.accepting_states[ours] != dfa.accepting_states[theirs] {
+                    found.set(ours * state_count + theirs, true);
+                    queue.push_back((ours, theirs));
+                }
+            }
+        }
+        while let Some((ours, theirs)) = queue.pop_front() {
+            for (sym, sources) in incoming.iter().enumerate() {
+                for our_source in &sources[ours] {
+                    for their_source in &sources[theirs] {
+                        let pair = our_source * state_count + their_source;
+                        if !found[pair] {
+                            found.set(pair, true);
+                            next[pair] = Some((sym as SymbolIdx, ours * state_count + theirs));
+                            queue.push_back((*our_source, *their_source));
+                        }
+                    }
+                }
+            }
+        }
+        DistinguishingSuffixes {
+            state_count,
+            next,
+            found,
+        }
+    }
+
+    //Follows the pointers from the pair to one with different outputs, or None if the states
+    //are equivalent
+    fn get(&self, ours: usize, theirs: usize) -> Option<Vec<SymbolIdx>> {
+        let mut cur_pair = ours * self.state_count + theirs;
+        if !self.found[cur_pair] {
+            return None;
+        }
+        let mut suffix = vec![];
+        while let Some((sym, next_pair)) = self.next[cur_pair] {
+            suffix.push(sym);
+            cur_pair = next_pair;
+        }
+        Some(suffix)
+    }
+}
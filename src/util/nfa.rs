@@ -0,0 +1,435 @@
+use std::collections::HashMap;
+
+use bitvec::prelude::*;
+use serde::{Deserialize, Serialize};
+use xml::common::{Position, TextPosition};
+use xml::reader::EventReader;
+
+use super::regex::build_subset_dfa;
+use super::{SymbolIdx, SymbolSet, DFA};
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::{fs::File, io::Read};
+
+//Nondeterministic automaton over the same kind of SymbolSet as DFA.
+//Every state can have any number of targets for each symbol (including none),
+//plus epsilon transitions that are taken without reading anything.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct NFA<Input = String> {
+    pub starting_state: usize,
+    pub state_transitions: Vec<Vec<Vec<usize>>>,
+    pub epsilon_transitions: Vec<Vec<usize>>,
+    pub accepting_states: Vec<bool>,
+    pub symbol_set: SymbolSet<Input>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JflapError {
+    //1-indexed, in the XML
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for JflapError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Line {}, column {}: {}", self.line, self.column, self.message)
+    }
+}
+
+fn jflap_error(position: TextPosition, message: String) -> JflapError {
+    JflapError {
+        line: position.row as usize + 1,
+        column: position.column as usize + 1,
+        message,
+    }
+}
+
+enum JFLAPTrans {
+    From,
+    To,
+    Read,
+    Unknown,
+}
+
+impl<I> NFA<I>
+where
+    I: Clone,
+{
+    //Just a starting state that doesn't accept anything
+    pub fn new(symbol_set: SymbolSet<I>) -> Self {
+        let mut result = NFA {
+            starting_state: 0,
+            state_transitions: vec![],
+            epsilon_transitions: vec![],
+            accepting_states: vec![],
+            symbol_set,
+        };
+        result.add_state(false);
+        result
+    }
+
+    pub fn add_state(&mut self, accepting: bool) -> usize {
+        self.state_transitions
+            .push(vec![vec![]; self.symbol_set.length]);
+        self.epsilon_transitions.push(vec![]);
+        self.accepting_states.push(accepting);
+        self.state_transitions.len() - 1
+    }
+
+    pub fn add_transition(&mut self, from: usize, symbol: SymbolIdx, to: usize) {
+        let targets = &mut self.state_transitions[from][symbol as usize];
+        if !targets.contains(&to) {
+            targets.push(to);
+        }
+    }
+
+    pub fn add_epsilon_transition(&mut self, from: usize, to: usize) {
+        if !self.epsilon_transitions[from].contains(&to) {
+            self.epsilon_transitions[from].push(to);
+        }
+    }
+
+    pub fn from_dfa(dfa: &DFA<I>) -> Self {
+        NFA {
+            starting_state: dfa.starting_state,
+            state_transitions: dfa
+                .state_transitions
+                .iter()
+                .map(|transitions| transitions.iter().map(|target| vec![*target]).collect())
+                .collect(),
+            epsilon_transitions: vec![vec![]; dfa.state_transitions.len()],
+            accepting_states: dfa.accepting_states.clone(),
+            symbol_set: dfa.symbol_set.clone(),
+        }
+    }
+
+    //Accepts anything either NFA accepts.
+    //The symbol sets are assumed to match, as in DFA::dfa_product.
+    pub fn union(&self, other: &Self) -> Self {
+        let mut result = self.clone();
+        let offset = result.append(other);
+        let start = result.add_state(false);
+        result.add_epsilon_transition(start, self.starting_state);
+        result.add_epsilon_transition(start, other.starting_state + offset);
+        result.starting_state = start;
+        result
+    }
+
+    //Accepts xy whenever self accepts x and other accepts y
+    pub fn concat(&self, other: &Self) -> Self {
+        let mut result = self.clone();
+        let offset = result.append(other);
+        for state in 0..self.state_transitions.len() {
+            if self.accepting_states[state] {
+                result.accepting_states[state] = false;
+                result.add_epsilon_transition(state, other.starting_state + offset);
+            }
+        }
+        result
+    }
+
+    //Accepts any number of strings self accepts one after another, including none at all
+    pub fn star(&self) -> Self {
+        let mut result = self.clone();
+        let start = result.add_state(true);
+        result.add_epsilon_transition(start, self.starting_state);
+        for state in 0..self.state_transitions.len() {
+            if self.accepting_states[state] {
+                result.add_epsilon_transition(state, start);
+            }
+        }
+        result.starting_state = start;
+        result
+    }
+
+    //Accepts exactly the reversed strings of self
+    pub fn reverse(&self) -> Self {
+        let mut result = NFA::new(self.symbol_set.clone());
+        for _ in 0..self.state_transitions.len() {
+            result.add_state(false);
+        }
+        //State 0 is the new start, and every other state is shifted up by one
+        result.accepting_states[self.starting_state + 1] = true;
+        for (state, transitions) in self.state_transitions.iter().enumerate() {
+            if self.accepting_states[state] {
+                result.add_epsilon_transition(0, state + 1);
+            }
+            for (sym, targets) in transitions.iter().enumerate() {
+                for target in targets {
+                    result.add_transition(target + 1, sym as SymbolIdx, state + 1);
+                }
+            }
+            for target in &self.epsilon_transitions[state] {
+                result.add_epsilon_transition(target + 1, state + 1);
+            }
+        }
+        result
+    }
+
+    pub fn contains(&self, input: &[SymbolIdx]) -> bool {
+        let mut states = self.start_set();
+        for sym in input {
+            states = self.step(&states, *sym as usize);
+        }
+        self.any_accepting(&states)
+    }
+
+    //Subset construction, only keeping the sets reachable from the start.
+    //The result isn't minimized.
+    pub fn to_dfa(&self) -> DFA<I> {
+        build_subset_dfa(
+            self.start_set(),
+            |states, sym| self.step(states, sym),
+            |states| self.any_accepting(states),
+            &self.symbol_set,
+        )
+    }
+
+    //The same automaton as a DFA, with the same state numbering, if no state has an epsilon
+    //transition or more than one target for a symbol. Missing transitions go to a rejecting state
+    //that never leaves itself, which is added at the end if there isn't one already.
+    pub fn to_dfa_if_deterministic(&self) -> Option<DFA<I>> {
+        if self.epsilon_transitions.iter().any(|targets| !targets.is_empty()) {
+            return None;
+        }
+        let mut state_transitions = vec![];
+        for transitions in &self.state_transitions {
+            let mut dfa_transitions = vec![];
+            for targets in transitions {
+                match targets[..] {
+                    [] => dfa_transitions.push(usize::MAX),
+                    [target] => dfa_transitions.push(target),
+                    _ => return None,
+                }
+            }
+            state_transitions.push(dfa_transitions);
+        }
+        let mut accepting_states = self.accepting_states.clone();
+        let is_missing = |target: &usize| *target == usize::MAX;
+        if state_transitions.iter().flatten().any(is_missing) {
+            let dead_state = (0..state_transitions.len())
+                .find(|state| {
+                    !accepting_states[*state]
+                        && state_transitions[*state]
+                            .iter()
+                            .all(|target| target == state || is_missing(target))
+                })
+                .unwrap_or_else(|| {
+                    state_transitions.push(vec![usize::MAX; self.symbol_set.length]);
+                    accepting_states.push(false);
+                    state_transitions.len() - 1
+                });
+            for target in state_transitions.iter_mut().flatten() {
+                if is_missing(target) {
+                    *target = dead_state;
+                }
+            }
+        }
+        Some(DFA {
+            starting_state: self.starting_state,
+            state_transitions,
+            accepting_states,
+            symbol_set: self.symbol_set.clone(),
+        })
+    }
+
+    //Copies other's states after self's, returning how far they were shifted
+    fn append(&mut self, other: &Self) -> usize {
+        let offset = self.state_transitions.len();
+        self.state_transitions
+            .extend(other.state_transitions.iter().map(|transitions| {
+                transitions
+                    .iter()
+                    .map(|targets| targets.iter().map(|target| target + offset).collect())
+                    .collect()
+            }));
+        self.epsilon_transitions.extend(
+            other
+                .epsilon_transitions
+                .iter()
+                .map(|targets| targets.iter().map(|target| target + offset).collect()),
+        );
+        self.accepting_states
+            .extend(other.accepting_states.iter().cloned());
+        offset
+    }
+
+    fn start_set(&self) -> BitVec {
+        let mut states = bitvec![0; self.state_transitions.len()];
+        states.set(self.starting_state, true);
+        self.epsilon_closure(&mut states);
+        states
+    }
+
+    fn step(&self, states: &BitVec, sym: usize) -> BitVec {
+        let mut result = bitvec![0; self.state_transitions.len()];
+        for state in states.iter_ones() {
+            for target in &self.state_transitions[state][sym] {
+                result.set(*target, true);
+            }
+        }
+        self.epsilon_closure(&mut result);
+        result
+    }
+
+    fn epsilon_closure(&self, states: &mut BitVec) {
+        let mut stack: Vec<usize> = states.iter_ones().collect();
+        while let Some(state) = stack.pop() {
+            for target in &self.epsilon_transitions[state] {
+                if !states[*target] {
+                    states.set(*target, true);
+                    stack.push(*target);
+                }
+            }
+        }
+    }
+
+    fn any_accepting(&self, states: &BitVec) -> bool {
+        states.iter_ones().any(|state| self.accepting_states[state])
+    }
+}
+
+impl NFA {
+    //Reads a JFLAP finite automaton, deterministic or not.
+    //Transitions that read nothing are epsilon transitions, and the symbols are every
+    //other read label, sorted.
+    pub fn parse_jflap(input_xml: &str) -> Result<Self, JflapError> {
+        let mut e_reader = EventReader::from_str(input_xml);
+        let mut jflap_trans = JFLAPTrans::Unknown;
+        let mut state_ids: HashMap<String, usize> = HashMap::new();
+        let mut starting_state = 0;
+        let mut accepting_states = vec![];
+        let mut trans_vec: Vec<(Option<usize>, Option<usize>, String)> = vec![];
+
+        loop {
+            let cur_event = match e_reader.next() {
+                Ok(cur_event) => cur_event,
+                Err(e) => {
+                    //The error's own message starts with its position
+                    let message = e.to_string();
+                    let message = message.split_once(' ').map_or(&message[..], |(_, msg)| msg);
+                    return Err(jflap_error(e.position(), message.to_owned()));
+                }
+            };
+            let position = e_reader.position();
+            match cur_event {
+                xml::reader::XmlEvent::EndDocument => break,
+                xml::reader::XmlEvent::StartElement {
+                    name, attributes, ..
+                } => match &name.local_name[..] {
+                    "state" => {
+                        let Some(id) = attributes.iter().find(|&x| x.name.local_name == "id")
+                        else {
+                            return Err(jflap_error(position, "A state needs an id".to_owned()));
+                        };
+                        if state_ids.insert(id.value.clone(), accepting_states.len()).is_some() {
+                            let message = format!("There's already a state with id {}", id.value);
+                            return Err(jflap_error(position, message));
+                        }
+                        accepting_states.push(false);
+                    }
+                    "initial" | "final" if accepting_states.is_empty() => {
+                        let message = format!("<{}> has to be inside a state", name.local_name);
+                        return Err(jflap_error(position, message));
+                    }
+                    "initial" => starting_state = accepting_states.len() - 1,
+                    "final" => *accepting_states.last_mut().unwrap() = true,
+                    "transition" => trans_vec.push((None, None, "".to_owned())),
+                    "from" | "to" | "read" if trans_vec.is_empty() => {
+                        let message =
+                            format!("<{}> has to be inside a transition", name.local_name);
+                        return Err(jflap_error(position, message));
+                    }
+                    "from" => jflap_trans = JFLAPTrans::From,
+                    "to" => jflap_trans = JFLAPTrans::To,
+                    "read" => jflap_trans = JFLAPTrans::Read,
+                    _ => {}
+                },
+                xml::reader::XmlEvent::EndElement { name } => match &name.local_name[..] {
+                    "from" | "to" | "read" => jflap_trans = JFLAPTrans::Unknown,
+                    "transition" => {
+                        if let Some((None, _, _) | (_, None, _)) = trans_vec.last() {
+                            let message = "A transition needs a <from> and a <to>".to_owned();
+                            return Err(jflap_error(position, message));
+                        }
+                    }
+                    _ => {}
+                },
+                xml::reader::XmlEvent::Characters(chars) => match jflap_trans {
+                    JFLAPTrans::From | JFLAPTrans::To => {
+                        let Some(&state) = state_ids.get(&chars) else {
+                            let message = format!("There's no state with id {}", chars);
+                            return Err(jflap_error(position, message));
+                        };
+                        let transition = trans_vec.last_mut().unwrap();
+                        if let JFLAPTrans::From = jflap_trans {
+                            transition.0 = Some(state);
+                        } else {
+                            transition.1 = Some(state);
+                        }
+                    }
+                    JFLAPTrans::Read => trans_vec.last_mut().unwrap().2 = chars,
+                    JFLAPTrans::Unknown => {}
+                },
+                _ => {}
+            }
+        }
+        if accepting_states.is_empty() {
+            let message = "No states were found".to_owned();
+            return Err(jflap_error(TextPosition::new(), message));
+        }
+
+        let mut reps_vec: Vec<String> = trans_vec
+            .iter()
+            .filter(|transition| !transition.2.is_empty())
+            .map(|transition| transition.2.clone())
+            .collect();
+        reps_vec.sort();
+        reps_vec.dedup();
+
+        let state_count = accepting_states.len();
+        let mut result = NFA {
+            starting_state,
+            state_transitions: vec![vec![vec![]; reps_vec.len()]; state_count],
+            epsilon_transitions: vec![vec![]; state_count],
+            accepting_states,
+            symbol_set: SymbolSet {
+                length: reps_vec.len(),
+                representations: reps_vec,
+            },
+        };
+        for (from, to, read) in &trans_vec {
+            //Checked when each transition ended
+            let (from, to) = (from.unwrap(), to.unwrap());
+            if read.is_empty() {
+                result.add_epsilon_transition(from, to);
+            } else {
+                let sym = result
+                    .symbol_set
+                    .representations
+                    .iter()
+                    .position(|x| x == read)
+                    .unwrap();
+                //Indexed directly, since there may be more symbols than SymbolIdx can hold
+                let targets = &mut result.state_transitions[from][sym];
+                if !targets.contains(&to) {
+                    targets.push(to);
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    //Panics on anything parse_jflap would report
+    pub fn load_jflap_from_string(input_xml: &str) -> Self {
+        Self::parse_jflap(input_xml).unwrap()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn jflap_load(file: &mut File) -> Self {
+        let mut contents = "".to_owned();
+        file.read_to_string(&mut contents).unwrap();
+        Self::load_jflap_from_string(&contents)
+    }
+}
@@ -0,0 +1,679 @@
+use std::collections::HashMap;
+
+use super::{SymbolIdx, SymbolSet, DFA};
+
+//Regular expressions over the symbols of a SymbolSet, for writing goals without a DFA editor.
+//
+//Symbols are matched by name, preferring the longest name that fits, so with symbols "1" and "10",
+//"10" is the single symbol 10. Whitespace is ignored and can be used to split names apart ("1 0").
+//From loosest to tightest binding, the operators are:
+//  a|b    union
+//  a&b    intersection
+//  ab     concatenation
+//  !a     complement (every string not matched by a)
+//  a* a+ a?
+//Atoms are symbols, (...), . for any symbol, and classes like [0 1] or [^0].
+//() is the empty string, and \ treats the next character as the start of a symbol name.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegexError {
+    //1-indexed, in characters
+    pub column: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for RegexError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Column {}: {}", self.column, self.message)
+    }
+}
+
+#[derive(Clone, PartialEq, Eq)]
+enum Regex {
+    Epsilon,
+    //Any single symbol in the list
+    Symbols(Vec<SymbolIdx>),
+    Concat(Box<Regex>, Box<Regex>),
+    Union(Box<Regex>, Box<Regex>),
+    Intersection(Box<Regex>, Box<Regex>),
+    Complement(Box<Regex>),
+    Star(Box<Regex>),
+}
+
+const METACHARACTERS: &str = "|&!*+?()[].\\";
+
+struct Parser<'a> {
+    chars: Vec<char>,
+    pos: usize,
+    symbol_set: &'a SymbolSet,
+    //Symbol names as characters, longest first
+    names: Vec<(Vec<char>, SymbolIdx)>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(pattern: &str, symbol_set: &'a SymbolSet) -> Self {
+        let mut names: Vec<(Vec<char>, SymbolIdx)> = symbol_set
+            .representations
+            .iter()
+            .enumerate()
+            .map(|(idx, rep)| (rep.chars().collect(), idx as SymbolIdx))
+            .collect();
+        names.sort_by_key(|(name, _)| std::cmp::Reverse(name.len()));
+        Parser {
+            chars: pattern.chars().collect(),
+            pos: 0,
+            symbol_set,
+            names,
+        }
+    }
+
+    fn error<T>(&self, message: &str) -> Result<T, RegexError> {
+        Err(RegexError {
+            column: self.pos + 1,
+            message: message.to_owned(),
+        })
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        while self.pos < self.chars.len() && self.chars[self.pos].is_whitespace() {
+            self.pos += 1;
+        }
+        self.chars.get(self.pos).copied()
+    }
+
+    fn parse(&mut self) -> Result<Regex, RegexError> {
+        let regex = self.union()?;
+        match self.peek() {
+            None => Ok(regex),
+            Some(')') => self.error("Unmatched )"),
+            Some(_) => self.error("Unexpected character"),
+        }
+    }
+
+    fn union(&mut self) -> Result<Regex, RegexError> {
+        let mut regex = self.intersection()?;
+        while self.peek() == Some('|') {
+            self.pos += 1;
+            regex = Regex::Union(Box::new(regex), Box::new(self.intersection()?));
+        }
+        Ok(regex)
+    }
+
+    fn intersection(&mut self) -> Result<Regex, RegexError> {
+        let mut regex = self.concat()?;
+        while self.peek() == Some('&') {
+            self.pos += 1;
+            regex = Regex::Intersection(Box::new(regex), Box::new(self.concat()?));
+        }
+        Ok(regex)
+    }
+
+    fn concat(&mut self) -> Result<Regex, RegexError> {
+        let mut regex = None;
+        while let Some(c) = self.peek() {
+            if c == '|' || c == '&' || c == ')' {
+                break;
+            }
+            let next = self.complement()?;
+            regex = Some(match regex {
+                None => next,
+                Some(prev) => Regex::Concat(Box::new(prev), Box::new(next)),
+            });
+        }
+        //Nothing at all (e.g. "()" or "0|") is the empty string
+        Ok(regex.unwrap_or(Regex::Epsilon))
+    }
+
+    fn complement(&mut self) -> Result<Regex, RegexError> {
+        if self.peek() == Some('!') {
+            self.pos += 1;
+            return Ok(Regex::Complement(Box::new(self.complement()?)));
+        }
+        let mut regex = self.atom()?;
+        loop {
+            regex = match self.peek() {
+                Some('*') => Regex::Star(Box::new(regex)),
+                Some('+') => Regex::Concat(
+                    Box::new(regex.clone()),
+                    Box::new(Regex::Star(Box::new(regex))),
+                ),
+                Some('?') => Regex::Union(Box::new(regex), Box::new(Regex::Epsilon)),
+                _ => break,
+            };
+            self.pos += 1;
+        }
+        Ok(regex)
+    }
+
+    fn atom(&mut self) -> Result<Regex, RegexError> {
+        match self.peek() {
+            None => self.error("Expected a symbol, but the pattern ended"),
+            Some('(') => {
+                self.pos += 1;
+                let regex = self.union()?;
+                if self.peek() != Some(')') {
+                    return self.error("Expected )");
+                }
+                self.pos += 1;
+                Ok(regex)
+            }
+            Some('[') => {
+                self.pos += 1;
+                let negated = self.peek() == Some('^');
+                if negated {
+                    self.pos += 1;
+                }
+                let mut in_class = vec![false; self.symbol_set.length];
+                loop {
+                    match self.peek() {
+                        None => return self.error("Expected ]"),
+                        Some(']') => break,
+                        Some(_) => in_class[self.symbol()? as usize] = true,
+                    }
+                }
+                self.pos += 1;
+                Ok(Regex::Symbols(
+                    (0..self.symbol_set.length)
+                        .filter(|sym| in_class[*sym] != negated)
+                        .map(|sym| sym as SymbolIdx)
+                        .collect(),
+                ))
+            }
+            Some('.') => {
+                self.pos += 1;
+                Ok(Regex::Symbols(
+                    (0..self.symbol_set.length)
+                        .map(|sym| sym as SymbolIdx)
+                        .collect(),
+                ))
+            }
+            Some(c) if c != '\\' && METACHARACTERS.contains(c) => {
+                self.error(&format!("Expected a symbol, found {}", c))
+            }
+            Some(_) => Ok(Regex::Symbols(vec![self.symbol()?])),
+        }
+    }
+
+    //Reads the longest symbol name at the current position
+    fn symbol(&mut self) -> Result<SymbolIdx, RegexError> {
+        if self.peek() == Some('\\') {
+            self.pos += 1;
+        }
+        let rest = &self.chars[self.pos..];
+        match self.names.iter().find(|(name, _)| rest.starts_with(name)) {
+            Some((name, sym)) => {
+                self.pos += name.len();
+                Ok(*sym)
+            }
+            None => self.error("Not the start of any symbol in the symbol set"),
+        }
+    }
+}
+
+impl Regex {
+    fn to_dfa(&self, symbol_set: &SymbolSet) -> DFA {
+        let mut dfa = match self {
+            Regex::Epsilon => symbols_dfa(&[], true, symbol_set),
+            Regex::Symbols(symbols) => symbols_dfa(symbols, false, symbol_set),
+            Regex::Concat(a, b) => concat_dfa(&a.to_dfa(symbol_set), &b.to_dfa(symbol_set)),
+            Regex::Union(a, b) => &a.to_dfa(symbol_set) | &b.to_dfa(symbol_set),
+            Regex::Intersection(a, b) => &a.to_dfa(symbol_set) & &b.to_dfa(symbol_set),
+            Regex::Complement(a) => !&a.to_dfa(symbol_set),
+            Regex::Star(a) => star_dfa(&a.to_dfa(symbol_set)),
+        };
+        //Keeps the intermediate DFAs from blowing up as they're combined
+        dfa.minimize();
+        dfa
+    }
+}
+
+//Reading one of the symbols from the start (0) accepts (1), anything else is an error (2)
+fn symbols_dfa(symbols: &[SymbolIdx], accept_start: bool, symbol_set: &SymbolSet) -> DFA {
+    build_subset_dfa(
+        0,
+        |state, sym| match state {
+            0 if symbols.contains(&(sym as SymbolIdx)) => 1,
+            _ => 2,
+        },
+        |state| *state == 1 || (*state == 0 && accept_start),
+        symbol_set,
+    )
+}
+
+//Subset construction for ab: each state is a state of a, plus every state b could be in
+//given where b could have started so far
+fn concat_dfa(a: &DFA, b: &DFA) -> DFA {
+    let with_b_start = |a_state: usize, mut b_states: Vec<usize>| {
+        if a.accepting_states[a_state] && !b_states.contains(&b.starting_state) {
+            b_states.push(b.starting_state);
+        }
+        b_states.sort();
+        b_states.dedup();
+        (a_state, b_states)
+    };
+    build_subset_dfa(
+        with_b_start(a.starting_state, vec![]),
+        |(a_state, b_states), sym| {
+            with_b_start(
+                a.state_transitions[*a_state][sym],
+                b_states
+                    .iter()
+                    .map(|b_state| b.state_transitions[*b_state][sym])
+                    .collect(),
+            )
+        },
+        |(_, b_states)| b_states.iter().any(|b_state| b.accepting_states[*b_state]),
+        &a.symbol_set,
+    )
+}
+
+//Subset construction for a*: each state is every state a could be in, with the start kept separate
+//as it accepts the empty string regardless of a
+fn star_dfa(a: &DFA) -> DFA {
+    let restart = |mut states: Vec<usize>| {
+        if states.iter().any(|state| a.accepting_states[*state])
+            && !states.contains(&a.starting_state)
+        {
+            states.push(a.starting_state);
+        }
+        states.sort();
+        states.dedup();
+        (false, states)
+    };
+    build_subset_dfa(
+        (true, vec![a.starting_state]),
+        |(_, states), sym| {
+            restart(
+                states
+                    .iter()
+                    .map(|state| a.state_transitions[*state][sym])
+                    .collect(),
+            )
+        },
+        |(is_start, states)| *is_start || states.iter().any(|state| a.accepting_states[*state]),
+        &a.symbol_set,
+    )
+}
+
+//Explores every state reachable from start, numbering them in the order they're found
+pub(crate) fn build_subset_dfa<I, S, T, A>(
+    start: S,
+    transition: T,
+    accepting: A,
+    symbol_set: &SymbolSet<I>,
+) -> DFA<I>
+where
+    I: Clone,
+    S: Clone + Eq + std::hash::Hash,
+    T: Fn(&S, usize) -> S,
+    A: Fn(&S) -> bool,
+{
+    let mut states = vec![start.clone()];
+    let mut state_idxs = HashMap::new();
+    state_idxs.insert(start, 0);
+    let mut state_transitions = vec![];
+    while state_transitions.len() < states.len() {
+        let state = states[state_transitions.len()].clone();
+        let mut transitions = vec![0; symbol_set.length];
+        for (sym, target) in transitions.iter_mut().enumerate() {
+            let next = transition(&state, sym);
+            *target = match state_idxs.get(&next) {
+                Some(idx) => *idx,
+                None => {
+                    state_idxs.insert(next.clone(), states.len());
+                    states.push(next);
+                    states.len() - 1
+                }
+            };
+        }
+        state_transitions.push(transitions);
+    }
+    DFA {
+        starting_state: 0,
+        accepting_states: states.iter().map(accepting).collect(),
+        state_transitions,
+        symbol_set: symbol_set.clone(),
+    }
+}
+
+//Simplifying constructors and printing, used to turn DFAs back into regular expressions
+impl Regex {
+    fn empty() -> Regex {
+        Regex::Symbols(vec![])
+    }
+
+    fn is_empty(&self) -> bool {
+        *self == Regex::Symbols(vec![])
+    }
+
+    fn union(a: Regex, b: Regex) -> Regex {
+        let mut alternatives = vec![];
+        for regex in [a, b] {
+            regex.flatten_union(&mut alternatives);
+        }
+        //All single symbol alternatives become one class
+        let mut symbols = vec![];
+        alternatives.retain(|regex| match regex {
+            Regex::Symbols(syms) => {
+                symbols.extend(syms.iter().copied());
+                false
+            }
+            _ => true,
+        });
+        symbols.sort();
+        symbols.dedup();
+        if !symbols.is_empty() {
+            alternatives.insert(0, Regex::Symbols(symbols));
+        }
+        let mut unique: Vec<Regex> = vec![];
+        for regex in alternatives {
+            if !unique.contains(&regex) {
+                unique.push(regex);
+            }
+        }
+        //(xx*)? is x*
+        if unique.contains(&Regex::Epsilon) {
+            for regex in unique.iter_mut() {
+                if let Regex::Concat(a, b) = regex {
+                    if **b == Regex::Star(a.clone()) {
+                        *regex = (**b).clone();
+                    }
+                }
+            }
+        }
+        //A star already matches the empty string
+        if unique.iter().any(|regex| matches!(regex, Regex::Star(_))) {
+            unique.retain(|regex| *regex != Regex::Epsilon);
+        }
+        match unique
+            .into_iter()
+            .reduce(|a, b| Regex::Union(Box::new(a), Box::new(b)))
+        {
+            Some(regex) => regex,
+            None => Regex::empty(),
+        }
+    }
+
+    fn flatten_union(self, alternatives: &mut Vec<Regex>) {
+        match self {
+            Regex::Union(a, b) => {
+                a.flatten_union(alternatives);
+                b.flatten_union(alternatives);
+            }
+            regex if regex.is_empty() => {}
+            regex => alternatives.push(regex),
+        }
+    }
+
+    fn concat(a: Regex, b: Regex) -> Regex {
+        if a.is_empty() || b.is_empty() {
+            Regex::empty()
+        } else if a == Regex::Epsilon {
+            b
+        } else if b == Regex::Epsilon {
+            a
+        } else {
+            Regex::Concat(Box::new(a), Box::new(b))
+        }
+    }
+
+    fn star(a: Regex) -> Regex {
+        match a {
+            Regex::Star(_) => a,
+            Regex::Epsilon => Regex::Epsilon,
+            a if a.is_empty() => Regex::Epsilon,
+            //(x|())* is x*
+            Regex::Union(_, _) => {
+                let mut alternatives = vec![];
+                a.flatten_union(&mut alternatives);
+                alternatives.retain(|regex| *regex != Regex::Epsilon);
+                let inner = alternatives.into_iter().fold(Regex::empty(), Regex::union);
+                match inner {
+                    Regex::Star(_) => inner,
+                    inner => Regex::Star(Box::new(inner)),
+                }
+            }
+            a => Regex::Star(Box::new(a)),
+        }
+    }
+
+    //Precedence is 0 for anything, 1 for the inside of a concatenation and 2 for the operand of *, + or ?
+    fn print(&self, symbol_set: &SymbolSet, separator: &str, precedence: u8) -> String {
+        match self {
+            Regex::Epsilon => "()".to_owned(),
+            Regex::Symbols(symbols) => print_symbols(symbols, symbol_set, separator),
+            Regex::Star(a) => format!("{}*", a.print(symbol_set, separator, 2)),
+            Regex::Union(_, _) => {
+                let mut alternatives = vec![];
+                self.clone().flatten_union(&mut alternatives);
+                let optional = alternatives.contains(&Regex::Epsilon);
+                alternatives.retain(|regex| *regex != Regex::Epsilon);
+                if optional && alternatives.len() == 1 {
+                    return format!("{}?", alternatives[0].print(symbol_set, separator, 2));
+                }
+                let printed = alternatives
+                    .iter()
+                    .map(|regex| regex.print(symbol_set, separator, 0))
+                    .collect::<Vec<_>>()
+                    .join("|");
+                if optional {
+                    format!("({})?", printed)
+                } else if precedence > 0 {
+                    format!("({})", printed)
+                } else {
+                    printed
+                }
+            }
+            Regex::Concat(_, _) => {
+                let mut parts = vec![];
+                self.flatten_concat(&mut parts);
+                let mut printed = vec![];
+                let mut idx = 0;
+                while idx < parts.len() {
+                    //xx* is x+
+                    if idx + 1 < parts.len()
+                        && *parts[idx + 1] == Regex::Star(Box::new(parts[idx].clone()))
+                    {
+                        printed.push(format!("{}+", parts[idx].print(symbol_set, separator, 2)));
+                        idx += 2;
+                    } else {
+                        printed.push(parts[idx].print(symbol_set, separator, 1));
+                        idx += 1;
+                    }
+                }
+                let printed = printed.join(separator);
+                if precedence > 1 {
+                    format!("({})", printed)
+                } else {
+                    printed
+                }
+            }
+            Regex::Intersection(a, b) => {
+                let printed = format!(
+                    "{}&{}",
+                    a.print(symbol_set, separator, 1),
+                    b.print(symbol_set, separator, 1)
+                );
+                if precedence > 0 {
+                    format!("({})", printed)
+                } else {
+                    printed
+                }
+            }
+            Regex::Complement(a) => format!("!{}", a.print(symbol_set, separator, 2)),
+        }
+    }
+
+    fn flatten_concat<'a>(&'a self, parts: &mut Vec<&'a Regex>) {
+        match self {
+            Regex::Concat(a, b) => {
+                a.flatten_concat(parts);
+                b.flatten_concat(parts);
+            }
+            regex => parts.push(regex),
+        }
+    }
+}
+
+fn print_symbols(symbols: &[SymbolIdx], symbol_set: &SymbolSet, separator: &str) -> String {
+    let name = |sym: usize| {
+        let rep = &symbol_set.representations[sym];
+        //Names starting with an operator need escaping to be read back in
+        match rep.chars().next() {
+            Some(c) if METACHARACTERS.contains(c) => format!("\\{}", rep),
+            _ => rep.clone(),
+        }
+    };
+    if symbols.len() == 1 {
+        return name(symbols[0] as usize);
+    }
+    if symbols.len() == symbol_set.length && symbols.len() > 1 {
+        return ".".to_owned();
+    }
+    //Whichever of the class and its complement is shorter
+    let (negated, listed): (bool, Vec<usize>) =
+        if symbols.len() * 2 > symbol_set.length && symbols.len() > 1 {
+            (
+                true,
+                (0..symbol_set.length)
+                    .filter(|sym| !symbols.contains(&(*sym as SymbolIdx)))
+                    .collect(),
+            )
+        } else {
+            (false, symbols.iter().map(|sym| *sym as usize).collect())
+        };
+    format!(
+        "[{}{}]",
+        if negated { "^" } else { "" },
+        listed
+            .into_iter()
+            .map(name)
+            .collect::<Vec<_>>()
+            .join(separator)
+    )
+}
+
+impl DFA {
+    //Builds a minimized, complete DFA over symbol_set from a regular expression (see the top of util/regex.rs for the syntax)
+    pub fn from_regex(pattern: &str, symbol_set: &SymbolSet) -> Result<DFA, RegexError> {
+        if symbol_set.length > SymbolIdx::MAX as usize + 1 {
+            return Err(RegexError {
+                column: 1,
+                message: format!(
+                    "The symbol set has {} symbols, but at most {} are supported without the u16-symbols or u32-symbols features",
+                    symbol_set.length,
+                    SymbolIdx::MAX as usize + 1
+                ),
+            });
+        }
+        let regex = Parser::new(pattern, symbol_set).parse()?;
+        Ok(regex.to_dfa(symbol_set))
+    }
+
+    //Describes the language of the DFA as a regular expression (in the syntax from_regex reads) using state elimination.
+    //Only states that are reachable and can still reach an accepting state are kept, and the state with the
+    //fewest paths running through it is eliminated first, which keeps the expression from growing as quickly.
+    pub fn to_regex(&self) -> String {
+        let state_count = self.state_transitions.len();
+        let start = state_count;
+        let end = state_count + 1;
+
+        let reachable = self.reachable_states(self.starting_state);
+        let can_accept = can_accept(self);
+        let useful: Vec<bool> = (0..state_count)
+            .map(|state| reachable[state] && can_accept[state])
+            .collect();
+
+        //edges[i][j] is the expression for getting from i to j, where start and end are extra states
+        //for entering the DFA and leaving it from any accepting state
+        let mut edges: Vec<HashMap<usize, Regex>> = vec![HashMap::new(); state_count + 2];
+        if useful[self.starting_state] {
+            edges[start].insert(self.starting_state, Regex::Epsilon);
+        }
+        for state in (0..state_count).filter(|state| useful[*state]) {
+            for (sym, target) in self.state_transitions[state].iter().enumerate() {
+                if useful[*target] {
+                    let edge = edges[state].remove(target).unwrap_or(Regex::empty());
+                    edges[state].insert(
+                        *target,
+                        Regex::union(edge, Regex::Symbols(vec![sym as SymbolIdx])),
+                    );
+                }
+            }
+            if self.accepting_states[state] {
+                edges[state].insert(end, Regex::Epsilon);
+            }
+        }
+
+        let mut remaining: Vec<usize> = (0..state_count).filter(|state| useful[*state]).collect();
+        while !remaining.is_empty() {
+            //The number of new edges eliminating a state creates
+            let cost = |state: usize| {
+                let ins = (0..state_count + 2)
+                    .filter(|other| *other != state && edges[*other].contains_key(&state))
+                    .count();
+                let outs = edges[state].keys().filter(|other| **other != state).count();
+                ins * outs
+            };
+            let (pos, _) = remaining
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, state)| cost(**state))
+                .unwrap();
+            let state = remaining.remove(pos);
+
+            let mut outs = std::mem::take(&mut edges[state]);
+            let self_loop = Regex::star(outs.remove(&state).unwrap_or(Regex::empty()));
+            let mut outs: Vec<(usize, Regex)> = outs.into_iter().collect();
+            outs.sort_by_key(|(target, _)| *target);
+            for pred_edges in edges.iter_mut() {
+                let into = match pred_edges.remove(&state) {
+                    Some(into) => into,
+                    None => continue,
+                };
+                for (target, out) in &outs {
+                    let through =
+                        Regex::concat(Regex::concat(into.clone(), self_loop.clone()), out.clone());
+                    let edge = pred_edges.remove(target).unwrap_or(Regex::empty());
+                    pred_edges.insert(*target, Regex::union(edge, through));
+                }
+            }
+        }
+
+        let separator = if self
+            .symbol_set
+            .representations
+            .iter()
+            .all(|rep| rep.chars().count() == 1)
+        {
+            ""
+        } else {
+            " "
+        };
+        edges[start]
+            .remove(&end)
+            .unwrap_or(Regex::empty())
+            .print(&self.symbol_set, separator, 0)
+    }
+}
+
+//Which states can still reach an accepting state, found with one search back from all of them
+fn can_accept(dfa: &DFA) -> Vec<bool> {
+    let mut sources: Vec<Vec<usize>> = vec![vec![]; dfa.state_transitions.len()];
+    for (state, targets) in dfa.state_transitions.iter().enumerate() {
+        for target in targets {
+            sources[*target].push(state);
+        }
+    }
+    let mut can_accept = dfa.accepting_states.clone();
+    let mut stack: Vec<usize> = (0..can_accept.len()).filter(|state| can_accept[*state]).collect();
+    while let Some(state) = stack.pop() {
+        for source in &sources[state] {
+            if !can_accept[*source] {
+                can_accept[*source] = true;
+                stack.push(*source);
+            }
+        }
+    }
+    can_accept
+}
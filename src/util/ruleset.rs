@@ -1,15 +1,57 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use super::{SymbolIdx, SymbolSet};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Ruleset {
     pub rules: HashMap<Vec<SymbolIdx>, Vec<Vec<SymbolIdx>>>,
+    //Rules that only apply at the start and/or end of the string, keyed by anchor and lhs.
+    //max_input and min_input count these too.
+    pub anchored_rules: HashMap<(Anchor, Vec<SymbolIdx>), Vec<Vec<SymbolIdx>>>,
     pub symbol_set: SymbolSet,
     pub max_input: usize,
     pub min_input: usize,
 }
 
+//Where an anchored rule's lhs has to be. Written as "^ lhs - rhs", "lhs $ - rhs" and "^ lhs $ - rhs".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Anchor {
+    Start,
+    End,
+    //The lhs has to be the whole string
+    Both,
+}
+
+impl Anchor {
+    pub fn at_start(self) -> bool {
+        matches!(self, Anchor::Start | Anchor::Both)
+    }
+
+    pub fn at_end(self) -> bool {
+        matches!(self, Anchor::End | Anchor::Both)
+    }
+
+    //Where lhs can be rewritten in board, if anywhere.
+    //at_start is false when board is only the end of a longer string, so it has no start to match.
+    pub fn find(self, lhs: &[SymbolIdx], board: &[SymbolIdx], at_start: bool) -> Option<usize> {
+        match self {
+            Anchor::Start if at_start && board.starts_with(lhs) => Some(0),
+            Anchor::End if board.ends_with(lhs) => Some(board.len() - lhs.len()),
+            Anchor::Both if at_start && board == lhs => Some(0),
+            _ => None,
+        }
+    }
+
+    //Adds the ^ and $ around an lhs that's already been written out
+    pub fn lhs_to_string(self, lhs: &str) -> String {
+        match self {
+            Anchor::Start => format!("^ {}", lhs),
+            Anchor::End => format!("{} $", lhs),
+            Anchor::Both => format!("^ {} $", lhs),
+        }
+    }
+}
+
 impl Ruleset {
     pub fn new(rules: HashMap<Vec<SymbolIdx>, Vec<Vec<SymbolIdx>>>, symbol_set: SymbolSet) -> Self {
         Ruleset {
@@ -17,84 +59,240 @@ impl Ruleset {
             max_input: rules.keys().max_by_key(|x| x.len()).unwrap().len(),
             min_input: rules.keys().min_by_key(|x| x.len()).unwrap().len(),
             rules: rules,
+            anchored_rules: HashMap::new(),
         }
     }
 
     pub fn from_vec(rules: Vec<(Vec<SymbolIdx>, Vec<SymbolIdx>)>, symbol_set: SymbolSet) -> Self {
+        Self::from_anchored_vec(
+            rules.into_iter().map(|(lhs, rhs)| (None, lhs, rhs)).collect(),
+            symbol_set,
+        )
+    }
+
+    //Like from_vec, but each rule can be anchored to the start and/or end of the string
+    pub fn from_anchored_vec(
+        rules: Vec<(Option<Anchor>, Vec<SymbolIdx>, Vec<SymbolIdx>)>,
+        symbol_set: SymbolSet,
+    ) -> Self {
         let mut rule_hash: HashMap<Vec<SymbolIdx>, Vec<Vec<SymbolIdx>>> = HashMap::new();
-        //Should use a fancy map function here I admit
-        for i in &rules {
-            match rule_hash.get_mut(&i.0) {
-                Some(result_vec) => result_vec.push(i.1.clone()),
-                None => {
-                    rule_hash.insert(i.0.clone(), vec![i.1.clone()]);
-                }
-            }
+        let mut anchored_hash: HashMap<(Anchor, Vec<SymbolIdx>), Vec<Vec<SymbolIdx>>> =
+            HashMap::new();
+        for (anchor, lhs, rhs) in &rules {
+            let rhs_list = match anchor {
+                Some(anchor) => anchored_hash.entry((*anchor, lhs.clone())).or_default(),
+                None => rule_hash.entry(lhs.clone()).or_default(),
+            };
+            rhs_list.push(rhs.clone());
         }
         Ruleset {
             rules: rule_hash,
-            symbol_set: symbol_set,
-            max_input: rules.iter().max_by_key(|x| x.0.len()).unwrap().0.len(),
-            min_input: rules.iter().min_by_key(|x| x.0.len()).unwrap().0.len(),
+            anchored_rules: anchored_hash,
+            symbol_set,
+            max_input: rules.iter().map(|x| x.1.len()).max().unwrap_or(0),
+            min_input: rules.iter().map(|x| x.1.len()).min().unwrap_or(0),
         }
     }
+
+    //Every lhs along with its anchor (None for rules that apply anywhere) and its rhs list
+    pub fn all_rules(
+        &self,
+    ) -> impl Iterator<Item = (Option<Anchor>, &Vec<SymbolIdx>, &Vec<Vec<SymbolIdx>>)> {
+        self.rules
+            .iter()
+            .map(|(lhs, rhs_list)| (None, lhs, rhs_list))
+            .chain(
+                self.anchored_rules
+                    .iter()
+                    .map(|((anchor, lhs), rhs_list)| (Some(*anchor), lhs, rhs_list)),
+            )
+    }
+    //Panicking version of Ruleset::parse, for rulesets that are known to be well-formed
     pub fn from_string(input_str: &str) -> Self {
-        let mut rules_str: HashMap<Vec<&str>, Vec<Vec<&str>>> = HashMap::new();
+        match Self::parse(input_str) {
+            Ok(ruleset) => ruleset,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    //Parses an SRS where each line is a rule of the form "lhs - rhs", symbols are separated by whitespace
+    //and anything after a # is a comment. If there is no -, the lhs simply goes into nothing.
+    //A ^ before the lhs or a $ after it anchors the rule to the start or end of the string.
+    //Rules can also be patterns that stand for many rules, see parse_with_stats.
+    pub fn parse(input_str: &str) -> Result<Self, ParseError> {
+        Self::parse_with_stats(input_str).map(|(ruleset, _)| ruleset)
+    }
+
+    //Like parse, but also reports how many rules each pattern expanded into.
+    //Patterns are rules using any of
+    // [a b c]         any one of a, b or c (lhs only)
+    // _               any symbol at all (lhs only)
+    // x               a variable declared with "let x = [a b c]" or "let x = _" on its own line,
+    //                 which stands for the same symbol everywhere it's used in a rule
+    //_, ^, $ and anything with [ or ] in it can't be used as symbols, and a line starting with let
+    //is always a variable declaration.
+    //Every combination of symbols is written out as its own rule. Combinations that give a rule
+    //that's already there are dropped, as is any rule that rewrites a string into itself.
+    pub fn parse_with_stats(input_str: &str) -> Result<(Self, ExpansionStats), ParseError> {
+        let lines: Vec<&str> = input_str
+            .split('\n')
+            .map(|line| line.split('#').next().unwrap())
+            .collect();
+        //Where every symbol is first used, to point at it if there are too many
+        let mut symbol_uses: Vec<(usize, usize, &str)> = Vec::new();
+        let variables = parse_variables(&lines, &mut symbol_uses)?;
+
+        let mut rules_str: Vec<(Option<Anchor>, Vec<Pattern>, Vec<Pattern>)> = Vec::new();
+        //Where each rule came from, to point back at it if it's repeated
+        let mut rule_lines: Vec<usize> = Vec::new();
         let mut symbols_rep: Vec<String> = Vec::new();
 
         //Add un-indexed list of rules
-        for line in input_str.split('\n') {
-            let uncommented_line = line.split('#').next().unwrap();
-            //If line is exclusively whitespace
-            if uncommented_line
-                .split_whitespace()
-                .collect::<Vec<_>>()
-                .is_empty()
-            {
+        for (line_idx, uncommented_line) in lines.iter().enumerate() {
+            let line_num = line_idx + 1;
+            //Columns are counted in characters, so the - is located the same way
+            let mut separators = uncommented_line
+                .chars()
+                .enumerate()
+                .filter(|(_, character)| *character == '-')
+                .map(|(column, _)| column + 1);
+            let separator = separators.next();
+            let tokens = tokenize(uncommented_line);
+            if tokens.first().is_some_and(|(_, token)| *token == "let") {
                 continue;
             }
-            let mut split_hs = uncommented_line.split("-");
-
-            //safe unwrap as there must be at least one section in the split
-            let lhs_raw = split_hs.next().unwrap();
+            if let Some(column) = separators.next() {
+                return Err(ParseError {
+                    line: line_num,
+                    column,
+                    token: "-".to_owned(),
+                    message: "A rule can only have one -".to_owned(),
+                });
+            }
 
             //If there is no -, assume the lhs simply goes into nothing
-            let rhs_raw = match split_hs.next() {
-                Some(rhs) => rhs,
-                None => "",
+            let (mut lhs, rhs): (Vec<_>, Vec<_>) = match separator {
+                Some(separator_col) => {
+                    tokens.iter().cloned().partition(|(col, _)| *col < separator_col)
+                }
+                None => (tokens.clone(), vec![]),
+            };
+            //If line is exclusively whitespace
+            if tokens.is_empty() && separator.is_none() {
+                continue;
+            }
+
+            let starts = lhs.first().is_some_and(|(_, token)| *token == "^");
+            if starts {
+                lhs.remove(0);
+            }
+            let ends = lhs.last().is_some_and(|(_, token)| *token == "$");
+            if ends {
+                lhs.pop();
+            }
+            let anchor = match (starts, ends) {
+                (true, true) => Some(Anchor::Both),
+                (true, false) => Some(Anchor::Start),
+                (false, true) => Some(Anchor::End),
+                (false, false) => None,
             };
+            if let Some((column, token)) = lhs
+                .iter()
+                .chain(rhs.iter())
+                .find(|(_, token)| *token == "^" || *token == "$")
+            {
+                return Err(ParseError {
+                    line: line_num,
+                    column: *column,
+                    token: (*token).to_owned(),
+                    message: "^ can only start the lhs of a rule, and $ can only end it".to_owned(),
+                });
+            }
+            if anchor.is_some() && lhs.is_empty() {
+                let (column, token) = tokens[0];
+                return Err(ParseError {
+                    line: line_num,
+                    column,
+                    token: token.to_owned(),
+                    message: "An anchored rule needs at least one symbol on its lhs".to_owned(),
+                });
+            }
+            //Rules can't make symbols out of nothing, and the solvers can't handle ones that do
+            if lhs.is_empty() {
+                return Err(ParseError {
+                    line: line_num,
+                    column: separator.unwrap(),
+                    token: "-".to_owned(),
+                    message: "A rule needs at least one symbol on its lhs".to_owned(),
+                });
+            }
 
-            //Remove any empty strings and collect rest of strings (divided by whitespace) into lhs & rhs
-            let lhs: Vec<_> = lhs_raw
-                .split_whitespace()
-                .filter(|&x| !x.is_empty())
-                .collect();
-            let rhs: Vec<_> = rhs_raw
-                .split_whitespace()
-                .filter(|&x| !x.is_empty())
-                .collect();
-
-            for str in &lhs {
-                if !symbols_rep.contains(&(*str).to_owned()) {
-                    symbols_rep.push((*str).to_owned());
-                }
+            let lhs = read_patterns(&lhs, &variables, line_num, &mut symbol_uses)?;
+            let rhs = read_patterns(&rhs, &variables, line_num, &mut symbol_uses)?;
+            for (column, pattern) in &rhs {
+                let message = match pattern {
+                    Pattern::Class(_) => "Classes and _ can only be used on the lhs".to_owned(),
+                    Pattern::Variable(name) if !lhs.iter().any(|(_, x)| x == pattern) => {
+                        format!("{} has to be on the lhs for the rhs to use it", name)
+                    }
+                    _ => continue,
+                };
+                return Err(ParseError {
+                    line: line_num,
+                    column: *column,
+                    token: tokens.iter().find(|(x, _)| x == column).unwrap().1.to_owned(),
+                    message,
+                });
             }
+            let lhs: Vec<Pattern> = lhs.into_iter().map(|(_, pattern)| pattern).collect();
+            let rhs: Vec<Pattern> = rhs.into_iter().map(|(_, pattern)| pattern).collect();
 
-            for str in &rhs {
-                if !symbols_rep.contains(&(*str).to_owned()) {
-                    symbols_rep.push((*str).to_owned());
-                }
+            let rule = (anchor, lhs, rhs);
+            if let Some(original) = rules_str.iter().position(|x| x == &rule) {
+                return Err(ParseError {
+                    line: line_num,
+                    column: uncommented_line.chars().take_while(|c| c.is_whitespace()).count() + 1,
+                    token: uncommented_line.trim().to_owned(),
+                    message: format!("Duplicate of the rule on line {}", rule_lines[original]),
+                });
             }
+            rules_str.push(rule);
+            rule_lines.push(line_num);
+        }
+        if rules_str.is_empty() {
+            return Err(ParseError {
+                line: 1,
+                column: 1,
+                token: "".to_owned(),
+                message: "No rules were found".to_owned(),
+            });
+        }
 
-            match rules_str.get_mut(&lhs) {
-                Some(rhs_list) => {
-                    rhs_list.push(rhs);
-                }
-                None => {
-                    rules_str.insert(lhs, vec![rhs]);
+        symbol_uses.sort();
+        for (line, column, symbol) in symbol_uses {
+            if !symbols_rep.iter().any(|x| x == symbol) {
+                if symbols_rep.len() > SymbolIdx::MAX as usize {
+                    return Err(ParseError {
+                        line,
+                        column,
+                        token: symbol.to_owned(),
+                        message: format!(
+                            "Too many symbols, at most {} are supported",
+                            SymbolIdx::MAX as usize + 1
+                        ),
+                    });
                 }
+                symbols_rep.push(symbol.to_owned());
             }
         }
+        if symbols_rep.is_empty() {
+            return Err(ParseError {
+                line: 1,
+                column: 1,
+                token: "".to_owned(),
+                message: "No symbols were found".to_owned(),
+            });
+        }
         //Sort symbols according to rust's str system for consistency between dfa & ruleset
         symbols_rep.sort();
 
@@ -103,24 +301,59 @@ impl Ruleset {
             representations: symbols_rep,
         };
 
-        //Convert rules to indexed equivalents
-
-        let mut rules: HashMap<Vec<SymbolIdx>, Vec<Vec<SymbolIdx>>> = HashMap::new();
-
-        for (key, val) in rules_str {
-            let mut rhs = vec![];
-            for result in val {
-                rhs.push(sym_set.string_to_symbols(&result).unwrap());
+        //Convert rules to indexed equivalents, expanding any patterns along the way
+        let indices: HashMap<&str, SymbolIdx> = sym_set
+            .representations
+            .iter()
+            .enumerate()
+            .map(|(idx, symbol)| (&symbol[..], idx as SymbolIdx))
+            .collect();
+        let mut rules = vec![];
+        let mut seen = HashSet::new();
+        let mut stats = ExpansionStats::default();
+        for ((anchor, lhs, rhs), line) in rules_str.iter().zip(rule_lines) {
+            let is_pattern = lhs
+                .iter()
+                .chain(rhs.iter())
+                .any(|pattern| !matches!(pattern, Pattern::Symbol(_)));
+            let mut kept = 0;
+            let expanded = match expand_rule(lhs, rhs, &variables, &indices) {
+                Some(expanded) => expanded,
+                None => {
+                    return Err(ParseError {
+                        line,
+                        column: lines[line - 1].chars().take_while(|c| c.is_whitespace()).count()
+                            + 1,
+                        token: lines[line - 1].trim().to_owned(),
+                        message: format!("Expands into more than {} rules", MAX_EXPANSION),
+                    })
+                }
+            };
+            for (lhs, rhs) in expanded {
+                if lhs == rhs {
+                    stats.identities += 1;
+                } else if !seen.insert((*anchor, lhs.clone(), rhs.clone())) {
+                    stats.duplicates += 1;
+                } else {
+                    kept += 1;
+                    rules.push((*anchor, lhs, rhs));
+                }
+            }
+            if is_pattern {
+                stats.patterns.push((line, kept));
             }
-            rules.insert(sym_set.string_to_symbols(&key).unwrap(), rhs);
         }
-
-        Ruleset {
-            symbol_set: sym_set,
-            max_input: rules.keys().max_by_key(|x| x.len()).unwrap().len(),
-            min_input: rules.keys().min_by_key(|x| x.len()).unwrap().len(),
-            rules: rules,
+        if rules.is_empty() {
+            return Err(ParseError {
+                line: 1,
+                column: 1,
+                token: "".to_owned(),
+                message: "Every rule either changes nothing or repeats another one".to_owned(),
+            });
         }
+        stats.rule_count = rules.len();
+
+        Ok((Self::from_anchored_vec(rules, sym_set), stats))
     }
 
     pub fn expand_to_symset(&mut self, expanded_ss: SymbolSet) {
@@ -130,36 +363,40 @@ impl Ruleset {
             while rep != &expanded_ss.representations[expanded_idx] {
                 expanded_idx += 1;
             }
-            translate_map.insert(idx as u8, expanded_idx as u8);
-        }
-        let mut new_rules = HashMap::new();
-        for (lhs, rhs) in self.rules.iter_mut() {
-            let mut new_lhs = lhs.clone();
-            new_lhs
-                .iter_mut()
-                .for_each(|x| *x = *translate_map.get(x).unwrap());
-            rhs.iter_mut().for_each(|i_vec| {
-                i_vec
-                    .iter_mut()
-                    .for_each(|x| *x = *translate_map.get(x).unwrap())
-            });
-            new_rules.insert(new_lhs, rhs.to_owned());
+            translate_map.insert(idx as SymbolIdx, expanded_idx as SymbolIdx);
         }
-        self.rules = new_rules;
+        let translate = |symbols: &Vec<SymbolIdx>| -> Vec<SymbolIdx> {
+            symbols.iter().map(|x| *translate_map.get(x).unwrap()).collect()
+        };
+        let translate_all = |rhs_list: &Vec<Vec<SymbolIdx>>| -> Vec<Vec<SymbolIdx>> {
+            rhs_list.iter().map(translate).collect()
+        };
+        self.rules = self
+            .rules
+            .iter()
+            .map(|(lhs, rhs_list)| (translate(lhs), translate_all(rhs_list)))
+            .collect();
+        self.anchored_rules = self
+            .anchored_rules
+            .iter()
+            .map(|((anchor, lhs), rhs_list)| ((*anchor, translate(lhs)), translate_all(rhs_list)))
+            .collect();
         self.symbol_set = expanded_ss;
     }
 
     pub fn to_string(&self) -> String {
         let mut result = "".to_owned();
-        for rule in &self.rules {
+        for (anchor, lhs, rhs_list) in self.all_rules() {
             //Map each element to its string rep then join all string reps together by a space.
-            let lhs = rule
-                .0
+            let mut lhs = lhs
                 .iter()
                 .map(|&x| self.symbol_set.representations[x as usize].clone())
                 .collect::<Vec<String>>()
                 .join(" ");
-            for rhs_vec in rule.1 {
+            if let Some(anchor) = anchor {
+                lhs = anchor.lhs_to_string(&lhs);
+            }
+            for rhs_vec in rhs_list {
                 let rhs = rhs_vec
                     .iter()
                     .map(|&x| self.symbol_set.representations[x as usize].clone())
@@ -174,50 +411,361 @@ impl Ruleset {
         result
     }
     pub fn has_generating_rule(&self) -> Option<(Vec<SymbolIdx>, Vec<SymbolIdx>)> {
-        for rule in &self.rules {
-            let lhs_len = rule.0.len();
-            for rhs_vec in rule.1 {
-                if rhs_vec.len() > lhs_len {
-                    return Some((rule.0.clone(), rhs_vec.clone()));
+        for (_, lhs, rhs_list) in self.all_rules() {
+            for rhs_vec in rhs_list {
+                if rhs_vec.len() > lhs.len() {
+                    return Some((lhs.clone(), rhs_vec.clone()));
                 }
             }
         }
         None
     }
     pub fn has_deleting_rule(&self) -> Option<(Vec<SymbolIdx>, Vec<SymbolIdx>)> {
-        for rule in &self.rules {
-            let lhs_len = rule.0.len();
-            for rhs_vec in rule.1 {
-                if rhs_vec.len() < lhs_len {
-                    return Some((rule.0.clone(), rhs_vec.clone()));
+        for (_, lhs, rhs_list) in self.all_rules() {
+            for rhs_vec in rhs_list {
+                if rhs_vec.len() < lhs.len() {
+                    return Some((lhs.clone(), rhs_vec.clone()));
                 }
             }
         }
         None
     }
     pub fn has_non_length_preserving_rule(&self) -> Option<(Vec<SymbolIdx>, Vec<SymbolIdx>)> {
-        for rule in &self.rules {
-            let lhs_len = rule.0.len();
-            for rhs_vec in rule.1 {
-                if rhs_vec.len() != lhs_len {
-                    return Some((rule.0.clone(), rhs_vec.clone()));
+        for (_, lhs, rhs_list) in self.all_rules() {
+            for rhs_vec in rhs_list {
+                if rhs_vec.len() != lhs.len() {
+                    return Some((lhs.clone(), rhs_vec.clone()));
                 }
             }
         }
         None
     }
+    //The first step of a loop find_loop turns up: an lhs and what it gets rewritten into on the
+    //way back around to itself
+    #[deprecated(note = "use find_loop, which gives the whole derivation")]
     pub fn has_definitely_cyclic_rule(&self) -> Option<(Vec<SymbolIdx>, Vec<SymbolIdx>)> {
-        for rule in &self.rules {
-            for rhs_vec in rule.1 {
-                if let Some(rhs_as_lhs_rules) = self.rules.get(rhs_vec) {
-                    for rhs_from_rhs in rhs_as_lhs_rules {
-                        if rule.0 == rhs_from_rhs {
-                            return Some((rule.0.clone(), rhs_vec.clone()));
-                        }
-                    }
+        self.find_loop()
+            .map(|derivation| (derivation[0].clone(), derivation[1].clone()))
+    }
+}
+
+//Splits a line into tokens divided by whitespace or -, along with the (1-indexed) column each starts at
+fn tokenize(line: &str) -> Vec<(usize, &str)> {
+    let mut tokens = vec![];
+    let mut start = None;
+    for (column, (byte_idx, character)) in line.char_indices().enumerate() {
+        match (character.is_whitespace() || character == '-', start) {
+            (false, None) => start = Some((column + 1, byte_idx)),
+            (true, Some((start_col, start_byte))) => {
+                tokens.push((start_col, &line[start_byte..byte_idx]));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some((start_col, start_byte)) = start {
+        tokens.push((start_col, &line[start_byte..]));
+    }
+    tokens
+}
+
+//One symbol's worth of a rule, before classes and variables are expanded
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Pattern<'a> {
+    Symbol(&'a str),
+    //Any of these symbols, or any symbol at all for None (written _)
+    Class(Option<Vec<&'a str>>),
+    //Stands for the same symbol everywhere it's used in a rule
+    Variable(&'a str),
+}
+
+//A variable declared on a "let" line
+struct Variable<'a> {
+    line: usize,
+    //None for _
+    class: Option<Vec<&'a str>>,
+}
+
+//A token or symbol along with the column it starts at
+type Located<'a> = (usize, &'a str);
+
+//Most rules a single line can expand into
+const MAX_EXPANSION: usize = 1 << 20;
+
+//Reads every "let x = [a b c]" and "let x = _" line. The symbols in their classes are added to symbol_uses.
+fn parse_variables<'a>(
+    lines: &[&'a str],
+    symbol_uses: &mut Vec<(usize, usize, &'a str)>,
+) -> Result<HashMap<&'a str, Variable<'a>>, ParseError> {
+    let mut variables: HashMap<&str, Variable> = HashMap::new();
+    for (line_idx, line) in lines.iter().enumerate() {
+        let line_num = line_idx + 1;
+        let tokens = tokenize(line);
+        if tokens.first().map(|(_, token)| *token) != Some("let") {
+            continue;
+        }
+        let malformed = ParseError {
+            line: line_num,
+            column: tokens[0].0,
+            token: line.trim().to_owned(),
+            message: "Variables are declared as \"let x = [a b c]\" or \"let x = _\"".to_owned(),
+        };
+        if tokens.len() < 4 || tokens[2].1 != "=" {
+            return Err(malformed);
+        }
+        let (column, name) = tokens[1];
+        let problem = if matches!(name, "_" | "^" | "$" | "let" | "=") || name.contains(['[', ']'])
+        {
+            Some(format!("{} can't be used as a variable name", name))
+        } else {
+            variables
+                .get(name)
+                .map(|original| format!("{} was already declared on line {}", name, original.line))
+        };
+        if let Some(message) = problem {
+            return Err(ParseError {
+                line: line_num,
+                column,
+                token: name.to_owned(),
+                message,
+            });
+        }
+        let class = if tokens.len() == 4 && tokens[3].1 == "_" {
+            None
+        } else if tokens[3].1.starts_with('[') {
+            let (class, next) = read_class(&tokens, 3, line_num)?;
+            if next != tokens.len() {
+                return Err(malformed);
+            }
+            symbol_uses.extend(class.iter().map(|(column, symbol)| (line_num, *column, *symbol)));
+            Some(class.into_iter().map(|(_, symbol)| symbol).collect())
+        } else {
+            return Err(malformed);
+        };
+        variables.insert(
+            name,
+            Variable {
+                line: line_num,
+                class,
+            },
+        );
+    }
+    //Classes were read before every variable was known
+    for (line, column, symbol) in symbol_uses.iter() {
+        if variables.contains_key(symbol) {
+            return Err(ParseError {
+                line: *line,
+                column: *column,
+                token: (*symbol).to_owned(),
+                message: "Classes can only hold symbols, not variables".to_owned(),
+            });
+        }
+    }
+    Ok(variables)
+}
+
+//Reads one side of a rule into patterns, along with the column each starts at.
+//The symbols it uses are added to symbol_uses.
+fn read_patterns<'a>(
+    tokens: &[(usize, &'a str)],
+    variables: &HashMap<&'a str, Variable<'a>>,
+    line: usize,
+    symbol_uses: &mut Vec<(usize, usize, &'a str)>,
+) -> Result<Vec<(usize, Pattern<'a>)>, ParseError> {
+    let mut patterns = vec![];
+    let mut idx = 0;
+    while idx < tokens.len() {
+        let (column, token) = tokens[idx];
+        idx += 1;
+        let pattern = if token.starts_with('[') {
+            let (class, next) = read_class(tokens, idx - 1, line)?;
+            idx = next;
+            if let Some((column, symbol)) = class.iter().find(|(_, x)| variables.contains_key(x)) {
+                return Err(ParseError {
+                    line,
+                    column: *column,
+                    token: (*symbol).to_owned(),
+                    message: "Classes can only hold symbols, not variables".to_owned(),
+                });
+            }
+            symbol_uses.extend(class.iter().map(|(column, symbol)| (line, *column, *symbol)));
+            Pattern::Class(Some(class.into_iter().map(|(_, symbol)| symbol).collect()))
+        } else if token.contains(['[', ']']) {
+            return Err(ParseError {
+                line,
+                column,
+                token: token.to_owned(),
+                message: "[ can only start a class, and ] can only end one".to_owned(),
+            });
+        } else if token == "_" {
+            Pattern::Class(None)
+        } else if variables.contains_key(token) {
+            Pattern::Variable(token)
+        } else {
+            symbol_uses.push((line, column, token));
+            Pattern::Symbol(token)
+        };
+        patterns.push((column, pattern));
+    }
+    Ok(patterns)
+}
+
+//Reads a class like "[a b c]" starting at tokens[start], whether or not the brackets are separate tokens.
+//Hands back its symbols along with their columns, and the index of the first token after it.
+fn read_class<'a>(
+    tokens: &[(usize, &'a str)],
+    start: usize,
+    line: usize,
+) -> Result<(Vec<Located<'a>>, usize), ParseError> {
+    let mut class: Vec<(usize, &str)> = vec![];
+    for (idx, (column, token)) in tokens.iter().enumerate().skip(start) {
+        let (mut column, mut symbol) = (*column, *token);
+        if idx == start {
+            symbol = &symbol[1..];
+            column += 1;
+        }
+        let closed = symbol.ends_with(']');
+        if closed {
+            symbol = &symbol[..symbol.len() - 1];
+        }
+        if symbol.contains(['[', ']']) || matches!(symbol, "_" | "^" | "$") {
+            return Err(ParseError {
+                line,
+                column,
+                token: symbol.to_owned(),
+                message: "Classes can only hold symbols".to_owned(),
+            });
+        }
+        if !symbol.is_empty() && !class.iter().any(|(_, x)| *x == symbol) {
+            class.push((column, symbol));
+        }
+        if closed {
+            if class.is_empty() {
+                return Err(ParseError {
+                    line,
+                    column: tokens[start].0,
+                    token: "[".to_owned(),
+                    message: "A class needs at least one symbol".to_owned(),
+                });
+            }
+            return Ok((class, idx + 1));
+        }
+    }
+    Err(ParseError {
+        line,
+        column: tokens[start].0,
+        token: "[".to_owned(),
+        message: "This [ is never closed".to_owned(),
+    })
+}
+
+//Every rule that a pattern rule stands for, or None if that's more than MAX_EXPANSION.
+//Classes and variables on the rhs have already been checked by parse_with_stats.
+fn expand_rule(
+    lhs: &[Pattern],
+    rhs: &[Pattern],
+    variables: &HashMap<&str, Variable>,
+    indices: &HashMap<&str, SymbolIdx>,
+) -> Option<Vec<(Vec<SymbolIdx>, Vec<SymbolIdx>)>> {
+    let index = |symbol: &str| indices[symbol];
+    let domain = |class: &Option<Vec<&str>>| -> Vec<SymbolIdx> {
+        match class {
+            Some(class) => class.iter().map(|symbol| index(symbol)).collect(),
+            None => (0..indices.len()).map(|sym| sym as SymbolIdx).collect(),
+        }
+    };
+    //Each class on the lhs and each variable is a choice of symbol, in the order they first show up.
+    //Classes don't have names, and just get used up in order.
+    let mut choices: Vec<(Option<&str>, Vec<SymbolIdx>)> = vec![];
+    for pattern in lhs {
+        match pattern {
+            Pattern::Class(class) => choices.push((None, domain(class))),
+            Pattern::Variable(name) if !choices.iter().any(|(x, _)| *x == Some(*name)) => {
+                choices.push((Some(*name), domain(&variables[name].class)))
+            }
+            _ => {}
+        }
+    }
+    let count = choices
+        .iter()
+        .try_fold(1_usize, |count, (_, domain)| count.checked_mul(domain.len()))
+        .filter(|count| *count <= MAX_EXPANSION)?;
+
+    let mut picks = vec![0; choices.len()];
+    let mut result = Vec::with_capacity(count);
+    for _ in 0..count {
+        let mut classes = (0..choices.len()).filter(|choice| choices[*choice].0.is_none());
+        let mut resolve = |pattern: &Pattern| -> SymbolIdx {
+            let choice = match pattern {
+                Pattern::Symbol(symbol) => return index(symbol),
+                Pattern::Class(_) => classes.next().unwrap(),
+                Pattern::Variable(name) => {
+                    choices.iter().position(|(x, _)| *x == Some(*name)).unwrap()
                 }
+            };
+            choices[choice].1[picks[choice]]
+        };
+        let new_lhs = lhs.iter().map(&mut resolve).collect();
+        let new_rhs = rhs.iter().map(&mut resolve).collect();
+        result.push((new_lhs, new_rhs));
+        //Move on to the next combination, counting with each choice as a digit
+        for (pick, (_, domain)) in picks.iter_mut().zip(&choices).rev() {
+            *pick += 1;
+            if *pick < domain.len() {
+                break;
             }
+            *pick = 0;
         }
-        None
+    }
+    Some(result)
+}
+
+//How Ruleset::parse_with_stats expanded the patterns in an SRS
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExpansionStats {
+    //Every line with a class, _ or variable, along with how many rules it added
+    pub patterns: Vec<(usize, usize)>,
+    //Rules in the parsed ruleset, whether they were written out or came from patterns
+    pub rule_count: usize,
+    //Expanded rules that were dropped for being the same as an earlier one
+    pub duplicates: usize,
+    //Rules that were dropped for rewriting a string into itself, whether they were written out or
+    //came from patterns
+    pub identities: usize,
+}
+
+impl std::fmt::Display for ExpansionStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} rules after expansion", self.rule_count)?;
+        for (line, count) in &self.patterns {
+            write!(f, "\n  Line {}: {} rules", line, count)?;
+        }
+        if self.duplicates > 0 || self.identities > 0 {
+            write!(
+                f,
+                "\n{} duplicate rules and {} rules that change nothing were dropped",
+                self.duplicates, self.identities
+            )?;
+        }
+        Ok(())
+    }
+}
+
+//Why Ruleset::parse couldn't read an SRS. line and column are 1-indexed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub token: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Line {}, column {}: {}", self.line, self.column, self.message)?;
+        if !self.token.is_empty() {
+            write!(f, " (at \"{}\")", self.token)?;
+        }
+        Ok(())
     }
 }
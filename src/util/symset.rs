@@ -1,5 +1,12 @@
 use serde::{Deserialize, Serialize};
+//Index of a symbol in a SymbolSet. Alphabets can't be bigger than SymbolIdx::MAX + 1,
+//so the u16-symbols/u32-symbols features are there for bigger ones.
+#[cfg(not(any(feature = "u16-symbols", feature = "u32-symbols")))]
 pub type SymbolIdx = u8;
+#[cfg(all(feature = "u16-symbols", not(feature = "u32-symbols")))]
+pub type SymbolIdx = u16;
+#[cfg(feature = "u32-symbols")]
+pub type SymbolIdx = u32;
 
 #[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug)]
 pub struct SymbolSet<PrettyInput = String> {
@@ -23,7 +30,7 @@ impl<'a, PrettyInput> Iterator for SymbolSetIter<'a, PrettyInput> {
             Some(self.cur_vec.clone())
         };
         let mut rollover = self.cur_vec.len();
-        while rollover > 0 && self.cur_vec[rollover - 1] == (self.symset.length - 1) as u8 {
+        while rollover > 0 && self.cur_vec[rollover - 1] == (self.symset.length - 1) as SymbolIdx {
             self.cur_vec[rollover - 1] = 0;
             rollover -= 1;
         }
@@ -45,7 +52,9 @@ where
         for sym in symbols {
             string += &format!("{} ", self.representations[*sym as usize]);
         }
-        string.pop();
+        if !symbols.is_empty() {
+            string.pop();
+        }
         string.push('"');
         string
     }
@@ -0,0 +1,504 @@
+use std::collections::{HashMap, VecDeque};
+
+use petgraph::{algo::toposort, prelude::DiGraph};
+
+use super::{Ruleset, SymbolIdx, SymbolSet};
+use crate::solver::rule_hash;
+
+//How many weightings, precedences or matrix interpretations each proof tries before giving up
+const SEARCH_LIMIT: usize = 100_000;
+//Weights are tried from 0 (or 1 for generating SRSs) up to this
+const MAX_WEIGHT: u64 = 4;
+//Matrix interpretations are only tried when the rules use at most this many symbols...
+const MATRIX_SYMBOLS: usize = 4;
+//...with entries up to this
+const MAX_MATRIX_ENTRY: u64 = 2;
+//The quick search for loops runs before any proofs are tried, so that obviously cyclic SRSs
+//don't have to wait for every proof method to fail. The full search runs after they all have.
+const QUICK_LOOP_SEARCH: (usize, usize) = (4, 200);
+const FULL_LOOP_SEARCH: (usize, usize) = (12, 20_000);
+
+pub type Matrix = [[u64; 2]; 2];
+
+//Whether every sequence of rule applications has to stop.
+//Proofs ignore anchors, since an anchored rule only applies where its unanchored version would.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Termination {
+    Terminating(TerminationProof),
+    //A derivation from a string to a string containing it, which can be repeated forever
+    Looping(Vec<Vec<SymbolIdx>>),
+    //Neither a proof nor a loop was found
+    Unknown,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TerminationProof {
+    //Every rule makes the string shorter
+    LengthDecreasing,
+    //Every rule lowers the total weight of the string's symbols, or keeps it the same and makes
+    //the string lexicographically smaller, comparing symbols by their position in precedence
+    Weights {
+        weights: Vec<u64>,
+        precedence: Vec<SymbolIdx>,
+    },
+    //Every rule makes the string smaller in the recursive path order with this precedence
+    //(lowest first), reading strings from the left or from the right
+    PathOrder {
+        precedence: Vec<SymbolIdx>,
+        from_left: bool,
+    },
+    //Each symbol stands for a matrix and a string for the product of its symbols' matrices.
+    //Every rule keeps each entry from going up and lowers the top right one.
+    Matrices(Vec<Matrix>),
+}
+
+impl Ruleset {
+    //Tries to prove that the ruleset terminates, or to find a loop if it doesn't
+    pub fn termination(&self) -> Termination {
+        if let Some(derivation) = find_loop(self, QUICK_LOOP_SEARCH) {
+            return Termination::Looping(derivation);
+        }
+        let rules: Vec<(&Vec<SymbolIdx>, &Vec<SymbolIdx>)> = self
+            .all_rules()
+            .flat_map(|(_, lhs, rhs_list)| rhs_list.iter().map(move |rhs| (lhs, rhs)))
+            .collect();
+        if rules.iter().all(|(lhs, rhs)| rhs.len() < lhs.len()) {
+            return Termination::Terminating(TerminationProof::LengthDecreasing);
+        }
+        let proof = weight_proof(&rules, self.symbol_set.length)
+            .or_else(|| path_order_proof(&rules, self.symbol_set.length))
+            .or_else(|| matrix_proof(&rules, self.symbol_set.length));
+        if let Some(proof) = proof {
+            return Termination::Terminating(proof);
+        }
+        match find_loop(self, FULL_LOOP_SEARCH) {
+            Some(derivation) => Termination::Looping(derivation),
+            None => Termination::Unknown,
+        }
+    }
+
+    //Just the search for a loop that termination falls back on, without trying any proofs.
+    //None doesn't mean the ruleset terminates, only that no loop turned up.
+    pub fn find_loop(&self) -> Option<Vec<Vec<SymbolIdx>>> {
+        find_loop(self, FULL_LOOP_SEARCH)
+    }
+}
+
+impl Termination {
+    pub fn to_string(&self, symset: &SymbolSet) -> String {
+        match self {
+            Termination::Terminating(proof) => {
+                format!("The SRS terminates: {}", proof.to_string(symset))
+            }
+            Termination::Looping(derivation) => format!(
+                "The SRS doesn't terminate: {} can be rewritten forever.",
+                derivation_to_string(derivation, symset)
+            ),
+            Termination::Unknown => {
+                "Unable to prove that the SRS terminates or to find a loop.".to_owned()
+            }
+        }
+    }
+}
+
+impl TerminationProof {
+    pub fn to_string(&self, symset: &SymbolSet) -> String {
+        let precedence_to_string = |precedence: &Vec<SymbolIdx>| {
+            precedence
+                .iter()
+                .map(|sym| symset.representations[*sym as usize].clone())
+                .collect::<Vec<String>>()
+                .join(" < ")
+        };
+        match self {
+            TerminationProof::LengthDecreasing => "every rule shortens the string.".to_owned(),
+            TerminationProof::Weights {
+                weights,
+                precedence,
+            } => format!(
+                "every rule lowers the string's weight ({}), or keeps it and makes the string lexicographically smaller ({}).",
+                weights
+                    .iter()
+                    .enumerate()
+                    .map(|(sym, weight)| format!("{} = {}", symset.representations[sym], weight))
+                    .collect::<Vec<String>>()
+                    .join(", "),
+                precedence_to_string(precedence)
+            ),
+            TerminationProof::PathOrder {
+                precedence,
+                from_left,
+            } => format!(
+                "every rule makes the string smaller in the recursive path order with {}, reading from the {}.",
+                precedence_to_string(precedence),
+                if *from_left { "left" } else { "right" }
+            ),
+            TerminationProof::Matrices(matrices) => format!(
+                "every rule lowers the top right entry of the string's matrix without raising any others ({}).",
+                matrices
+                    .iter()
+                    .enumerate()
+                    .map(|(sym, m)| format!(
+                        "{} = [{} {}; {} {}]",
+                        symset.representations[sym],
+                        m[0][0],
+                        m[0][1],
+                        m[1][0],
+                        m[1][1]
+                    ))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+        }
+    }
+}
+
+//Written out like "1 1 0 -> 0 0 1 -> ..."
+pub(crate) fn derivation_to_string(derivation: &[Vec<SymbolIdx>], symset: &SymbolSet) -> String {
+    derivation
+        .iter()
+        .map(|string| symset.symbols_to_string(string))
+        .collect::<Vec<String>>()
+        .join(" -> ")
+}
+
+//Breadth first search from every lhs for a derivation that comes back around to it.
+//Anchored rules might not apply to a copy of the string in the middle of a longer one,
+//so with any of those around only derivations that return to exactly the same string count.
+fn find_loop(
+    ruleset: &Ruleset,
+    (max_steps, max_strings): (usize, usize),
+) -> Option<Vec<Vec<SymbolIdx>>> {
+    let exact_only = !ruleset.anchored_rules.is_empty();
+    let mut starts: Vec<&Vec<SymbolIdx>> = ruleset.all_rules().map(|(_, lhs, _)| lhs).collect();
+    starts.sort();
+    starts.dedup();
+    for start in starts {
+        let mut parents: HashMap<Vec<SymbolIdx>, Vec<SymbolIdx>> = HashMap::new();
+        let mut queue = VecDeque::from([(start.clone(), 0)]);
+        while let Some((string, steps)) = queue.pop_front() {
+            if steps == max_steps {
+                continue;
+            }
+            for next in rule_hash(ruleset, &string, true) {
+                let loops = if exact_only {
+                    next == *start
+                } else {
+                    start.is_empty() || next.windows(start.len()).any(|window| window == &start[..])
+                };
+                if loops {
+                    let mut derivation = vec![next, string.clone()];
+                    while let Some(parent) = parents.get(derivation.last().unwrap()) {
+                        derivation.push(parent.clone());
+                    }
+                    derivation.reverse();
+                    return Some(derivation);
+                }
+                if parents.len() < max_strings && !parents.contains_key(&next) {
+                    parents.insert(next.clone(), string.clone());
+                    queue.push_back((next, steps + 1));
+                }
+            }
+        }
+    }
+    None
+}
+
+//How many more of each symbol the lhs has than the rhs
+fn count_difference(lhs: &[SymbolIdx], rhs: &[SymbolIdx], symbol_count: usize) -> Vec<i64> {
+    let mut difference = vec![0; symbol_count];
+    for sym in lhs {
+        difference[*sym as usize] += 1;
+    }
+    for sym in rhs {
+        difference[*sym as usize] -= 1;
+    }
+    difference
+}
+
+//The symbols that show up in any rule, since they're the only ones a proof has to pick values for
+fn used_symbols(rules: &[(&Vec<SymbolIdx>, &Vec<SymbolIdx>)]) -> Vec<usize> {
+    let mut used: Vec<usize> = rules
+        .iter()
+        .flat_map(|(lhs, rhs)| lhs.iter().chain(rhs.iter()))
+        .map(|sym| *sym as usize)
+        .collect();
+    used.sort();
+    used.dedup();
+    used
+}
+
+//Orders every symbol so that each (higher, lower) pair is respected, if that's possible
+fn precedence_from(
+    constraints: &[(SymbolIdx, SymbolIdx)],
+    symbol_count: usize,
+) -> Option<Vec<SymbolIdx>> {
+    let mut graph = DiGraph::<(), ()>::new();
+    let nodes: Vec<_> = (0..symbol_count).map(|_| graph.add_node(())).collect();
+    for (higher, lower) in constraints {
+        graph.add_edge(nodes[*lower as usize], nodes[*higher as usize], ());
+    }
+    toposort(&graph, None).ok().map(|order| {
+        order
+            .into_iter()
+            .map(|node| node.index() as SymbolIdx)
+            .collect()
+    })
+}
+
+//Searches for symbol weights that every rule lowers, breaking ties lexicographically.
+//With no generating rules strings can't grow, so zero weights are fine.
+fn weight_proof(
+    rules: &[(&Vec<SymbolIdx>, &Vec<SymbolIdx>)],
+    symbol_count: usize,
+) -> Option<TerminationProof> {
+    let used = used_symbols(rules);
+    let min_weight = if rules.iter().any(|(lhs, rhs)| rhs.len() > lhs.len()) {
+        1
+    } else {
+        0
+    };
+    let differences: Vec<Vec<i64>> = rules
+        .iter()
+        .map(|(lhs, rhs)| count_difference(lhs, rhs, symbol_count))
+        .collect();
+    //A rule can be checked as soon as the last of its symbols has a weight
+    let mut checkable_at = vec![vec![]; used.len()];
+    for (rule_idx, difference) in differences.iter().enumerate() {
+        if let Some(last) = used.iter().rposition(|sym| difference[*sym] != 0) {
+            checkable_at[last].push(rule_idx);
+        }
+    }
+    let mut weights = vec![min_weight; symbol_count];
+    let mut budget = SEARCH_LIMIT;
+    let mut tried = |weights: &Vec<u64>| {
+        let mut constraints = vec![];
+        for ((lhs, rhs), difference) in rules.iter().zip(&differences) {
+            if weight_change(difference, weights) == 0 {
+                //Only a difference inside both strings stays put when they're put in context
+                let first = lhs.iter().zip(rhs.iter()).position(|(l, r)| l != r)?;
+                constraints.push((lhs[first], rhs[first]));
+            }
+        }
+        precedence_from(&constraints, symbol_count)
+    };
+    search_weights(
+        0,
+        &used,
+        &differences,
+        &checkable_at,
+        min_weight,
+        &mut weights,
+        &mut budget,
+        &mut tried,
+    )
+    .map(|precedence| TerminationProof::Weights {
+        weights,
+        precedence,
+    })
+}
+
+fn weight_change(difference: &[i64], weights: &[u64]) -> i64 {
+    difference
+        .iter()
+        .zip(weights)
+        .map(|(count, weight)| count * *weight as i64)
+        .sum()
+}
+
+//Depth first over the used symbols' weights, dropping any branch where an already checkable rule
+//would make the string heavier. Leaves weights set to the first weighting that works.
+#[allow(clippy::too_many_arguments)]
+fn search_weights(
+    depth: usize,
+    used: &[usize],
+    differences: &[Vec<i64>],
+    checkable_at: &[Vec<usize>],
+    min_weight: u64,
+    weights: &mut Vec<u64>,
+    budget: &mut usize,
+    tried: &mut impl FnMut(&Vec<u64>) -> Option<Vec<SymbolIdx>>,
+) -> Option<Vec<SymbolIdx>> {
+    if depth == used.len() {
+        *budget = budget.saturating_sub(1);
+        return tried(weights);
+    }
+    for weight in min_weight..=MAX_WEIGHT {
+        if *budget == 0 {
+            return None;
+        }
+        weights[used[depth]] = weight;
+        if checkable_at[depth]
+            .iter()
+            .all(|rule_idx| weight_change(&differences[*rule_idx], weights) >= 0)
+        {
+            if let Some(precedence) = search_weights(
+                depth + 1,
+                used,
+                differences,
+                checkable_at,
+                min_weight,
+                weights,
+                budget,
+                tried,
+            ) {
+                return Some(precedence);
+            }
+        }
+    }
+    weights[used[depth]] = min_weight;
+    None
+}
+
+//Whether lhs is bigger than rhs in the recursive path order given by each symbol's rank.
+//Strings are read as unary terms over a variable, so a b c is a(b(c(x))).
+fn path_order_greater(lhs: &[SymbolIdx], rhs: &[SymbolIdx], rank: &[usize]) -> bool {
+    //greater[i][j] is whether lhs[i..] is bigger than rhs[j..]
+    let mut greater = vec![vec![false; rhs.len() + 1]; lhs.len() + 1];
+    for i in (0..lhs.len()).rev() {
+        greater[i][rhs.len()] = true;
+        for j in (0..rhs.len()).rev() {
+            let (l, r) = (lhs[i] as usize, rhs[j] as usize);
+            let at_least = greater[i + 1][j] || lhs[i + 1..] == rhs[j..];
+            greater[i][j] = at_least
+                || (rank[l] > rank[r] && greater[i][j + 1])
+                || (l == r && greater[i + 1][j + 1]);
+        }
+    }
+    greater[0][0]
+}
+
+//Tries precedences over the used symbols in turn, reading strings from both directions
+fn path_order_proof(
+    rules: &[(&Vec<SymbolIdx>, &Vec<SymbolIdx>)],
+    symbol_count: usize,
+) -> Option<TerminationProof> {
+    let used = used_symbols(rules);
+    let reversed: Vec<(Vec<SymbolIdx>, Vec<SymbolIdx>)> = rules
+        .iter()
+        .map(|(lhs, rhs)| {
+            (
+                lhs.iter().rev().copied().collect(),
+                rhs.iter().rev().copied().collect(),
+            )
+        })
+        .collect();
+    let mut order: Vec<usize> = (0..used.len()).collect();
+    let mut rank = vec![0; symbol_count];
+    for _ in 0..SEARCH_LIMIT {
+        for (position, used_idx) in order.iter().enumerate() {
+            rank[used[*used_idx]] = position;
+        }
+        let from_left = if rules
+            .iter()
+            .all(|(lhs, rhs)| path_order_greater(lhs, rhs, &rank))
+        {
+            Some(true)
+        } else if reversed
+            .iter()
+            .all(|(lhs, rhs)| path_order_greater(lhs, rhs, &rank))
+        {
+            Some(false)
+        } else {
+            None
+        };
+        if let Some(from_left) = from_left {
+            let mut precedence: Vec<SymbolIdx> = (0..symbol_count as SymbolIdx).collect();
+            precedence.sort_by_key(|sym| (used.contains(&(*sym as usize)), rank[*sym as usize]));
+            return Some(TerminationProof::PathOrder {
+                precedence,
+                from_left,
+            });
+        }
+        if !next_permutation(&mut order) {
+            break;
+        }
+    }
+    None
+}
+
+//Steps to the next permutation in lexicographic order, returning false after the last one
+fn next_permutation(order: &mut [usize]) -> bool {
+    let Some(pivot) = (1..order.len())
+        .rev()
+        .find(|idx| order[idx - 1] < order[*idx])
+    else {
+        return false;
+    };
+    let swap = (pivot..order.len())
+        .rev()
+        .find(|idx| order[*idx] > order[pivot - 1])
+        .unwrap();
+    order.swap(pivot - 1, swap);
+    order[pivot..].reverse();
+    true
+}
+
+fn multiply(a: &Matrix, b: &Matrix) -> Matrix {
+    let mut result = [[0; 2]; 2];
+    for (row, result_row) in result.iter_mut().enumerate() {
+        for (col, entry) in result_row.iter_mut().enumerate() {
+            *entry = a[row][0]
+                .saturating_mul(b[0][col])
+                .saturating_add(a[row][1].saturating_mul(b[1][col]));
+        }
+    }
+    result
+}
+
+fn interpret(string: &[SymbolIdx], matrices: &[Matrix]) -> Matrix {
+    string.iter().fold([[1, 0], [0, 1]], |product, sym| {
+        multiply(&product, &matrices[*sym as usize])
+    })
+}
+
+//Every rule has to keep each entry from going up and lower the top right one. The diagonal is
+//kept positive so that a lower top right entry anywhere in a string lowers it for the whole string.
+fn matrix_decreases(lhs: &[SymbolIdx], rhs: &[SymbolIdx], matrices: &[Matrix]) -> bool {
+    let (l, r) = (interpret(lhs, matrices), interpret(rhs, matrices));
+    l[0][1] > r[0][1] && (0..2).all(|row| (0..2).all(|col| l[row][col] >= r[row][col]))
+}
+
+fn matrix_proof(
+    rules: &[(&Vec<SymbolIdx>, &Vec<SymbolIdx>)],
+    symbol_count: usize,
+) -> Option<TerminationProof> {
+    let used = used_symbols(rules);
+    if used.len() > MATRIX_SYMBOLS {
+        return None;
+    }
+    let mut candidates = vec![];
+    for top_left in 1..=MAX_MATRIX_ENTRY {
+        for top_right in 0..=MAX_MATRIX_ENTRY {
+            for bottom_left in 0..=MAX_MATRIX_ENTRY {
+                for bottom_right in 1..=MAX_MATRIX_ENTRY {
+                    candidates.push([[top_left, top_right], [bottom_left, bottom_right]]);
+                }
+            }
+        }
+    }
+    //Smaller matrices first, since they're the likeliest to be readable
+    candidates.sort_by_key(|m: &Matrix| m.iter().flatten().sum::<u64>());
+    let mut matrices = vec![[[1, 0], [0, 1]]; symbol_count];
+    //Counts through every assignment of candidates to the used symbols
+    let mut choice = vec![0; used.len()];
+    for _ in 0..SEARCH_LIMIT {
+        for (used_idx, candidate_idx) in choice.iter().enumerate() {
+            matrices[used[used_idx]] = candidates[*candidate_idx];
+        }
+        if rules
+            .iter()
+            .all(|(lhs, rhs)| matrix_decreases(lhs, rhs, &matrices))
+        {
+            return Some(TerminationProof::Matrices(matrices));
+        }
+        let Some(digit) = choice.iter().position(|idx| idx + 1 < candidates.len()) else {
+            break;
+        };
+        choice[digit] += 1;
+        for earlier in &mut choice[..digit] {
+            *earlier = 0;
+        }
+    }
+    None
+}
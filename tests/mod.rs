@@ -4,8 +4,19 @@ use std::fs::File;
 use srs_to_dfa::builder::*;
 use srs_to_dfa::solver::*;
 use srs_to_dfa::test::*;
+use srs_to_dfa::util::Anchor;
+use srs_to_dfa::util::{
+    Completion, CompletionError, CompletionLimit, CompletionLimits, ReductionOrder,
+};
+use srs_to_dfa::util::Confluence;
 use srs_to_dfa::util::Ruleset;
 use srs_to_dfa::util::DFA;
+use srs_to_dfa::util::NerodeReport;
+use srs_to_dfa::util::NFA;
+use srs_to_dfa::util::SymbolIdx;
+use srs_to_dfa::util::SymbolSet;
+use srs_to_dfa::util::Termination;
+use srs_to_dfa::util::TerminationProof;
 
 #[test]
 #[ignore = "expensive"]
@@ -98,6 +109,42 @@ fn ruleset_parsing() {
     );
 }
 
+#[test]
+fn ruleset_parse_errors() {
+    let rules = Ruleset::parse("# comment\n1 1 0 - 0 0 1\n0 1 1 - 1 0 0 # trailing\n\n1 - \n").unwrap();
+    assert!(rules.symbol_set.representations == vec!["0".to_owned(), "1".to_owned()]);
+    assert!(rules.rules.len() == 3);
+    assert!(rules.rules[&vec![1]] == vec![Vec::<SymbolIdx>::new()]);
+
+    let error = Ruleset::parse("").unwrap_err();
+    assert!(error.line == 1 && error.column == 1);
+    assert!(Ruleset::parse("# nothing here\n   \n").is_err());
+
+    let error = Ruleset::parse("1 1 0 - 0 0 1\n0 1 - 1 - 0\n").unwrap_err();
+    assert!(error.line == 2 && error.column == 9);
+    assert!(error.token == "-");
+
+    let error = Ruleset::parse("1 1 0 - 0 0 1\n0 1 1 - 1 0 0\n  1 1 0 -   0 0 1\n").unwrap_err();
+    assert!(error.line == 3 && error.column == 3);
+    assert!(error.message.contains("line 1"));
+
+    //Rules can't make symbols out of nothing
+    let error = Ruleset::parse("1 - 0
+  - 1
+").unwrap_err();
+    assert!((error.line, error.column) == (2, 3) && error.token == "-");
+    assert!(Ruleset::parse("-").is_err());
+    assert!(Ruleset::parse("_ -").unwrap_err().message.contains("No symbols"));
+
+    //Rules that change nothing are dropped whether they're written out or not
+    let (rules, stats) = Ruleset::parse_with_stats("1 - 0
+0 1 - 0 1
+").unwrap();
+    assert!(rules.rules.len() == 1 && stats.identities == 1 && stats.rule_count == 1);
+    assert!(Ruleset::parse("0 - 0
+").is_err());
+}
+
 fn assert_k<S>(solver: &S, k: usize, test_string: &str)
 where
     S: SRSSolver,
@@ -131,3 +178,1115 @@ where
         test_string
     );
 }
+
+#[test]
+fn k_search() {
+    let solve_test = build_flip::<MinkidSolver>().unwrap();
+    let search = match solve_test.find_k(1, 4) {
+        Ok(search) => search,
+        Err(_) => panic!("No correct DFA found for flip"),
+    };
+    assert!(search.k == 2, "find_k picked k = {} for flip", search.k);
+    assert!(search.history.len() == 2, "find_k kept searching after a proven k");
+    assert!(search.dfa == solve_test.run(2, vec![]));
+
+    let solve_test = build_default1dpeg::<MinkidSolver>().unwrap();
+    assert!(
+        solve_test.find_k(1, 3).is_err(),
+        "find_k proved 1dpeg correct below its k"
+    );
+}
+
+#[test]
+fn rule_self_loops() {
+    //Every a can become a b, so any nonempty string reaches the goal. Both states of that DFA have
+    //a rule that stays inside them, which doesn't make them equivalent to anything else.
+    let rules = Ruleset::parse("a - b\n").unwrap();
+    let goal = DFA::from_regex("(a|b)*b(a|b)*", &rules.symbol_set).unwrap();
+    let solver = <HashSolver as SRSSolver>::new(rules, goal).unwrap();
+    let dfa = solver.run(2, vec![]);
+    let expanded_dfa = dfa.dfa_product(&solver.build_no_rule_dfa(), |s, _o| *s);
+    let rule_graph = solver.build_rule_graph(&expanded_dfa);
+    assert!(rule_graph.edge_indices().any(|e| {
+        let (a, b) = rule_graph.edge_endpoints(e).unwrap();
+        a == b
+    }));
+    assert!(solver.is_correct(&dfa));
+    for string in dfa.symbol_set.sig_set_iter(8) {
+        assert!(dfa.contains(&string) == reaches_goal(&solver, &string));
+    }
+}
+
+#[test]
+fn cancellation() {
+    let cancel = CancelToken::new();
+    cancel.cancel();
+    let solve_test = build_default1dpeg::<MinkidSolver>().unwrap();
+    assert!(solve_test.run_cancellable(5, vec![], cancel.clone()).is_err());
+    let solve_test = build_default1dpeg::<SubsetSolver>().unwrap();
+    assert!(solve_test.run_cancellable(5, vec![], cancel.clone()).is_err());
+    let solve_test = build_default1dpeg::<HashSolver>().unwrap();
+    assert!(solve_test.run_cancellable(5, vec![], cancel.clone()).is_err());
+    let solve_test = build_default1dpeg::<BFSSolver>().unwrap();
+    assert!(solve_test.run_cancellable(5, vec![], cancel.clone()).is_err());
+
+    //Only a run that isn't cancelled ends with Finished
+    assert_cancelled_mid_run::<MinkidSolver>();
+    assert_cancelled_mid_run::<SubsetSolver>();
+    assert_cancelled_mid_run::<HashSolver>();
+    assert_cancelled_mid_run::<BFSSolver>();
+    assert_finished_despite_late_cancel::<MinkidSolver>();
+    assert_finished_despite_late_cancel::<SubsetSolver>();
+    assert_finished_despite_late_cancel::<HashSolver>();
+    assert_finished_despite_late_cancel::<BFSSolver>();
+
+    //BFS should stop during its first, slowest dispatch rather than wait for it to finish
+    let cancel = CancelToken::new();
+    let solve_test = build_2xnswap::<BFSSolver>().unwrap();
+    let (_event_rx, handle) = solve_test.run_debug(11, vec![], cancel.clone());
+    std::thread::sleep(std::time::Duration::from_millis(100));
+    let cancelled_at = std::time::Instant::now();
+    cancel.cancel();
+    assert!(handle.join().unwrap().is_err());
+    assert!(cancelled_at.elapsed() < std::time::Duration::from_secs(10));
+
+    //A run that would take ages should stop shortly after being cancelled
+    let cancel = CancelToken::new();
+    let solve_test = build_2xnswap::<MinkidSolver>().unwrap();
+    let (_event_rx, handle) = solve_test.run_debug(11, vec![], cancel.clone());
+    std::thread::sleep(std::time::Duration::from_millis(100));
+    cancel.cancel();
+    match handle.join().unwrap() {
+        Ok(_) => panic!("2xnswap finished before it could be cancelled"),
+        Err(cancelled) => assert!(cancelled.dfa.len() > 0),
+    }
+}
+
+//Cancels the run it's watching as soon as the first iteration finishes
+struct CancelAfterIteration {
+    cancel: CancelToken,
+    events: Vec<SolverEvent>,
+}
+
+impl SolverObserver for CancelAfterIteration {
+    fn notify(&mut self, event: &SolverEvent) {
+        if let SolverEvent::IterationFinished { .. } = event {
+            self.cancel.cancel();
+        }
+        self.events.push(event.clone());
+    }
+}
+
+fn assert_cancelled_mid_run<S: SRSSolver>() {
+    let solve_test = build_default1dpeg::<S>().unwrap();
+    let cancel = CancelToken::new();
+    let mut observer = CancelAfterIteration {
+        cancel: cancel.clone(),
+        events: vec![],
+    };
+    assert!(solve_test.run_observed(5, vec![], &mut observer, cancel).is_err());
+    assert!(
+        !observer
+            .events
+            .iter()
+            .any(|event| matches!(event, SolverEvent::Finished { .. })),
+        "{} sent Finished after being cancelled",
+        type_name::<S>()
+    );
+}
+
+//Cancels the run it's watching once it's already finished
+struct CancelWhenFinished(CancelToken);
+
+impl SolverObserver for CancelWhenFinished {
+    fn notify(&mut self, event: &SolverEvent) {
+        if let SolverEvent::Finished { .. } = event {
+            self.0.cancel();
+        }
+    }
+}
+
+fn assert_finished_despite_late_cancel<S: SRSSolver>() {
+    let solve_test = build_default1dpeg::<S>().unwrap();
+    let cancel = CancelToken::new();
+    let mut observer = CancelWhenFinished(cancel.clone());
+    assert!(
+        solve_test.run_observed(5, vec![], &mut observer, cancel.clone()).is_ok(),
+        "{} threw away a finished DFA",
+        type_name::<S>()
+    );
+    assert!(cancel.is_cancelled());
+}
+
+#[test]
+fn solver_events() {
+    let solve_test = build_default1dpeg::<MinkidSolver>().unwrap();
+    let (event_rx, handle) = solve_test.run_debug(5, vec![], CancelToken::new());
+    let dfa = handle.join().ok().unwrap().ok().unwrap();
+    let events: Vec<SolverEvent> = event_rx.iter().collect();
+
+    assert!(matches!(events.first(), Some(SolverEvent::Initialized { .. })));
+    match events.last() {
+        Some(SolverEvent::Finished { states }) => assert!(*states == dfa.state_transitions.len()),
+        _ => panic!("Last event should be Finished"),
+    }
+    let mut iterations = 0;
+    let mut phases = vec![];
+    for event in &events {
+        match event {
+            SolverEvent::PhaseFinished { name, .. } => phases.push(*name),
+            SolverEvent::IterationFinished { .. } => {
+                assert!(phases == MinkidSolver::PHASES, "Phases reported out of order");
+                phases.clear();
+                iterations += 1;
+            }
+            _ => {}
+        }
+    }
+    assert!(iterations > 0);
+}
+
+#[test]
+fn counterexamples() {
+    let solve_test = build_default1dpeg::<MinkidSolver>().unwrap();
+    let correct_dfa = solve_test.run(5, vec![]);
+    assert!(solve_test.check_correct(&correct_dfa, 8).is_ok());
+    for bad_k in 1..5 {
+        let bad_dfa = solve_test.run(bad_k, vec![]);
+        let counterexample = match solve_test.check_correct(&bad_dfa, 12) {
+            Err(Refutation::Counterexample(counterexample)) => counterexample,
+            _ => panic!("No counterexample found for 1dpeg when k = {}", bad_k),
+        };
+        assert!(bad_dfa.contains(&counterexample.string) == counterexample.dfa_accepts);
+        assert!(correct_dfa.contains(&counterexample.string) != counterexample.dfa_accepts);
+        match counterexample.rule_path {
+            Some(rule_path) => {
+                assert!(!counterexample.dfa_accepts);
+                assert!(rule_path[0] == counterexample.string);
+                assert!(solve_test.get_goal().contains(rule_path.last().unwrap()));
+                for step in rule_path.windows(2) {
+                    assert!(solve_test.single_rule_hash(&step[0]).contains(&step[1]));
+                }
+            }
+            None => assert!(counterexample.dfa_accepts),
+        }
+    }
+}
+
+#[test]
+fn proof_certificates() {
+    let solve_test = build_default1dpeg::<MinkidSolver>().unwrap();
+    let rules = solve_test.get_ruleset();
+    let goal = solve_test.get_goal();
+    let correct_dfa = solve_test.run(5, vec![]);
+    let audit = solve_test.correct_audit(&correct_dfa, false);
+    assert!(audit.is_complete());
+    assert!(verify_certificate(rules, goal, &correct_dfa, &audit).is_ok());
+
+    let certificate: ProofAudit =
+        serde_json::from_str(&serde_json::to_string(&audit).unwrap()).unwrap();
+    assert!(certificate.steps == audit.steps);
+    assert!(certificate.is_complete());
+    assert!(verify_certificate(rules, goal, &correct_dfa, &certificate).is_ok());
+
+    //Claiming a state matches the goal when it doesn't shouldn't verify
+    let mut tampered = certificate.clone();
+    let regex_states: Vec<usize> = tampered
+        .steps
+        .iter()
+        .filter(|step| step.reason == ProofStepRationale::MatchesRegex)
+        .map(|step| match &step.element {
+            ProofElement::AddProperty(_, targets) => targets[0],
+            _ => unreachable!(),
+        })
+        .collect();
+    let unmatched = (0..correct_dfa.state_transitions.len())
+        .find(|state| !regex_states.contains(state))
+        .unwrap();
+    let forged = tampered
+        .steps
+        .iter()
+        .position(|step| step.reason == ProofStepRationale::MatchesRegex)
+        .unwrap();
+    tampered.steps[forged].element =
+        ProofElement::AddProperty(ProofProperty::Correct, vec![unmatched]);
+    match verify_certificate(rules, goal, &correct_dfa, &tampered) {
+        Err(error) => assert!(error.step == Some(forged)),
+        Ok(_) => panic!("Tampered certificate verified"),
+    }
+
+    //An incomplete proof is consistent, but doesn't prove anything
+    let bad_dfa = solve_test.run(4, vec![]);
+    let audit = solve_test.correct_audit(&bad_dfa, false);
+    assert!(!audit.is_complete());
+    match verify_certificate(rules, goal, &bad_dfa, &audit) {
+        Err(error) => assert!(error.step.is_none()),
+        Ok(_) => panic!("Incomplete certificate verified"),
+    }
+    assert!(verify_certificate(rules, goal, &bad_dfa, &certificate).is_err());
+
+    //A DFA that's wrong about a string no rules apply to is thrown out before the steps are checked
+    let mut terminal_dfa = correct_dfa.clone();
+    terminal_dfa.accepting_states[terminal_dfa.starting_state] ^= true;
+    match verify_certificate(rules, goal, &terminal_dfa, &certificate) {
+        Err(error) => assert!(error.step.is_none() && error.message.contains("no rules apply")),
+        Ok(_) => panic!("Certificate verified for a DFA that's wrong about the empty string"),
+    }
+
+    //The two accepting states are equivalent, and the rules go back and forth between them.
+    //Every step of the proof holds, but is_correct won't accept a DFA with a rule graph cycle.
+    let rules = Ruleset::parse("a - b\nb - a\n").unwrap();
+    let goal = DFA::from_regex("(a|b)*b(a|b)*", &rules.symbol_set).unwrap();
+    let solver = <HashSolver as SRSSolver>::new(rules.clone(), goal.clone()).unwrap();
+    let cyclic_dfa = DFA {
+        state_transitions: vec![vec![1, 2], vec![1, 2], vec![1, 2]],
+        accepting_states: vec![false, true, true],
+        starting_state: 0,
+        symbol_set: rules.symbol_set.clone(),
+    };
+    let audit = solver.correct_audit(&cyclic_dfa, false);
+    assert!(audit.is_complete());
+    assert!(!solver.is_correct(&cyclic_dfa));
+    match verify_certificate(&rules, &goal, &cyclic_dfa, &audit) {
+        Err(error) => assert!(error.step.is_none() && error.message.contains("cycle")),
+        Ok(_) => panic!("Certificate verified for a DFA with a rule graph cycle"),
+    }
+}
+
+#[test]
+fn dot_export() {
+    let solve_test = build_default1dpeg::<MinkidSolver>().unwrap();
+    let dfa = solve_test.run(5, vec![]);
+    let dot = dfa.to_dot();
+    assert!(dot.starts_with("digraph"));
+    assert!(dot.contains(&format!("start -> q{};", dfa.starting_state)));
+    let accepting = dfa.accepting_states.iter().filter(|x| **x).count();
+    assert!(dot.matches("shape=doublecircle").count() == accepting);
+    //Parallel edges are merged, so every pair of states shows up at most once
+    let edges: Vec<&str> = dot
+        .lines()
+        .filter(|line| line.contains("->") && !line.contains("start"))
+        .map(|line| line.split('[').next().unwrap())
+        .collect();
+    let unique: std::collections::HashSet<&&str> = edges.iter().collect();
+    assert!(edges.len() == unique.len());
+    assert!(edges.len() < dfa.state_transitions.len() * dfa.symbol_set.length);
+
+    let (event_rx, handle) = solve_test.run_debug(5, vec![], CancelToken::new());
+    handle.join().ok().unwrap().ok().unwrap();
+    let partial = event_rx
+        .iter()
+        .filter_map(|event| match event {
+            SolverEvent::PartialDFA(dfa_structure, sig_sets) => Some((dfa_structure, sig_sets)),
+            _ => None,
+        })
+        .last()
+        .unwrap();
+    let dot = partial.0.to_dot(&dfa.symbol_set, Some(&partial.1));
+    let states = dot
+        .lines()
+        .filter(|line| line.trim_start().starts_with('q') && !line.contains("->"))
+        .count();
+    assert!(states == partial.0.len());
+
+    let rule_dot = solve_test.rule_graph_to_dot(&dfa);
+    assert!(rule_dot.starts_with("digraph"));
+    assert!(rule_dot.contains("label=\"1 1 0 -> 0 0 1\""));
+}
+
+#[test]
+fn regex_goals() {
+    let binary = build_onlyone1().symbol_set;
+    let onlyone1 = DFA::from_regex("0*10*", &binary).unwrap();
+    assert!(onlyone1 == build_onlyone1());
+    assert!(onlyone1.state_transitions.len() == build_onlyone1().state_transitions.len());
+    assert!(DFA::from_regex("0* 1 0*", &binary).unwrap() == onlyone1);
+    assert!(DFA::from_regex("[^1]*[1][0]*", &binary).unwrap() == onlyone1);
+    assert!(DFA::from_regex("(0|())*1(0?)+|1&0", &binary).unwrap() == onlyone1);
+    assert!(DFA::from_regex("!(.*1.*1.*|0*)", &binary).unwrap() == onlyone1);
+    assert!(DFA::from_regex(".*1.* & !(.*1.*1.*)", &binary).unwrap() == onlyone1);
+    let solve_test = MinkidSolver::new(build_1dpeg_rs(), onlyone1).unwrap();
+    assert!(solve_test.is_correct(&solve_test.run(5, vec![])));
+
+    let ternary = build_onlyone2().symbol_set;
+    assert!(DFA::from_regex("0*20*", &ternary).unwrap() == build_onlyone2());
+
+    //Longest symbol names are matched first
+    let symbol_set = SymbolSet {
+        length: 3,
+        representations: vec!["1".to_owned(), "10".to_owned(), "peg".to_owned()],
+    };
+    let dfa = DFA::from_regex("10 peg* 1 10", &symbol_set).unwrap();
+    assert!(dfa.contains(&vec![1, 2, 2, 0, 1]));
+    assert!(!dfa.contains(&vec![0, 2, 2, 1]));
+
+    assert!(DFA::from_regex("0*(10", &binary).is_err_and(|error| error.column == 6));
+    assert!(DFA::from_regex("0*2", &binary).is_err_and(|error| error.column == 3));
+    assert!(DFA::from_regex("0)", &binary).is_err());
+    assert!(DFA::from_regex("*0", &binary).is_err());
+}
+
+#[test]
+fn regex_output() {
+    assert!(build_onlyone1().to_regex() == "0*10*");
+    assert!(build_onlyone2().to_regex() == "0*20*");
+
+    let binary = build_onlyone1().symbol_set;
+    assert!(DFA::from_regex("[]", &binary).unwrap().to_regex() == "[]");
+    assert!(DFA::from_regex("()", &binary).unwrap().to_regex() == "()");
+    assert!(DFA::from_regex("(0|1)*", &binary).unwrap().to_regex() == ".*");
+
+    let solve_test = build_flip::<MinkidSolver>().unwrap();
+    for dfa in [build_1dpeg_result(), build_2dpeg_goal(), solve_test.run(2, vec![])] {
+        let regex = dfa.to_regex();
+        assert!(
+            DFA::from_regex(&regex, &dfa.symbol_set).unwrap() == dfa,
+            "{} doesn't describe the DFA it came from",
+            regex
+        );
+    }
+}
+
+#[test]
+fn hopcroft_minimize() {
+    //Two disconnected copies of the same DFA should collapse back down to one
+    let original = build_1dpeg_result();
+    let mut doubled = original.clone();
+    let offset = doubled.state_transitions.len();
+    for transitions in &original.state_transitions {
+        doubled
+            .state_transitions
+            .push(transitions.iter().map(|state| state + offset).collect());
+    }
+    doubled
+        .accepting_states
+        .extend(original.accepting_states.iter().cloned());
+    doubled.starting_state = offset + original.starting_state;
+    doubled.minimize();
+    assert!(doubled.state_transitions.len() == original.state_transitions.len());
+    assert!(doubled == original);
+
+    //Distinct outputs are never merged, even when the transitions agree
+    let mut counter = DFA::<String, usize> {
+        starting_state: 0,
+        state_transitions: vec![vec![1], vec![2], vec![3], vec![3]],
+        accepting_states: vec![0, 1, 2, 2],
+        symbol_set: SymbolSet {
+            length: 1,
+            representations: vec!["a".to_owned()],
+        },
+    };
+    counter.minimize();
+    assert!(counter.state_transitions == vec![vec![1], vec![2], vec![2]]);
+    assert!(counter.accepting_states == vec![0, 1, 2]);
+}
+
+#[test]
+fn trim_and_canonicalize() {
+    let original = build_1dpeg_result();
+    let mut padded = original.clone();
+    //An unreachable copy of every state, placed before the real ones
+    let offset = padded.state_transitions.len();
+    let mut transitions: Vec<Vec<usize>> = original.state_transitions.clone();
+    transitions.extend(
+        original
+            .state_transitions
+            .iter()
+            .map(|t| t.iter().map(|s| s + offset).collect()),
+    );
+    padded.state_transitions = transitions;
+    padded
+        .accepting_states
+        .extend(original.accepting_states.iter().cloned());
+    padded.starting_state = offset + original.starting_state;
+    let mut trimmed = padded.clone();
+    trimmed.trim();
+    assert!(trimmed.state_transitions.len() == original.state_transitions.len());
+    assert!(trimmed.state_transitions == original.state_transitions);
+    assert!(trimmed.starting_state == original.starting_state);
+
+    //Equal languages canonicalize identically, however they were built
+    let binary = build_onlyone1().symbol_set;
+    let mut from_regex = DFA::from_regex("!(.*1.*1.*|0*)", &binary).unwrap();
+    let mut built = build_onlyone1();
+    from_regex.canonicalize();
+    built.canonicalize();
+    assert!(serde_json::to_string(&from_regex).unwrap() == serde_json::to_string(&built).unwrap());
+    assert!(built.starting_state == 0);
+    padded.canonicalize();
+    assert!(padded.state_transitions.len() == original.state_transitions.len());
+    assert!(padded == original);
+}
+
+#[test]
+fn nfa_operations() {
+    let binary = build_onlyone1().symbol_set;
+    let regex = |pattern: &str| DFA::from_regex(pattern, &binary).unwrap();
+
+    //"Contains 11 somewhere" is much easier to say nondeterministically
+    let mut contains_11 = NFA::new(binary.clone());
+    let one = contains_11.add_state(false);
+    let two = contains_11.add_state(true);
+    for sym in 0..2 {
+        contains_11.add_transition(0, sym, 0);
+        contains_11.add_transition(two, sym, two);
+    }
+    contains_11.add_transition(0, 1, one);
+    contains_11.add_transition(one, 1, two);
+    assert!(contains_11.contains(&[0, 1, 1, 0]));
+    assert!(!contains_11.contains(&[1, 0, 1]));
+    assert!(contains_11.to_dfa() == regex(".*11.*"));
+
+    let onlyone1 = NFA::from_dfa(&build_onlyone1());
+    let ones = NFA::from_dfa(&regex("1+"));
+    assert!(onlyone1.union(&ones).to_dfa() == regex("0*10*|1+"));
+    assert!(onlyone1.concat(&ones).to_dfa() == regex("0*10*1+"));
+    assert!(onlyone1.star().to_dfa() == regex("(0*10*)*"));
+    assert!(onlyone1.concat(&ones).reverse().to_dfa() == regex("1+0*10*"));
+    assert!(contains_11.reverse().to_dfa() == regex(".*11.*"));
+    assert!(onlyone1.concat(&ones).reverse().reverse().to_dfa() == regex("0*10*1+"));
+
+    //JFLAP round trips, and nondeterministic JFLAP files with lambda transitions load too
+    let dfa = build_1dpeg_result();
+    let saved = String::from_utf8(dfa.save_jflap_to_bytes()).unwrap();
+    let loaded = DFA::load_jflap_from_string(&saved);
+    assert!(loaded.state_transitions == dfa.state_transitions);
+    assert!(loaded.accepting_states == dfa.accepting_states);
+    //Deterministic files aren't renumbered or trimmed, even if some states can't be reached
+    let unreachable = "<structure><type>fa</type><automaton>\
+        <state id=\"0\" name=\"q0\"></state>\
+        <state id=\"1\" name=\"q1\"><initial/></state>\
+        <state id=\"2\" name=\"q2\"><final/></state>\
+        <transition><from>1</from><to>2</to><read>a</read></transition>\
+        <transition><from>2</from><to>2</to><read>a</read></transition>\
+        <transition><from>0</from><to>1</to><read>a</read></transition>\
+        </automaton></structure>";
+    let loaded = DFA::load_jflap_from_string(unreachable);
+    assert!(loaded.starting_state == 1);
+    assert!(loaded.state_transitions == vec![vec![1], vec![2], vec![2]]);
+    assert!(loaded.accepting_states == vec![false, false, true]);
+    let jflap_nfa = "<structure><type>fa</type><automaton>\
+        <state id=\"3\" name=\"q3\"><initial/></state>\
+        <state id=\"7\" name=\"q7\"></state>\
+        <state id=\"9\" name=\"q9\"><final/></state>\
+        <transition><from>3</from><to>3</to><read>a</read></transition>\
+        <transition><from>3</from><to>3</to><read>b</read></transition>\
+        <transition><from>3</from><to>7</to><read>a</read></transition>\
+        <transition><from>7</from><to>9</to><read/></transition>\
+        </automaton></structure>";
+    let nfa = NFA::load_jflap_from_string(jflap_nfa);
+    assert!(nfa.symbol_set.representations == vec!["a".to_owned(), "b".to_owned()]);
+    assert!(nfa.epsilon_transitions[1] == vec![2]);
+    let ends_in_a = DFA::load_jflap_from_string(jflap_nfa);
+    assert!(ends_in_a == DFA::from_regex("(a|b)*a", &nfa.symbol_set).unwrap());
+
+    //Malformed files are reported instead of panicking
+    let error_at = |xml: &str| {
+        let error = DFA::parse_jflap(xml).err().unwrap();
+        (error.line, error.column)
+    };
+    assert!(DFA::parse_jflap(jflap_nfa).unwrap() == ends_in_a);
+    assert!(error_at("<structure><automaton>") == (1, 23));
+    assert!(error_at("<structure>\n<state name=\"q0\"/></structure>") == (2, 1));
+    assert!(error_at("<structure><initial/></structure>") == (1, 12));
+    assert!(error_at("<structure><from>0</from></structure>") == (1, 12));
+    let unknown_state = "<structure><state id=\"0\"/>\
+        <transition><from>0</from><to>1</to><read>a</read></transition></structure>";
+    assert!(DFA::parse_jflap(unknown_state).err().unwrap().message.contains("id 1"));
+    let no_target = "<structure><state id=\"0\"/>\
+        <transition><from>0</from><read>a</read></transition></structure>";
+    assert!(DFA::parse_jflap(no_target).is_err());
+    assert!(DFA::parse_jflap("<structure></structure>").is_err());
+}
+
+#[test]
+fn decision_procedures() {
+    let binary = build_onlyone1().symbol_set;
+    let regex = |pattern: &str| DFA::from_regex(pattern, &binary).unwrap();
+
+    assert!(regex("[]").is_empty().is_ok());
+    assert!(regex("0*10*").is_empty() == Err(vec![1]));
+    assert!(regex("0*110").is_empty() == Err(vec![1, 1, 0]));
+
+    assert!(regex(".*").is_universal().is_ok());
+    assert!(regex("!(01)").is_universal() == Err(vec![0, 1]));
+    assert!(regex("0*10*").is_universal() == Err(vec![]));
+
+    assert!(regex("[]").is_finite().is_ok());
+    assert!(regex("01|1|110").is_finite().is_ok());
+    assert!(regex("0*10*").is_finite() == Err(vec![1]));
+    assert!(regex("11(01)*").is_finite() == Err(vec![1, 1]));
+    assert!(regex("0(11)*0").is_finite() == Err(vec![0, 0]));
+
+    let goal = build_onlyone1();
+    let solve_test = MinkidSolver::new(build_1dpeg_rs(), goal.clone()).unwrap();
+    let result = solve_test.run(5, vec![]);
+    assert!(goal.is_subset_of(&result).is_ok());
+    let witness = result.is_subset_of(&goal).unwrap_err();
+    assert!(result.contains(&witness) && !goal.contains(&witness));
+    assert!(result.difference_witness(&goal) == Some(witness));
+    assert!(goal.difference_witness(&regex("0*10*")).is_none());
+    assert!(regex("0*").difference_witness(&regex("0*|1")) == Some(vec![1]));
+}
+
+#[test]
+fn counting_accepted_strings() {
+    let result = build_1dpeg_result();
+    let series = result.count_accepted_series(10);
+    assert!(series.len() == 11);
+    let mut brute_force = [0u32; 11];
+    for board in result.symbol_set.sig_set_iter(10) {
+        if result.contains(&board) {
+            brute_force[board.len()] += 1;
+        }
+    }
+    for (len, count) in series.iter().enumerate() {
+        assert!(*count == brute_force[len].into(), "wrong count at {}", len);
+        assert!(result.count_accepted(len) == *count);
+    }
+
+    let accepted: Vec<Vec<SymbolIdx>> = result.accepted_strings(10).collect();
+    assert!(accepted.len() as u32 == brute_force.iter().sum::<u32>());
+    assert!(accepted.windows(2).all(|pair| pair[0] < pair[1]));
+    assert!(accepted.iter().all(|board| result.contains(board)));
+    assert!(result.symbol_set.symbols_to_string(&accepted[0]) == "\"0 0 0 0 0 0 0 0 0 1\"");
+
+    let binary = build_onlyone1().symbol_set;
+    let everything = DFA::from_regex(".*", &binary).unwrap();
+    assert!(everything.count_accepted(300) == num_bigint::BigUint::from(2u8).pow(300));
+    let short: Vec<Vec<SymbolIdx>> = everything.accepted_strings(2).collect();
+    assert!(short == vec![vec![], vec![0], vec![0, 0], vec![0, 1], vec![1], vec![1, 0], vec![1, 1]]);
+    let nothing = DFA::from_regex("[]", &binary).unwrap();
+    assert!(nothing.count_accepted(5) == 0u8.into());
+    assert!(nothing.accepted_strings(5).next().is_none());
+    let too_long = DFA::from_regex("0000", &binary).unwrap();
+    assert!(too_long.accepted_strings(3).next().is_none());
+}
+
+#[test]
+fn uniform_sampling() {
+    use rand::{rngs::SmallRng, SeedableRng};
+    use std::collections::HashMap;
+    let mut rng = SmallRng::seed_from_u64(16);
+
+    //Every accepted string of length 7 should turn up about equally often
+    let result = build_1dpeg_result();
+    let accepted: Vec<Vec<SymbolIdx>> = result
+        .accepted_strings(7)
+        .filter(|board| board.len() == 7)
+        .collect();
+    let mut seen: HashMap<Vec<SymbolIdx>, usize> = HashMap::new();
+    let samples = 400 * accepted.len();
+    for _ in 0..samples {
+        let board = result.sample_accepted(7, &mut rng).unwrap();
+        *seen.entry(board).or_default() += 1;
+    }
+    assert!(seen.len() == accepted.len());
+    assert!(seen.values().all(|count| *count > 300 && *count < 500));
+
+    for _ in 0..20 {
+        let board = result.sample_accepted(300, &mut rng).unwrap();
+        assert!(board.len() == 300 && result.contains(&board));
+        let board = result.sample_rejected(300, &mut rng).unwrap();
+        assert!(board.len() == 300 && !result.contains(&board));
+    }
+
+    let binary = build_onlyone1().symbol_set;
+    let everything = DFA::from_regex(".*", &binary).unwrap();
+    assert!(everything.sample_rejected(5, &mut rng).is_none());
+    assert!(everything.sample_accepted(0, &mut rng) == Some(vec![]));
+    let short = DFA::from_regex("0|11", &binary).unwrap();
+    assert!(short.sample_accepted(3, &mut rng).is_none());
+}
+
+#[test]
+fn distinguishability() {
+    let binary = build_onlyone1().symbol_set;
+    let regex = |pattern: &str| DFA::from_regex(pattern, &binary).unwrap();
+
+    //Checks the witness, and that k really is the smallest bound
+    let check = |dfa: &DFA, expected_k: usize| {
+        let result = dfa.distinguishability_k();
+        assert!(result.k == expected_k, "expected k = {}, got {}", expected_k, result.k);
+        let (ours, theirs, suffix) = result.witness.unwrap();
+        assert!(suffix.len() == result.k);
+        assert!(dfa.contains_from_start(&suffix, ours) != dfa.contains_from_start(&suffix, theirs));
+        let tells_apart = |ours: usize, theirs: usize, k: usize| {
+            dfa.symbol_set.sig_set_iter(k).any(|suffix| {
+                dfa.contains_from_start(&suffix, ours) != dfa.contains_from_start(&suffix, theirs)
+            })
+        };
+        if expected_k > 0 {
+            assert!(!tells_apart(ours, theirs, expected_k - 1));
+        }
+    };
+    check(&regex("0*10*"), 1);
+    check(&regex(".*1.."), 2);
+    check(&regex(".*1....."), 5);
+    check(&regex("1|0"), 1);
+    //The same k correctness_check expects the solvers to need
+    check(&build_1dpeg_result(), 5);
+    check(&build_flip::<MinkidSolver>().unwrap().run(2, vec![]), 2);
+
+    let everything = regex(".*");
+    assert!(everything.distinguishability_k().k == 0);
+    assert!(everything.distinguishability_k().witness.is_none());
+    assert!(regex("()").distinguishability_k().k == 0);
+
+    //Unreachable states don't count
+    let mut padded = regex("0*10*");
+    padded.state_transitions.push(vec![0, 0]);
+    padded.accepting_states.push(true);
+    assert!(padded.distinguishability_k() == regex("0*10*").distinguishability_k());
+}
+
+#[test]
+fn nerode_report() {
+    let dfa = build_1dpeg_result();
+    let report = dfa.nerode_report(5);
+    assert!(report.states.len() == dfa.state_transitions.len());
+    let parse = |string: &str| {
+        if string == "ε" {
+            return vec![];
+        }
+        let names: Vec<&str> = string.split(' ').collect();
+        dfa.symbol_set.string_to_symbols(&names).unwrap()
+    };
+    for (idx, state) in report.states.iter().enumerate() {
+        assert!(dfa.final_state(&parse(state.prefix.as_ref().unwrap())) == idx);
+        for suffix in dfa.symbol_set.sig_set_iter(5) {
+            let listed = state.signature.iter().any(|listed| parse(listed) == suffix);
+            assert!(listed == dfa.contains_from_start(&suffix, idx));
+        }
+    }
+    let state_count = dfa.state_transitions.len();
+    assert!(report.pairs.len() == state_count * (state_count - 1) / 2);
+    for pair in &report.pairs {
+        let (ours, theirs) = pair.states;
+        let suffix = parse(pair.suffix.as_ref().unwrap());
+        let differs = |suffix: &Vec<SymbolIdx>| {
+            dfa.contains_from_start(suffix, ours) != dfa.contains_from_start(suffix, theirs)
+        };
+        assert!(differs(&suffix));
+        let mut shorter = dfa.symbol_set.sig_set_iter(suffix.len());
+        assert!(!shorter.any(|candidate| candidate.len() < suffix.len() && differs(&candidate)));
+    }
+
+    let markdown = report.to_markdown();
+    assert!(markdown.contains("| 2 | yes | `1` |"));
+    assert!(markdown.contains("| 0, 2 | `ε` |"));
+    let json: NerodeReport = serde_json::from_str(&report.to_json()).unwrap();
+    assert!(json.states[2].prefix == Some("1".to_owned()) && json.k == 5);
+
+    //Equivalent states are reported as such
+    let mut doubled = dfa.clone();
+    doubled.state_transitions.push(dfa.state_transitions[0].clone());
+    doubled.accepting_states.push(dfa.accepting_states[0]);
+    let report = doubled.nerode_report(2);
+    assert!(report.states[state_count].prefix.is_none());
+    assert!(report
+        .pairs
+        .iter()
+        .any(|pair| pair.states == (0, state_count) && pair.suffix.is_none()));
+}
+
+#[test]
+fn symbol_limits() {
+    let symbol_set = SymbolSet::<String>::new((0..300).map(|sym| format!("s{:03}", sym)).collect());
+    let goal = DFA {
+        starting_state: 0,
+        state_transitions: vec![vec![0; 300]],
+        accepting_states: vec![true],
+        symbol_set: symbol_set.clone(),
+    };
+    let ruleset = Ruleset::from_vec(vec![(vec![0, 1], vec![2])], symbol_set.clone());
+    let solver = MinkidSolver::new(ruleset, goal);
+    let regex = DFA::from_regex("s000 s299*", &symbol_set);
+    if (SymbolIdx::MAX as usize) < 299 {
+        assert!(matches!(solver, Err(DomainError::TooManySymbols(300))));
+        assert!(regex.is_err());
+    } else {
+        assert!(solver.is_ok());
+        let regex = regex.unwrap();
+        let last = (symbol_set.length - 1) as SymbolIdx;
+        assert!(regex.contains(&vec![0, last, last]));
+        assert!(!regex.contains(&vec![last]));
+    }
+}
+
+//Whether any string the rules can reach from start is in the goal, by brute force
+fn reaches_goal<S: SRSSolver>(solver: &S, start: &[SymbolIdx]) -> bool {
+    let mut seen = vec![start.to_vec()];
+    let mut stack = vec![start.to_vec()];
+    while let Some(string) = stack.pop() {
+        if solver.get_goal().contains(&string) {
+            return true;
+        }
+        for next in solver.single_rule_hash(&string) {
+            if !seen.contains(&next) {
+                seen.push(next.clone());
+                stack.push(next);
+            }
+        }
+    }
+    false
+}
+
+fn assert_anchored_solution<S: SRSSolver>(srs: &str, goal: &str, k: usize) {
+    let rules = Ruleset::parse(srs).unwrap();
+    let goal = DFA::from_regex(goal, &rules.symbol_set).unwrap();
+    let solver = S::new(rules, goal).unwrap();
+    let dfa = solver.run(k, vec![]);
+    assert!(solver.is_correct(&dfa), "{}", type_name::<S>());
+    for string in dfa.symbol_set.sig_set_iter(9) {
+        assert!(dfa.contains(&string) == reaches_goal(&solver, &string));
+    }
+}
+
+#[test]
+fn anchored_rules() {
+    let rules = Ruleset::parse("^ 1 1 0 - 0 0 1\n0 1 1 $ - 1 0 0\n").unwrap();
+    assert!(rules.rules.is_empty());
+    assert!(rules.anchored_rules[&(Anchor::Start, vec![1, 1, 0])] == vec![vec![0, 0, 1]]);
+    assert!(rules.anchored_rules[&(Anchor::End, vec![0, 1, 1])] == vec![vec![1, 0, 0]]);
+    let reparsed = Ruleset::parse(&rules.to_string()).unwrap();
+    assert!(reparsed.anchored_rules == rules.anchored_rules);
+    let whole_string = Ruleset::parse("^ 1 $ - 0").unwrap();
+    assert!(whole_string.anchored_rules.contains_key(&(Anchor::Both, vec![1])));
+
+    let error = Ruleset::parse("1 ^ 1 - 0").unwrap_err();
+    assert!(error.line == 1 && error.column == 3);
+    assert!(Ruleset::parse("1 $ 1 - 0").is_err());
+    assert!(Ruleset::parse("1 1 - ^ 0").is_err());
+    assert!(Ruleset::parse("^ - 1").is_err());
+
+    //Anchored rules only match at their end of the string
+    let goal = DFA::from_regex("0*10*", &rules.symbol_set).unwrap();
+    let solver = MinkidSolver::new(rules, goal).unwrap();
+    assert!(solver.single_rule_hash(&vec![1, 1, 0, 1, 1, 0]) == vec![vec![0, 0, 1, 1, 1, 0]]);
+    assert!(solver.single_rule_hash(&vec![0, 1, 1, 0, 1, 1]) == vec![vec![0, 1, 1, 1, 0, 0]]);
+    assert!(solver.single_rule_hash(&vec![0, 1, 1, 0]).is_empty());
+
+    let peg_edges = "1 1 0 - 0 0 1\n0 1 1 - 1 0 0\n^ 1 1 - 0 1\n1 1 $ - 1 0\n";
+    assert_anchored_solution::<MinkidSolver>(peg_edges, "0*10*", 6);
+    assert_anchored_solution::<SubsetSolver>(peg_edges, "0*10*", 6);
+    assert_anchored_solution::<HashSolver>(peg_edges, "0*10*", 6);
+    let shuffle = "^ 1 0 - 0 1\n0 1 $ - 1 0\n1 1 - 0 0\n";
+    assert_anchored_solution::<MinkidSolver>(shuffle, "0*", 4);
+    assert_anchored_solution::<HashSolver>(shuffle, "0*", 4);
+
+    //Undoing each other on the string "0 1" makes these cyclic, even though the anchors differ
+    let rules = Ruleset::parse(shuffle).unwrap();
+    let goal = DFA::from_regex("0*", &rules.symbol_set).unwrap();
+    assert!(matches!(SubsetSolver::new(rules, goal), Err(DomainError::Cyclic(_))));
+}
+
+#[test]
+fn rule_patterns() {
+    let (rules, stats) = Ruleset::parse_with_stats("let x = [a b]\n[a b] x 0 - 0 0 x\n").unwrap();
+    assert!(rules.symbol_set.representations == vec!["0", "a", "b"]);
+    assert!(rules.rules.len() == 4);
+    assert!(rules.rules[&vec![1, 2, 0]] == vec![vec![0, 0, 2]]);
+    assert!(rules.rules[&vec![2, 1, 0]] == vec![vec![0, 0, 1]]);
+    assert!(stats.patterns == vec![(2, 4)] && stats.rule_count == 4);
+    assert!(stats.to_string().contains("Line 2: 4 rules"));
+
+    assert!(
+        Ruleset::parse("let x = _\n1 1 x - 0 0 x\n").unwrap()
+            == Ruleset::parse("1 1 0 - 0 0 0\n1 1 1 - 0 0 1\n").unwrap()
+    );
+    let anchored = Ruleset::parse("^ [0 1] 1 - 0 0").unwrap();
+    assert!(anchored.anchored_rules.len() == 2 && anchored.rules.is_empty());
+
+    //Swapping a symbol with itself does nothing, so those rules are dropped
+    let (rules, stats) =
+        Ruleset::parse_with_stats("let x = [a b c]\nlet y = [a b c]\nx y - y x\n").unwrap();
+    assert!(rules.rules.len() == 6 && stats.identities == 3);
+    assert!(rules.find_loop().is_some());
+    //Patterns can overlap each other and rules that are written out
+    let (rules, stats) =
+        Ruleset::parse_with_stats("[0 1] 1 - 0 0\n_ 1 - 0 0\n1 1 - 0 0\n").unwrap();
+    assert!(rules.rules.len() == 2 && stats.duplicates == 3);
+    assert!(stats.patterns == vec![(1, 2), (2, 0)]);
+
+    let error_at = |srs: &str| {
+        let error = Ruleset::parse(srs).unwrap_err();
+        (error.line, error.column)
+    };
+    assert!(error_at("[0 1 - 0") == (1, 1));
+    assert!(error_at("0 - [0 1]") == (1, 5));
+    assert!(error_at("0 - _") == (1, 5));
+    assert!(error_at("let x = [0 1]\n0 - x") == (2, 5));
+    assert!(error_at("let x = [0 1]\nlet x = _\n0 - 1") == (2, 5));
+    assert!(error_at("let x [0 1]\n0 - 1") == (1, 1));
+    assert!(error_at("0 ] - 1") == (1, 3));
+    assert!(error_at("let x = [0 1]\n[x 0] - 1") == (2, 2));
+    assert!(error_at("[] 0 - 1") == (1, 1));
+    //Names are symbols unless a let line declares them, even next to a class
+    let (rules, stats) = Ruleset::parse_with_stats("[a b] 0 - 0 0").unwrap();
+    assert!(rules.symbol_set.representations == vec!["0", "a", "b"] && stats.rule_count == 2);
+    let rules = Ruleset::parse("[a b] x 0 - 0 0 x").unwrap();
+    assert!(rules.symbol_set.representations == vec!["0", "a", "b", "x"]);
+    assert!(rules.rules[&vec![1, 3, 0]] == vec![vec![0, 0, 3]]);
+    let error = Ruleset::parse(&format!("{}- 0 1", "_ ".repeat(21))).unwrap_err();
+    assert!(error.message.contains("more than"));
+}
+
+#[test]
+fn multi_track() {
+    let binary = SymbolSet::<String>::new(vec!["0".to_owned(), "1".to_owned()]);
+    let board = MultiTrack::new(2, binary.clone()).unwrap();
+    assert!(board.symbol_set.representations == vec!["00", "01", "10", "11"]);
+    let column = |cells: &[SymbolIdx]| board.column(cells).unwrap();
+    assert!(board.cells_of(column(&[1, 0])) == [1, 0]);
+    assert!(board.draw(&[column(&[1, 0]), column(&[1, 1])]) == "1 1\n0 1");
+    assert!(matches!(board.column(&[1]), Err(DomainError::InvalidCells(_))));
+    assert!(matches!(board.column(&[2, 0]), Err(DomainError::InvalidCells(_))));
+    assert!(matches!(board.row_rules(&[1, 1], &[0]), Err(DomainError::Deleting(_))));
+    assert!(matches!(board.column_rules(&[1], &[0, 0]), Err(DomainError::Generating(_))));
+    assert!(matches!(board.row_rules(&[2], &[0]), Err(DomainError::InvalidCells(_))));
+    let wide = SymbolSet::<String>::new(vec!["hole".to_owned(), "peg".to_owned()]);
+    assert!(MultiTrack::new(2, wide).unwrap().symbol_set.representations[1] == "hole_peg");
+
+    //A single row is just the 1D game
+    let peg_moves = [(vec![1, 1, 0], vec![0, 0, 1]), (vec![0, 1, 1], vec![1, 0, 0])];
+    let line = MultiTrack::new(1, binary.clone()).unwrap();
+    assert!(line.ruleset(&peg_moves, MoveDirection::Both).unwrap() == build_1dpeg_rs());
+    assert!(line.count_goal(1, 1) == build_onlyone1());
+
+    //Jumps don't fit down a column of 2, and along a row the other row can hold any of 2^3 things
+    let rule_count = |direction: MoveDirection, moves: &[(Vec<SymbolIdx>, Vec<SymbolIdx>)]| {
+        let rules = board.ruleset(moves, direction).unwrap();
+        rules.rules.values().map(|rhs_list| rhs_list.len()).sum::<usize>()
+    };
+    assert!(rule_count(MoveDirection::Both, &peg_moves) == 2 * 2 * 8);
+    assert!(rule_count(MoveDirection::Columns, &peg_moves) == 0);
+    //Sliding a peg works along either row, with 2^2 things in the other one, or down the column
+    let slide = [(vec![1, 0], vec![0, 1])];
+    assert!(rule_count(MoveDirection::Rows, &slide) == 2 * 4);
+    assert!(rule_count(MoveDirection::Columns, &slide) == 1);
+    assert!(rule_count(MoveDirection::Both, &slide) == 2 * 4 + 1);
+    assert!(board.ruleset(&[(vec![1, 0], vec![1])], MoveDirection::Rows).is_err());
+
+    let rules = board.ruleset(&peg_moves, MoveDirection::Both).unwrap();
+    let solver = MinkidSolver::new(rules, board.count_goal(1, 1)).unwrap();
+    let dfa = solver.run(5, vec![]);
+    assert!(solver.is_correct(&dfa));
+    let string = |columns: &[[SymbolIdx; 2]]| -> Vec<SymbolIdx> {
+        columns.iter().map(|cells| column(cells)).collect()
+    };
+    assert!(dfa.contains(&string(&[[1, 0], [1, 0], [0, 0]])));
+    assert!(!dfa.contains(&string(&[[1, 0], [0, 1]])));
+
+    if (SymbolIdx::MAX as usize) < 511 {
+        assert!(matches!(MultiTrack::new(9, binary), Err(DomainError::TooManySymbols(512))));
+    }
+}
+
+#[test]
+fn termination() {
+    let proof = |srs: &str| match Ruleset::from_string(srs).termination() {
+        Termination::Terminating(proof) => Some(proof),
+        _ => None,
+    };
+    assert!(proof("a a b - a\nb b - \n") == Some(TerminationProof::LengthDecreasing));
+    assert!(matches!(
+        build_1dpeg_rs().termination(),
+        Termination::Terminating(TerminationProof::Weights { weights, .. }) if weights == vec![0, 1]
+    ));
+    //Swapping symbols never changes the weight, so it comes down to a being ordered after b
+    assert!(matches!(
+        proof("a b - b a\n"),
+        Some(TerminationProof::Weights { precedence, .. }) if precedence == vec![1, 0]
+    ));
+    assert!(matches!(proof("a b - b b a\n"), Some(TerminationProof::PathOrder { .. })));
+    assert!(matches!(
+        proof("a b - b a a\n"),
+        Some(TerminationProof::PathOrder { from_left: false, .. })
+    ));
+    assert!(matches!(proof("b b - a a\na a b - b b a\n"), Some(TerminationProof::Matrices(_))));
+    assert!(matches!(build_default2dpegx3_rs().termination(), Termination::Terminating(_)));
+
+    //Longer cycles than a rule and its reverse
+    let cycle = Ruleset::from_string("a - b\nb - c\nc - a\n");
+    assert!(cycle.termination() == Termination::Looping(vec![vec![0], vec![1], vec![2], vec![0]]));
+    //Loops can also come back around to a longer string
+    let growing = Ruleset::from_string("a b - b a b\n");
+    assert!(growing.termination() == Termination::Looping(vec![vec![0, 1], vec![1, 0, 1]]));
+    assert!(matches!(build_flipx3_rs().termination(), Termination::Looping(_)));
+    //The loop search on its own, as SubsetSolver uses it
+    assert!(cycle.find_loop() == Some(vec![vec![0], vec![1], vec![2], vec![0]]));
+    assert!(build_1dpeg_rs().find_loop().is_none());
+    #[allow(deprecated)]
+    let first_step = cycle.has_definitely_cyclic_rule();
+    assert!(first_step == Some((vec![0], vec![1])));
+    //Anchored loops have to come back to exactly the same string
+    let anchored = Ruleset::from_string("^ 1 0 - 0 1\n0 1 $ - 1 0\n");
+    let derivation = vec![vec![0, 1], vec![1, 0], vec![0, 1]];
+    assert!(anchored.termination() == Termination::Looping(derivation));
+    //Proofs hold for anchored rules too, since they just treat them as unanchored
+    let anchored = Ruleset::from_string("^ 1 0 - 0 1\n0 1 - 1 1\n");
+    assert!(matches!(
+        anchored.termination(),
+        Termination::Terminating(TerminationProof::Weights { .. })
+    ));
+
+    //Terminates, but it takes more than these methods to show it
+    assert!(Ruleset::from_string("a a - a b a\n").termination() == Termination::Unknown);
+
+    let goal = DFA::from_regex("a*", &cycle.symbol_set).unwrap();
+    assert!(matches!(SubsetSolver::new(cycle, goal), Err(DomainError::Cyclic(_))));
+}
+
+#[test]
+fn confluence() {
+    //1 1 0 1 1 can jump either way, leaving the lone peg on opposite sides
+    let peg = build_1dpeg_rs();
+    let pairs = peg.critical_pairs();
+    assert!(pairs.len() == 3);
+    assert!(pairs.iter().any(|pair| pair.peak == vec![0, 1, 1, 0]));
+    let pair = pairs.iter().find(|pair| pair.peak == vec![1, 1, 0, 1, 1]).unwrap();
+    assert!(pair.left.result == vec![0, 0, 1, 1, 1] && pair.right.result == vec![1, 1, 1, 0, 0]);
+    assert!(pair.right.position == 2);
+    let Confluence::NotJoinable(unjoinable) = peg.confluence(10) else {
+        panic!("1D peg solitaire isn't confluent");
+    };
+    assert!(unjoinable.iter().all(|unjoinable| unjoinable.exhaustive));
+    let peak = unjoinable.iter().find(|unjoinable| unjoinable.pair.peak == vec![1, 1, 0, 1, 1]);
+    let left_derivation = &peak.unwrap().left_derivation;
+    assert!(left_derivation[..2] == [vec![1, 1, 0, 1, 1], vec![0, 0, 1, 1, 1]]);
+    let normal_form = left_derivation.last().unwrap();
+    assert!(peg.rules.keys().all(|lhs| !normal_form.windows(3).any(|window| window == lhs)));
+
+    //Sorting a's behind b's always ends up in the same place
+    let sorting = Ruleset::from_string("a b - b a\n");
+    assert!(sorting.critical_pairs().is_empty());
+    assert!(sorting.confluence(5) == Confluence::Confluent);
+    let joining = Ruleset::from_string("a a - b\na b - b a\nb b - a\n");
+    assert!(!joining.critical_pairs().is_empty());
+    assert!(joining.confluence(5) == Confluence::Confluent);
+    //An lhs inside another, and a rule with two rhs
+    let inside = Ruleset::from_string("a b c - d\nb - e\n");
+    assert!(inside.critical_pairs()[0].peak == vec![0, 1, 2]);
+    assert!(matches!(inside.confluence(5), Confluence::NotJoinable(_)));
+    let choice = Ruleset::from_string("a - b\na - c\n");
+    let Confluence::NotJoinable(unjoinable) = choice.confluence(5) else {
+        panic!("a can become either b or c");
+    };
+    assert!(unjoinable.len() == 1 && unjoinable[0].left_derivation.len() == 2);
+    //Joinable, but it never stops
+    assert!(Ruleset::from_string("a - a a\n").confluence(5) == Confluence::LocallyConfluent);
+    //Joining these takes more steps than it's given
+    let slow = Ruleset::from_string("a - b\nb - c\nc - d\na - d\n");
+    let Confluence::NotJoinable(unjoinable) = slow.confluence(1) else {
+        panic!("not joinable in one step");
+    };
+    assert!(!unjoinable[0].exhaustive);
+    assert!(slow.confluence(2) == Confluence::Confluent);
+    //At the start of the string a can become b or c, but only the unanchored rules are checked
+    let anchored = Ruleset::from_string("^ a - b\na - c\n");
+    assert!(anchored.confluence(5) == Confluence::AnchoredUnchecked);
+    let anchored = Ruleset::from_string("^ d - b\na - b\na - c\n");
+    let Confluence::NotJoinable(unjoinable) = anchored.confluence(5) else {
+        panic!("a can become either b or c");
+    };
+    assert!(!unjoinable[0].exhaustive);
+}
+
+#[test]
+fn completion() {
+    //The Klein four-group, where every string is equivalent to one of a, b, a b and the empty string
+    let klein = Ruleset::from_string("a a - \nb b - \na b a b - \n");
+    let order = ReductionOrder::LengthLex(vec![0, 1]);
+    let completion = klein.complete(order, CompletionLimits::default()).unwrap();
+    let Completion::Complete(complete) = completion else {
+        panic!("the Klein four-group has a finite complete system");
+    };
+    let expected = Ruleset::from_vec(
+        vec![(vec![0, 0], vec![]), (vec![1, 0], vec![0, 1]), (vec![1, 1], vec![])],
+        klein.symbol_set.clone(),
+    );
+    assert!(complete == expected);
+    assert!(complete.confluence(5) == Confluence::Confluent);
+    assert!(complete.normal_form(&[1, 0, 1, 0, 1]) == vec![1]);
+    assert!(complete.normal_form(&[1, 0, 0, 1, 0]) == vec![0]);
+
+    //The normal forms of the completed system can be solved for like any other SRS
+    let goal = DFA {
+        starting_state: 0,
+        state_transitions: vec![vec![1, 1], vec![1, 1]],
+        accepting_states: vec![true, false],
+        symbol_set: complete.symbol_set.clone(),
+    };
+    let strings: Vec<Vec<SymbolIdx>> = complete.symbol_set.sig_set_iter(6).collect();
+    let solver = HashSolver::new(complete, goal).unwrap();
+    let dfa = solver.run(2, vec![]);
+    assert!(solver.is_correct(&dfa));
+    for string in strings {
+        let even = |sym| string.iter().filter(|s| **s == sym).count() % 2 == 0;
+        assert!(dfa.contains(&string) == (even(0) && even(1)));
+    }
+
+    //Weights decide which way a rule goes
+    let split = Ruleset::from_string("a - b b\n");
+    let weighted = ReductionOrder::WeightLex {
+        weights: vec![3, 1],
+        precedence: vec![0, 1],
+    };
+    assert!(weighted.compare(&[0], &[1, 1]) == std::cmp::Ordering::Greater);
+    let completion = split.complete(weighted, CompletionLimits::default()).unwrap();
+    let Completion::Complete(complete) = completion else {
+        panic!("a single rule is already complete");
+    };
+    assert!(complete.rules[&vec![0]] == vec![vec![1, 1]]);
+    let order = ReductionOrder::LengthLex(vec![0, 1]);
+    let completion = split.complete(order, CompletionLimits::default()).unwrap();
+    let Completion::Complete(complete) = completion else {
+        panic!("a single rule is already complete");
+    };
+    assert!(complete.rules[&vec![1, 1]] == vec![vec![0]]);
+    //That's the rule being turned around, which changes what the SRS can reach
+    let order = ReductionOrder::LengthLex(vec![0, 1]);
+    assert!(split.reversed_rules(&order) == vec![(vec![0], vec![1, 1])]);
+    assert!(klein.reversed_rules(&order).is_empty());
+
+    //The positive braid monoid on 3 strands has no finite complete system with a before b
+    let braid = Ruleset::from_string("a b a - b a b\n");
+    let limits = CompletionLimits {
+        max_rules: 5,
+        ..Default::default()
+    };
+    let Completion::Incomplete {
+        rules,
+        pending,
+        limit,
+        steps,
+    } = braid.complete(ReductionOrder::LengthLex(vec![0, 1]), limits).unwrap()
+    else {
+        panic!("braid completion can't finish");
+    };
+    assert!(limit == CompletionLimit::Rules && steps >= 5 && !pending.is_empty());
+    assert!(rules.rules.values().map(|rhs_list| rhs_list.len()).sum::<usize>() == 5);
+    assert!(rules.rules[&vec![1, 0, 1]] == vec![vec![0, 1, 0]]);
+
+    //Orders that don't rank every symbol exactly once are turned down instead of panicking or
+    //letting two different sides tie
+    let limits = CompletionLimits::default();
+    for precedence in [vec![0], vec![0, 2], vec![1, 1], vec![0, 1, 0]] {
+        let order = ReductionOrder::LengthLex(precedence.clone());
+        assert!(order.check(2) == Err(CompletionError::Precedence(precedence.clone())));
+        let result = klein.complete(order, limits);
+        assert!(result == Err(CompletionError::Precedence(precedence)));
+    }
+    let weighted = ReductionOrder::WeightLex {
+        weights: vec![1],
+        precedence: vec![0, 1],
+    };
+    assert!(klein.complete(weighted, limits) == Err(CompletionError::Weights(1)));
+    assert!(ReductionOrder::LengthLex(vec![1, 0]).check(2) == Ok(()));
+
+    //Anchored rules would be dropped from a "complete" system, so they're turned down too
+    let anchored = Ruleset::from_string("^ a - b\nb b - \n");
+    let result = anchored.complete(ReductionOrder::LengthLex(vec![0, 1]), limits);
+    assert!(result == Err(CompletionError::AnchoredRules));
+}